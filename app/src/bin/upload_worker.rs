@@ -0,0 +1,9 @@
+use gloo::worker::Registrable;
+
+// Shared with the app, which spawns the worker.
+#[path = "../upload_worker.rs"]
+mod upload_worker;
+
+fn main() {
+    upload_worker::UploadWorker::registrar().register();
+}
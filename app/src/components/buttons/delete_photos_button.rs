@@ -36,8 +36,8 @@ pub fn delete_photos_button(props: &DeletePhotosButtonProps) -> Html {
     html! {
         <ConfirmButton label="Delete"
             on_click={delete_photos}
-            confirm_dialog_title="Delete photos?"
-            confirm_dialog_body={format!("{n_selected_photos} photos will be deleted.")}
+            confirm_dialog_title="Move photos to trash?"
+            confirm_dialog_body={format!("{n_selected_photos} photos will be moved to the trash, from where they can be restored.")}
             >
             <IconDelete/>
         </ConfirmButton>
@@ -0,0 +1,53 @@
+use crate::{
+    components::{
+        buttons::Button,
+        icons::{IconHeart, IconHeartOutline},
+    },
+    hooks::use_overlay,
+    WASM_CLIENT,
+};
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq)]
+pub struct FavouritePhotosButtonProps {
+    pub photo_ids: Vec<String>,
+    /// Whether to mark the photos as favourite, or unmark them.
+    pub favourite: bool,
+    pub on_changed: Callback<()>,
+}
+
+#[function_component(FavouritePhotosButton)]
+pub fn favourite_photos_button(props: &FavouritePhotosButtonProps) -> Html {
+    let (_, set_overlay) = use_overlay();
+
+    let set_favourite = {
+        let photo_ids = props.photo_ids.clone();
+        let favourite = props.favourite;
+        let on_changed = props.on_changed.clone();
+        move |_| {
+            let photo_ids = photo_ids.clone();
+            let on_changed = on_changed.clone();
+            let set_overlay = set_overlay.clone();
+
+            set_overlay.emit(true);
+
+            wasm_bindgen_futures::spawn_local(async move {
+                WASM_CLIENT.set_favourite(&photo_ids, favourite).await.unwrap();
+                set_overlay.emit(false);
+                on_changed.emit(())
+            });
+        }
+    };
+
+    html! {
+        if props.favourite {
+            <Button label="Favourite" on_click={set_favourite}>
+                <IconHeart/>
+            </Button>
+        } else {
+            <Button label="Unfavourite" on_click={set_favourite}>
+                <IconHeartOutline/>
+            </Button>
+        }
+    }
+}
@@ -8,11 +8,15 @@ pub mod delete_album_button;
 pub mod delete_photos_button;
 pub mod download_photo_button;
 pub mod edit_album_button;
+pub mod favourite_photos_button;
 pub mod open_library_button;
+pub mod photo_details_button;
 pub mod photo_exif_button;
+pub mod rate_photos_button;
 pub mod remove_from_album_button;
 pub mod set_album_cover_button;
 pub mod share_album_button;
+pub mod tag_photos_button;
 pub mod upload_button;
 
 pub use add_to_album_button::*;
@@ -23,11 +27,15 @@ pub use delete_album_button::*;
 pub use delete_photos_button::*;
 pub use download_photo_button::*;
 pub use edit_album_button::*;
+pub use favourite_photos_button::*;
 pub use open_library_button::*;
+pub use photo_details_button::*;
 pub use photo_exif_button::*;
+pub use rate_photos_button::*;
 pub use remove_from_album_button::*;
 pub use set_album_cover_button::*;
 pub use share_album_button::*;
+pub use tag_photos_button::*;
 pub use upload_button::*;
 
 #[derive(PartialEq)]
@@ -0,0 +1,71 @@
+use crate::{
+    components::{buttons::Button, IconTag, PhotoDetails},
+    hooks::use_photo,
+    models::Photo,
+    WASM_CLIENT,
+};
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq)]
+pub struct PhotoDetailsButtonProps {
+    pub photo_id: AttrValue,
+}
+
+#[function_component(PhotoDetailsButton)]
+pub fn photo_details_button(props: &PhotoDetailsButtonProps) -> Html {
+    let photo = use_photo(props.photo_id.as_str());
+    let visible = use_state(|| false);
+    let editable = use_state(|| false);
+
+    {
+        let editable = editable.clone();
+        use_effect_with_deps(
+            move |photo_id| {
+                let photo_id = photo_id.to_string();
+                wasm_bindgen_futures::spawn_local(async move {
+                    let library_photo = WASM_CLIENT.get_library_photo(&photo_id).await.ok().flatten();
+                    editable.set(library_photo.is_some());
+                });
+            },
+            props.photo_id.clone(),
+        );
+    }
+
+    let on_click = {
+        let visible = visible.clone();
+        move |_| {
+            visible.set(!*visible);
+        }
+    };
+
+    let on_saved = {
+        let photo = photo.clone();
+        let visible = visible.clone();
+        Callback::from(move |saved: Photo| {
+            photo.set(Some(saved));
+            visible.set(false);
+        })
+    };
+
+    let Some(current_photo) = (*photo).clone() else {
+        return html! {};
+    };
+    let has_details =
+        !current_photo.caption.is_empty() || !current_photo.description.is_empty() || !current_photo.tags.is_empty();
+    if !*editable && !has_details {
+        return html! {};
+    }
+
+    html! {
+        <>
+            <Button label={"Details"} on_click={on_click}>
+                <IconTag/>
+            </Button>
+            if *visible {
+                <div class="photo-details-wrapper">
+                    <PhotoDetails photo={current_photo} editable={*editable} on_saved={on_saved}/>
+                </div>
+            }
+        </>
+    }
+}
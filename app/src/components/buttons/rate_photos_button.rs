@@ -0,0 +1,79 @@
+use crate::{
+    components::{buttons::Button, dialog::ConfirmDialog, icons::IconStar, RatingInput},
+    hooks::use_overlay,
+    WASM_CLIENT,
+};
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq)]
+pub struct RatePhotosButtonProps {
+    pub photo_ids: Vec<String>,
+    pub on_rated: Callback<()>,
+}
+
+#[function_component(RatePhotosButton)]
+pub fn rate_photos_button(props: &RatePhotosButtonProps) -> Html {
+    let dialog_state = use_state(|| false);
+    let (_, set_overlay) = use_overlay();
+    let rating = use_state(|| 0u8);
+
+    let show_dialog = {
+        let dialog_state = dialog_state.clone();
+        let rating = rating.clone();
+        move |_| {
+            rating.set(0);
+            dialog_state.set(true);
+        }
+    };
+
+    let hide_dialog = {
+        let dialog_state = dialog_state.clone();
+        move |_| {
+            dialog_state.set(false);
+        }
+    };
+
+    let rate_photos = {
+        let photo_ids = props.photo_ids.clone();
+        let on_rated = props.on_rated.clone();
+        let dialog_state = dialog_state.clone();
+        let rating = *rating;
+        move |_| {
+            set_overlay.emit(true);
+
+            let photo_ids = photo_ids.clone();
+            let on_rated = on_rated.clone();
+            let dialog_state = dialog_state.clone();
+            let set_overlay = set_overlay.clone();
+
+            wasm_bindgen_futures::spawn_local(async move {
+                WASM_CLIENT.set_rating(&photo_ids, rating).await.unwrap();
+                dialog_state.set(false);
+                set_overlay.emit(false);
+                on_rated.emit(())
+            });
+        }
+    };
+
+    let on_change = {
+        let rating = rating.clone();
+        Callback::from(move |value: u8| rating.set(value))
+    };
+
+    let dialog_visible = *dialog_state;
+
+    html! {
+        <>
+            <Button label={"Rate"} on_click={show_dialog}>
+                <IconStar/>
+            </Button>
+            <ConfirmDialog
+                    visible={dialog_visible}
+                    title="Rate photos"
+                    confirm_action={rate_photos}
+                    cancel_action={hide_dialog}>
+                <RatingInput rating={*rating} on_change={on_change}/>
+            </ConfirmDialog>
+        </>
+    }
+}
@@ -0,0 +1,81 @@
+use crate::{
+    components::{buttons::Button, dialog::ConfirmDialog, IconTag},
+    hooks::use_overlay,
+    WASM_CLIENT,
+};
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq)]
+pub struct TagPhotosButtonProps {
+    pub photo_ids: Vec<String>,
+    pub on_tagged: Callback<()>,
+}
+
+#[function_component(TagPhotosButton)]
+pub fn tag_photos_button(props: &TagPhotosButtonProps) -> Html {
+    let dialog_state = use_state(|| false);
+    let (_, set_overlay) = use_overlay();
+    let tags_ref = use_node_ref();
+
+    let show_dialog = {
+        let dialog_state = dialog_state.clone();
+        move |_| {
+            dialog_state.set(true);
+        }
+    };
+
+    let hide_dialog = {
+        let dialog_state = dialog_state.clone();
+        move |_| {
+            dialog_state.set(false);
+        }
+    };
+
+    let tag_photos = {
+        let photo_ids = props.photo_ids.clone();
+        let on_tagged = props.on_tagged.clone();
+        let dialog_state = dialog_state.clone();
+        let tags_ref = tags_ref.clone();
+
+        move |_| {
+            let Some(tags_input) = tags_ref.cast::<HtmlInputElement>() else {
+                return;
+            };
+            let tags: Vec<String> = tags_input.value().split(',').map(String::from).collect();
+
+            set_overlay.emit(true);
+
+            let photo_ids = photo_ids.clone();
+            let on_tagged = on_tagged.clone();
+            let dialog_state = dialog_state.clone();
+            let set_overlay = set_overlay.clone();
+
+            wasm_bindgen_futures::spawn_local(async move {
+                WASM_CLIENT.add_tags(&photo_ids, tags).await.unwrap();
+                dialog_state.set(false);
+                set_overlay.emit(false);
+                on_tagged.emit(())
+            });
+        }
+    };
+
+    let dialog_visible = *dialog_state;
+
+    html! {
+        <>
+            <Button label={"Tag"} on_click={show_dialog}>
+                <IconTag/>
+            </Button>
+            <ConfirmDialog
+                    visible={dialog_visible}
+                    title="Tag photos"
+                    confirm_action={tag_photos}
+                    cancel_action={hide_dialog}>
+                <label>{"Tags to add, separated by commas"}
+                    <input ref={tags_ref} type="text"/>
+                </label>
+            </ConfirmDialog>
+        </>
+    }
+}
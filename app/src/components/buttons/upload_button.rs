@@ -1,9 +1,11 @@
 use crate::{
     components::{buttons::Button, IconUpload},
     models::{UploadQueue, UploadQueueAction},
+    upload_store::{self, SelectedFile},
 };
 use bounce::use_slice;
 use web_sys::HtmlInputElement;
+use weblog::console_error;
 use yew::prelude::*;
 
 #[derive(Properties, PartialEq)]
@@ -16,8 +18,22 @@ pub fn upload_button(_: &UploadButtonProps) -> Html {
 
     let on_click = {
         let input_ref = input_ref.clone();
+        let slice = slice.clone();
         move |_| {
-            if let Some(input_ref) = input_ref.cast::<HtmlInputElement>() {
+            // Prefer the browser's file picker, which provides file system handles,
+            // so uploads can continue after a page reload.
+            if upload_store::supports_file_picker() {
+                let slice = slice.clone();
+                wasm_bindgen_futures::spawn_local(async move {
+                    match upload_store::pick_files().await {
+                        Ok(files) => slice.dispatch(UploadQueueAction::AddToQueue {
+                            files,
+                            target_album_id: None,
+                        }),
+                        Err(error) => console_error!(format!("{error:?}")),
+                    }
+                });
+            } else if let Some(input_ref) = input_ref.cast::<HtmlInputElement>() {
                 input_ref.click();
             }
         }
@@ -28,8 +44,12 @@ pub fn upload_button(_: &UploadButtonProps) -> Html {
         move |_| {
             if let Some(input_ref) = input_ref.cast::<HtmlInputElement>() {
                 if let Some(filelist) = input_ref.files() {
+                    let files = (0..filelist.length())
+                        .filter_map(|i| filelist.get(i))
+                        .map(|file| SelectedFile { file, handle: None })
+                        .collect();
                     slice.dispatch(UploadQueueAction::AddToQueue {
-                        filelist,
+                        files,
                         target_album_id: None,
                     });
                 }
@@ -38,7 +58,7 @@ pub fn upload_button(_: &UploadButtonProps) -> Html {
     };
 
     html! {
-        <label>
+        <>
             <Button label={"Upload"} {on_click}>
                 <IconUpload/>
             </Button>
@@ -49,6 +69,6 @@ pub fn upload_button(_: &UploadButtonProps) -> Html {
                 accept=".jpg,.jpeg"
                 onchange={on_change}
                 multiple={true} />
-        </label>
+        </>
     }
 }
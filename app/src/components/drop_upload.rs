@@ -1,18 +1,30 @@
 use crate::{
     components::IconUpload,
     models::{UploadQueue, UploadQueueAction},
+    upload_store,
 };
 use bounce::use_slice;
 use std::fmt::Display;
+use weblog::console_error;
 use yew::prelude::*;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum FileUploadStatus {
     Queued,
+    /// Being processed and encrypted by an upload worker.
+    Processing,
     Busy,
-    Done { photo_id: String },
+    Done {
+        photo_id: String,
+    },
     Failed,
-    Exists { photo_id: String },
+    Exists {
+        photo_id: String,
+    },
+    /// Restored after a page reload; the file has to be selected again.
+    NeedsFile,
+    /// Restored after a page reload; the user has to grant access to the file again.
+    NeedsPermission,
 }
 
 impl Display for FileUploadStatus {
@@ -20,6 +32,8 @@ impl Display for FileUploadStatus {
         match self {
             Self::Done { .. } => write!(f, "Done"),
             Self::Exists { .. } => write!(f, "Exists"),
+            Self::NeedsFile => write!(f, "Select again"),
+            Self::NeedsPermission => write!(f, "Needs access"),
             _ => write!(f, "{:?}", self),
         }
     }
@@ -55,12 +69,16 @@ pub fn drop_upload(props: &DropUploadProps) -> Html {
                 event.prevent_default();
 
                 if let Some(data_transfer) = event.data_transfer() {
-                    if let Some(filelist) = data_transfer.files() {
-                        slice.dispatch(UploadQueueAction::AddToQueue {
-                            filelist,
-                            target_album_id: target_album_id.clone().map(|v| v.to_string()),
-                        });
-                    }
+                    // Start getting the files while handling the event; the browser clears them afterwards.
+                    let files = upload_store::get_dropped_files(&data_transfer);
+                    let slice = slice.clone();
+                    let target_album_id = target_album_id.clone().map(|v| v.to_string());
+                    wasm_bindgen_futures::spawn_local(async move {
+                        match files.await {
+                            Ok(files) => slice.dispatch(UploadQueueAction::AddToQueue { files, target_album_id }),
+                            Err(error) => console_error!(format!("{error:?}")),
+                        }
+                    });
                 }
             },
             hovering.clone(),
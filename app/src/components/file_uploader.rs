@@ -1,86 +1,160 @@
 use crate::{
     components::{FileUploadStatus, UploadProgress},
-    models::{UploadQueue, UploadQueueAction, UploadQueueItem},
-    WASM_CLIENT,
+    models::{AuthStatus, UploadQueue, UploadQueueAction, UploadQueueItem},
+    upload_pool::UploadWorkerPool,
+    upload_store, WASM_CLIENT,
 };
-use bounce::use_slice;
+use anyhow::{anyhow, Result};
+use bounce::{use_atom_value, use_slice, UseSliceHandle};
+use futures::lock::Mutex;
+use gloo::timers::callback::Timeout;
 use js_sys::Uint8Array;
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
+use upholi_core::client::{PhotoUploadResult, UploadBatch};
+use upholi_core::hashing;
 use weblog::console_error;
 use yew::prelude::*;
 
+/// Commit the current batch once it holds this many photos, so they show up in the library before the whole queue is done.
+const MAX_BATCH_SIZE: usize = 25;
+/// Stop retrying a failed upload after this many attempts.
+const MAX_ATTEMPTS: u32 = 5;
+/// Delay before retrying a failed upload for the first time. Doubles with each attempt.
+const RETRY_DELAY_MS: u32 = 5_000;
+
+/// State shared by all files being uploaded. Files are processed concurrently,
+/// but uploaded one at a time, as part of the same batch.
+#[derive(Default)]
+struct UploadSession {
+    batch: Mutex<Option<UploadBatch>>,
+    /// IDs of the queue items being uploaded, including those waiting for a worker.
+    in_progress: RefCell<HashSet<String>>,
+    /// Items uploaded in the current batch, with the status to set once the batch is committed.
+    uploaded: RefCell<Vec<(UploadQueueItem, FileUploadStatus)>>,
+}
+
 #[function_component(FileUploader)]
 pub fn file_uploader() -> Html {
-    let processing = use_state(Vec::<String>::new);
     let slice_state = use_slice::<UploadQueue>();
+    let pool = use_memo(|_| Rc::new(UploadWorkerPool::new()), ());
+    let session = use_memo(|_| Rc::new(UploadSession::default()), ());
+    let auth_status = use_atom_value::<AuthStatus>();
+    // Queue items stored in IndexedDB, and whether they were stored with a file handle.
+    let stored = use_mut_ref(HashMap::<String, bool>::new);
 
+    // The queue belongs to the logged in user.
     {
         let slice = slice_state.clone();
+        use_effect_with_deps(
+            move |auth_status| match **auth_status {
+                AuthStatus::Authenticated => wasm_bindgen_futures::spawn_local(async move {
+                    match WASM_CLIENT.get_user_id().await {
+                        Ok(user_id) => slice.dispatch(UploadQueueAction::SetUser { user_id: Some(user_id) }),
+                        Err(error) => console_error!(format!("{error:?}")),
+                    }
+                }),
+                AuthStatus::Unauthenticated => slice.dispatch(UploadQueueAction::SetUser { user_id: None }),
+                AuthStatus::Fetching => {}
+            },
+            auth_status,
+        );
+    }
 
+    // Restore the queue the user left on a previous visit.
+    {
+        let slice = slice_state.clone();
         use_effect_with_deps(
-            move |_| {
-                if !slice.queue.is_empty() {
-                    let mut currently_processing = (*processing).clone();
-                    let batch: Vec<UploadQueueItem> = slice
-                        .queue
-                        .clone()
-                        .into_iter()
-                        .filter(|item| !currently_processing.contains(&item.filename))
-                        .collect();
-                    currently_processing.extend(batch.iter().map(|item| item.filename.clone()));
-                    processing.set(currently_processing);
-
-                    let set_status = {
-                        move |file_name: &str, status: FileUploadStatus| {
-                            slice.dispatch(UploadQueueAction::UpdateItemState {
-                                file_name: file_name.to_string(),
-                                status,
-                            });
-                        }
-                    };
-
-                    if !batch.is_empty() {
-                        wasm_bindgen_futures::spawn_local(async move {
-                            for queue_item in batch {
-                                set_status(&queue_item.filename, FileUploadStatus::Busy);
-
-                                let promise = queue_item.file.array_buffer();
-                                let js_value = wasm_bindgen_futures::JsFuture::from(promise).await.unwrap();
-                                let array = Uint8Array::new(&js_value);
-                                let bytes: Vec<u8> = array.to_vec();
-
-                                match WASM_CLIENT.upload_photo(&bytes).await {
-                                    Ok(upload_result) => {
-                                        if let Some(album_id) = queue_item.target_album_id {
-                                            let result = WASM_CLIENT
-                                                .add_photos_to_album(&album_id, &[upload_result.photo_id.clone()])
-                                                .await;
-                                            if let Err(error) = result {
-                                                console_error!(format!("{error}"));
-                                            }
-                                        }
-
-                                        set_status(
-                                            &queue_item.filename,
-                                            if upload_result.skipped {
-                                                FileUploadStatus::Exists {
-                                                    photo_id: upload_result.photo_id,
-                                                }
-                                            } else {
-                                                FileUploadStatus::Done {
-                                                    photo_id: upload_result.photo_id,
-                                                }
-                                            },
-                                        );
-                                    }
-                                    Err(error) => {
-                                        set_status(&queue_item.filename, FileUploadStatus::Failed);
-                                        weblog::console_error!(format!("{error:?}"));
-                                    }
-                                }
+            move |user_id| {
+                if let Some(user_id) = user_id.clone() {
+                    wasm_bindgen_futures::spawn_local(async move {
+                        match restore_queue(&user_id).await {
+                            Ok(items) if !items.is_empty() => {
+                                slice.dispatch(UploadQueueAction::Restore { user_id, items })
                             }
-                        });
+                            Ok(_) => {}
+                            Err(error) => console_error!(format!("{error:?}")),
+                        }
+                    });
+                }
+            },
+            slice_state.user_id.clone(),
+        );
+    }
+
+    // Keep the stored queue up to date.
+    use_effect_with_deps(
+        move |(queue, user_id)| {
+            let Some(user_id) = user_id.clone() else {
+                return;
+            };
+            let mut stored = stored.borrow_mut();
+            let mut items_to_save = vec![];
+            let mut ids_to_delete = vec![];
+            for item in queue.iter() {
+                if item.is_completed() {
+                    if stored.remove(&item.id).is_some() {
+                        ids_to_delete.push(item.id.clone());
+                    }
+                } else if stored.get(&item.id) != Some(&item.handle.is_some()) {
+                    stored.insert(item.id.clone(), item.handle.is_some());
+                    items_to_save.push(item.clone());
+                }
+            }
+
+            // Items removed from the queue, such as dismissed failed items, or the items of a previous user.
+            let removed_ids: Vec<String> = stored
+                .keys()
+                .filter(|id| !queue.iter().any(|item| &item.id == *id))
+                .cloned()
+                .collect();
+            for id in removed_ids {
+                stored.remove(&id);
+                ids_to_delete.push(id);
+            }
+
+            wasm_bindgen_futures::spawn_local(async move {
+                for item in items_to_save {
+                    if let Err(error) = upload_store::save(&user_id, &item).await {
+                        console_error!(format!("{error:?}"));
                     }
                 }
+                for id in ids_to_delete {
+                    if let Err(error) = upload_store::delete(&id).await {
+                        console_error!(format!("{error:?}"));
+                    }
+                }
+            });
+        },
+        (slice_state.queue.clone(), slice_state.user_id.clone()),
+    );
+
+    {
+        let slice = slice_state.clone();
+
+        use_effect_with_deps(
+            move |_| {
+                let batch: Vec<UploadQueueItem> = slice
+                    .queue
+                    .iter()
+                    .filter(|item| item.status == FileUploadStatus::Queued && item.file.is_some())
+                    .filter(|item| session.in_progress.borrow_mut().insert(item.id.clone()))
+                    .cloned()
+                    .collect();
+
+                for queue_item in batch {
+                    let slice = slice.clone();
+                    let pool = (*pool).clone();
+                    let session = (*session).clone();
+
+                    wasm_bindgen_futures::spawn_local(async move {
+                        let result = upload(&slice, &pool, &session, &queue_item).await;
+                        finish_upload(&slice, &session, queue_item, result).await;
+                    });
+                }
             },
             slice_state.queue.clone(),
         );
@@ -90,3 +164,132 @@ pub fn file_uploader() -> Html {
         <UploadProgress/>
     }
 }
+
+/// Get the stored queue items of given user. Their files are available right away only if access to them is still granted.
+async fn restore_queue(user_id: &str) -> Result<Vec<UploadQueueItem>> {
+    let mut items = vec![];
+    for stored_upload in upload_store::load(user_id).await? {
+        let handle = stored_upload.handle.clone();
+        let mut item = UploadQueueItem::from(stored_upload);
+        if let Some(handle) = handle {
+            if let Some(file) = upload_store::get_file(&handle, false).await? {
+                item.set_file(file);
+            }
+        }
+        items.push(item);
+    }
+
+    Ok(items)
+}
+
+/// Process and encrypt a file in a worker once one is available, then upload it as part of the current batch.
+/// Files already in the library are recognized by their hash first, and skipped without processing them.
+async fn upload(
+    slice: &UseSliceHandle<UploadQueue>,
+    pool: &Rc<UploadWorkerPool>,
+    session: &UploadSession,
+    queue_item: &UploadQueueItem,
+) -> Result<PhotoUploadResult> {
+    let file = queue_item.file.as_ref().ok_or_else(|| anyhow!("File not available"))?;
+    let worker = pool.acquire().await?;
+    set_status(slice, &queue_item.id, FileUploadStatus::Processing);
+
+    let promise = file.array_buffer();
+    let js_value = wasm_bindgen_futures::JsFuture::from(promise)
+        .await
+        .map_err(|error| anyhow!("Error reading file: {error:?}"))?;
+    let bytes: Vec<u8> = Uint8Array::new(&js_value).to_vec();
+
+    let photo_hash = hashing::compute_sha256_hash(&bytes)?;
+    if let Some(photo_id) = WASM_CLIENT.find_photo_by_hash(&photo_hash).await? {
+        return Ok(PhotoUploadResult {
+            skipped: true,
+            photo_id,
+        });
+    }
+
+    let mut encrypted_photo = worker.encrypt_photo(bytes).await?;
+    encrypted_photo.photo.file_name = file.name();
+
+    // Keep the worker until the upload can start, so no more photos are processed than can be uploaded.
+    let mut batch = session.batch.lock().await;
+    drop(worker);
+    set_status(slice, &queue_item.id, FileUploadStatus::Busy);
+
+    let batch = match &mut *batch {
+        Some(batch) => batch,
+        None => batch.insert(WASM_CLIENT.begin_upload_batch().await?),
+    };
+    WASM_CLIENT
+        .upload_photo_in_batch(batch, encrypted_photo, queue_item.target_album_id.as_deref())
+        .await
+}
+
+/// Record the result of an upload, and commit the batch when it is full or when no more files are in progress.
+async fn finish_upload(
+    slice: &UseSliceHandle<UploadQueue>,
+    session: &UploadSession,
+    queue_item: UploadQueueItem,
+    result: Result<PhotoUploadResult>,
+) {
+    let mut batch = session.batch.lock().await;
+    session.in_progress.borrow_mut().remove(&queue_item.id);
+
+    match result {
+        Ok(upload_result) => {
+            let status = if upload_result.skipped {
+                FileUploadStatus::Exists {
+                    photo_id: upload_result.photo_id,
+                }
+            } else {
+                FileUploadStatus::Done {
+                    photo_id: upload_result.photo_id,
+                }
+            };
+            session.uploaded.borrow_mut().push((queue_item, status));
+        }
+        Err(error) => {
+            console_error!(format!("{error:?}"));
+            fail(slice, &queue_item);
+        }
+    }
+
+    let batch_is_full = batch.as_ref().is_some_and(|batch| batch.len() >= MAX_BATCH_SIZE);
+    if session.in_progress.borrow().is_empty() || batch_is_full {
+        let committed = match batch.take() {
+            Some(batch) => WASM_CLIENT.commit_upload_batch(batch).await,
+            None => Ok(()),
+        };
+        if let Err(error) = &committed {
+            console_error!(format!("{error:?}"));
+        }
+
+        for (queue_item, status) in session.uploaded.take() {
+            match committed {
+                Ok(_) => set_status(slice, &queue_item.id, status),
+                Err(_) => fail(slice, &queue_item),
+            }
+        }
+    }
+}
+
+/// Mark an item as failed, and retry it later unless it failed too often.
+fn fail(slice: &UseSliceHandle<UploadQueue>, queue_item: &UploadQueueItem) {
+    slice.dispatch(UploadQueueAction::Failed {
+        id: queue_item.id.clone(),
+    });
+
+    if queue_item.attempts + 1 < MAX_ATTEMPTS {
+        let slice = slice.clone();
+        let id = queue_item.id.clone();
+        let delay = RETRY_DELAY_MS * 2u32.pow(queue_item.attempts);
+        Timeout::new(delay, move || set_status(&slice, &id, FileUploadStatus::Queued)).forget();
+    }
+}
+
+fn set_status(slice: &UseSliceHandle<UploadQueue>, id: &str, status: FileUploadStatus) {
+    slice.dispatch(UploadQueueAction::UpdateItemState {
+        id: id.to_string(),
+        status,
+    });
+}
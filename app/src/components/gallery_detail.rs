@@ -1,6 +1,7 @@
 use crate::{
     components::{
-        Button, DownloadPhotoButton, IconChevronLeft, IconChevronRight, IconClose, PhotoExifButton, PhotoPreview,
+        Button, DownloadPhotoButton, IconChevronLeft, IconChevronRight, IconClose, PhotoDetailsButton, PhotoExifButton,
+        PhotoPreview, PhotoRating,
     },
     models::AlbumPhoto,
     RouteQuery,
@@ -99,6 +100,8 @@ pub fn gallery_detail(props: &GalleryDetailProps) -> Html {
                 <div class="header">
                     <DownloadPhotoButton photo_id={&props.photo_id}/>
                     <PhotoExifButton photo_id={&props.photo_id}/>
+                    <PhotoDetailsButton photo_id={&props.photo_id}/>
+                    <PhotoRating photo_id={&props.photo_id}/>
                     <div class="spacing"/>
                     {close_button}
                 </div>
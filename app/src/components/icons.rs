@@ -128,3 +128,28 @@ pub fn icon_check() -> Html {
 pub fn icon_info() -> Html {
     html! { <Icon svg_path_d={"M11,9H13V7H11M12,20C7.59,20 4,16.41 4,12C4,7.59 7.59,4 12,4C16.41,4 20,7.59 20,12C20,16.41 16.41,20 12,20M12,2A10,10 0 0,0 2,12A10,10 0 0,0 12,22A10,10 0 0,0 22,12A10,10 0 0,0 12,2M11,17H13V11H11V17Z".to_string()}/>}
 }
+
+#[function_component(IconHeart)]
+pub fn icon_heart() -> Html {
+    html! { <Icon svg_path_d={"M12,21.35L10.55,20.03C5.4,15.36 2,12.27 2,8.5C2,5.41 4.42,3 7.5,3C9.24,3 10.91,3.81 12,4.95C13.09,3.81 14.76,3 16.5,3C19.58,3 22,5.41 22,8.5C22,12.27 18.6,15.36 13.45,20.03L12,21.35Z".to_string()}/>}
+}
+
+#[function_component(IconHeartOutline)]
+pub fn icon_heartoutline() -> Html {
+    html! { <Icon svg_path_d={"M12.1,18.55L12,18.65L11.89,18.55C7.14,14.24 4,11.39 4,8.5C4,6.5 5.5,5 7.5,5C9.04,5 10.54,6 11.07,7.36H12.93C13.46,6 14.96,5 16.5,5C18.5,5 20,6.5 20,8.5C20,11.39 16.86,14.24 12.1,18.55M16.5,3C14.76,3 13.09,3.81 12,5.08C10.91,3.81 9.24,3 7.5,3C4.42,3 2,5.41 2,8.5C2,12.27 5.4,15.36 10.55,20.03L12,21.35L13.45,20.03C18.6,15.36 22,12.27 22,8.5C22,5.41 19.58,3 16.5,3Z".to_string()}/>}
+}
+
+#[function_component(IconStar)]
+pub fn icon_star() -> Html {
+    html! { <Icon svg_path_d={"M12,17.27L18.18,21L16.54,13.64L22,8.27L14.81,7.65L12,1L9.19,7.65L2,8.27L7.45,13.64L5.82,21L12,17.27Z".to_string()}/>}
+}
+
+#[function_component(IconStarOutline)]
+pub fn icon_staroutline() -> Html {
+    html! { <Icon svg_path_d={"M12,15.39L8.24,17.66L9.23,13.38L5.91,10.5L10.29,10.13L12,6.09L13.71,10.13L18.09,10.5L14.77,13.38L15.76,17.66M22,9.24L14.81,8.63L12,2L9.19,8.63L2,9.24L7.45,13.97L5.82,21L12,17.27L18.18,21L16.54,13.97L22,9.24Z".to_string()}/>}
+}
+
+#[function_component(IconTag)]
+pub fn icon_tag() -> Html {
+    html! { <Icon svg_path_d={"M5.5,7A1.5,1.5 0 0,0 7,5.5A1.5,1.5 0 0,0 5.5,4A1.5,1.5 0 0,0 4,5.5A1.5,1.5 0 0,0 5.5,7M21.41,11.58C21.77,11.94 22,12.44 22,13C22,13.55 21.78,14.05 21.41,14.41L14.41,21.41C14.05,21.77 13.55,22 13,22C12.45,22 11.95,21.77 11.58,21.41L2.59,12.41C2.22,12.05 2,11.55 2,11V4C2,2.89 2.89,2 4,2H11C11.55,2 12.05,2.22 12.41,2.58L21.41,11.58M13,20L20,13L11.5,4.5L4.5,11.5L13,20Z".to_string()}/>}
+}
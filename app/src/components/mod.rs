@@ -10,12 +10,18 @@ pub mod gallery_photo;
 pub mod icons;
 pub mod layouts;
 pub mod overlay;
+pub mod photo_details;
 pub mod photo_exif;
 pub mod photo_placeholder;
 pub mod photo_preview;
+pub mod photo_rating;
 pub mod pick_album;
+pub mod rating_input;
 pub mod require_auth;
+pub mod search_bar;
 pub mod share_url;
+pub mod storage_usage;
+pub mod tag_filter;
 pub mod upload_progress;
 
 pub use album_thumb::*;
@@ -30,10 +36,16 @@ pub use gallery_photo::*;
 pub use icons::*;
 pub use layouts::*;
 pub use overlay::*;
+pub use photo_details::*;
 pub use photo_exif::*;
 pub use photo_placeholder::*;
 pub use photo_preview::*;
+pub use photo_rating::*;
 pub use pick_album::*;
+pub use rating_input::*;
 pub use require_auth::*;
+pub use search_bar::*;
 pub use share_url::*;
+pub use storage_usage::*;
+pub use tag_filter::*;
 pub use upload_progress::*;
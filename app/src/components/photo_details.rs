@@ -0,0 +1,90 @@
+use crate::{components::buttons::Button, models::Photo, WASM_CLIENT};
+use upholi_core::models::normalize_tags;
+use web_sys::{HtmlInputElement, HtmlTextAreaElement};
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq)]
+pub struct PhotoDetailsProps {
+    pub photo: Photo,
+    /// Whether the details can be edited, which is the case for photos in the library.
+    pub editable: bool,
+    pub on_saved: Callback<Photo>,
+}
+
+/// Caption, description and tags of a photo.
+#[function_component(PhotoDetails)]
+pub fn photo_details(props: &PhotoDetailsProps) -> Html {
+    let caption_ref = use_node_ref();
+    let description_ref = use_node_ref();
+    let tags_ref = use_node_ref();
+
+    if !props.editable {
+        return html! {
+            <div class="photo-details">
+                if !props.photo.caption.is_empty() {
+                    <div class="caption">{&props.photo.caption}</div>
+                }
+                if !props.photo.description.is_empty() {
+                    <div class="description">{&props.photo.description}</div>
+                }
+                if !props.photo.tags.is_empty() {
+                    <div class="tags">
+                        {props.photo.tags.iter().map(|tag| html! { <span class="tag">{tag}</span> }).collect::<Html>()}
+                    </div>
+                }
+            </div>
+        };
+    }
+
+    let save = {
+        let photo = props.photo.clone();
+        let on_saved = props.on_saved.clone();
+        let caption_ref = caption_ref.clone();
+        let description_ref = description_ref.clone();
+        let tags_ref = tags_ref.clone();
+
+        move |_| {
+            let (Some(caption), Some(description), Some(tags)) = (
+                caption_ref.cast::<HtmlInputElement>(),
+                description_ref.cast::<HtmlTextAreaElement>(),
+                tags_ref.cast::<HtmlInputElement>(),
+            ) else {
+                return;
+            };
+
+            let caption = caption.value();
+            let description = description.value();
+            let tags = normalize_tags(tags.value().split(',').map(String::from));
+            let photo = photo.clone();
+            let on_saved = on_saved.clone();
+
+            wasm_bindgen_futures::spawn_local(async move {
+                WASM_CLIENT
+                    .update_photo_details(&photo.id, &caption, &description, tags.clone())
+                    .await
+                    .unwrap();
+                on_saved.emit(Photo {
+                    caption: caption.trim().to_string(),
+                    description: description.trim().to_string(),
+                    tags,
+                    ..photo
+                });
+            });
+        }
+    };
+
+    html! {
+        <div class="photo-details">
+            <label>{"Caption"}
+                <input ref={caption_ref} type="text" value={props.photo.caption.clone()}/>
+            </label>
+            <label>{"Description"}
+                <textarea ref={description_ref} value={props.photo.description.clone()}/>
+            </label>
+            <label>{"Tags, separated by commas"}
+                <input ref={tags_ref} type="text" value={props.photo.tags.join(", ")}/>
+            </label>
+            <Button label="Save" on_click={save}/>
+        </div>
+    }
+}
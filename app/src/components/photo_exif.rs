@@ -1,4 +1,4 @@
-use crate::exif::Exif;
+use upholi_core::exif::Exif;
 use yew::prelude::*;
 
 #[derive(Properties, PartialEq)]
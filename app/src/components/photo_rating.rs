@@ -0,0 +1,86 @@
+use crate::{
+    components::{
+        buttons::Button,
+        icons::{IconHeart, IconHeartOutline},
+        RatingInput,
+    },
+    models::LibraryPhoto,
+    WASM_CLIENT,
+};
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq)]
+pub struct PhotoRatingProps {
+    pub photo_id: AttrValue,
+}
+
+/// Favourite toggle and rating of a photo in the library.
+/// Shows nothing for photos that are not in the library, such as photos in a share.
+#[function_component(PhotoRating)]
+pub fn photo_rating(props: &PhotoRatingProps) -> Html {
+    let photo: UseStateHandle<Option<LibraryPhoto>> = use_state(|| None);
+
+    {
+        let photo = photo.clone();
+        use_effect_with_deps(
+            move |photo_id| {
+                let photo_id = photo_id.to_string();
+                wasm_bindgen_futures::spawn_local(async move {
+                    let library_photo = WASM_CLIENT.get_library_photo(&photo_id).await.ok().flatten();
+                    photo.set(library_photo);
+                });
+            },
+            props.photo_id.clone(),
+        );
+    }
+
+    let library_photo = match (*photo).clone() {
+        Some(library_photo) => library_photo,
+        None => return html! {},
+    };
+
+    let toggle_favourite = {
+        let photo = photo.clone();
+        let library_photo = library_photo.clone();
+        move |_| {
+            let favourite = !library_photo.favourite;
+            let id = library_photo.id.clone();
+            photo.set(Some(LibraryPhoto {
+                favourite,
+                ..library_photo.clone()
+            }));
+
+            wasm_bindgen_futures::spawn_local(async move {
+                WASM_CLIENT.set_favourite(&[id], favourite).await.unwrap();
+            });
+        }
+    };
+
+    let set_rating = {
+        let library_photo = library_photo.clone();
+        Callback::from(move |rating: u8| {
+            let id = library_photo.id.clone();
+            photo.set(Some(LibraryPhoto {
+                rating,
+                ..library_photo.clone()
+            }));
+
+            wasm_bindgen_futures::spawn_local(async move {
+                WASM_CLIENT.set_rating(&[id], rating).await.unwrap();
+            });
+        })
+    };
+
+    html! {
+        <div class="photo-rating">
+            <Button label="" on_click={toggle_favourite}>
+                if library_photo.favourite {
+                    <IconHeart/>
+                } else {
+                    <IconHeartOutline/>
+                }
+            </Button>
+            <RatingInput rating={library_photo.rating} on_change={set_rating}/>
+        </div>
+    }
+}
@@ -0,0 +1,36 @@
+use crate::components::{
+    buttons::Button,
+    icons::{IconStar, IconStarOutline},
+};
+use upholi_core::client::MAX_RATING;
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq)]
+pub struct RatingInputProps {
+    /// Number of stars, or 0 if not rated.
+    pub rating: u8,
+    pub on_change: Callback<u8>,
+}
+
+/// A row of stars to choose a rating with. Choosing the current rating again clears it.
+#[function_component(RatingInput)]
+pub fn rating_input(props: &RatingInputProps) -> Html {
+    html! {
+        <div class="rating-input">
+            {(1..=MAX_RATING).map(|stars| {
+                let on_change = props.on_change.clone();
+                let rating = if stars == props.rating { 0 } else { stars };
+
+                html! {
+                    <Button label="" on_click={move |_| on_change.emit(rating)}>
+                        if stars <= props.rating {
+                            <IconStar/>
+                        } else {
+                            <IconStarOutline/>
+                        }
+                    </Button>
+                }
+            }).collect::<Html>()}
+        </div>
+    }
+}
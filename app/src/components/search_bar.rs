@@ -0,0 +1,30 @@
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq)]
+pub struct SearchBarProps {
+    pub on_search: Callback<String>,
+}
+
+/// Text input to search with, submitted by pressing enter.
+#[function_component(SearchBar)]
+pub fn search_bar(props: &SearchBarProps) -> Html {
+    let query_ref = use_node_ref();
+
+    let on_submit = {
+        let on_search = props.on_search.clone();
+        let query_ref = query_ref.clone();
+        move |event: SubmitEvent| {
+            event.prevent_default();
+            if let Some(query_input) = query_ref.cast::<HtmlInputElement>() {
+                on_search.emit(query_input.value());
+            }
+        }
+    };
+
+    html! {
+        <form class="search-bar" onsubmit={on_submit}>
+            <input ref={query_ref} type="search" placeholder="camera:Pixel year:2023 tag:beach"/>
+        </form>
+    }
+}
@@ -0,0 +1,51 @@
+use upholi_lib::http::response::UsageResult;
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq)]
+pub struct StorageUsageProps {
+    pub usage: UsageResult,
+}
+
+#[function_component(StorageUsage)]
+pub fn storage_usage(props: &StorageUsageProps) -> Html {
+    let used = format_bytes(props.usage.bytes_used);
+
+    match props.usage.bytes_quota {
+        Some(bytes_quota) => {
+            let percentage = f64::min(props.usage.bytes_used as f64 / bytes_quota as f64, 1.) * 100.;
+            let quota = format_bytes(bytes_quota);
+
+            html! {
+                <div class="storage-usage">
+                    <span>{format!("{used} of {quota} used")}</span>
+                    <div class="bar">
+                        <div class="bar-used" style={format!("width: {percentage}%;")}/>
+                    </div>
+                </div>
+            }
+        }
+        None => html! {
+            <div class="storage-usage">
+                <span>{format!("{used} used")}</span>
+            </div>
+        },
+    }
+}
+
+/// Format a number of bytes for display, e.g. "1.5 GB".
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1000. && unit < UNITS.len() - 1 {
+        value /= 1000.;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{bytes} {}", UNITS[unit])
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
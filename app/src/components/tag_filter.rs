@@ -0,0 +1,30 @@
+use web_sys::HtmlSelectElement;
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq)]
+pub struct TagFilterProps {
+    pub tags: Vec<String>,
+    pub selected: Option<String>,
+    pub on_change: Callback<Option<String>>,
+}
+
+/// Select a tag to show only the photos that have it.
+#[function_component(TagFilter)]
+pub fn tag_filter(props: &TagFilterProps) -> Html {
+    let on_change = {
+        let on_change = props.on_change.clone();
+        move |event: Event| {
+            let value = event.target_unchecked_into::<HtmlSelectElement>().value();
+            on_change.emit(if value.is_empty() { None } else { Some(value) });
+        }
+    };
+
+    html! {
+        <select class="tag-filter" onchange={on_change}>
+            <option value="" selected={props.selected.is_none()}>{"All tags"}</option>
+            {props.tags.iter().map(|tag| html! {
+                <option value={tag.clone()} selected={props.selected.as_ref() == Some(tag)}>{tag}</option>
+            }).collect::<Html>()}
+        </select>
+    }
+}
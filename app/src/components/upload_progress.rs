@@ -2,8 +2,10 @@ use super::FileUploadStatus;
 use crate::{
     components::{Button, IconClose},
     models::{UploadQueue, UploadQueueAction},
+    upload_store,
 };
 use bounce::use_slice;
+use weblog::console_error;
 use yew::prelude::*;
 
 #[derive(Properties, PartialEq)]
@@ -21,7 +23,7 @@ pub fn upload_progress(_: &UploadProgressProps) -> Html {
                 <UploadProgressItem
                     filename={queue_item.filename.clone()}
                     status={queue_item.status.clone()}
-                    object_url={queue_item.object_url.clone()}
+                    object_url={queue_item.object_url.clone().map(AttrValue::from)}
                 />
             }
         })
@@ -34,7 +36,29 @@ pub fn upload_progress(_: &UploadProgressProps) -> Html {
         }
     };
 
+    // Asking for access to files requires a user gesture, hence the button.
+    let resume = {
+        let slice = slice.clone();
+        move |_| {
+            let slice = slice.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                for item in slice.items_needing_permission() {
+                    let Some(handle) = &item.handle else {
+                        continue;
+                    };
+                    match upload_store::get_file(handle, true).await {
+                        Ok(Some(file)) => slice.dispatch(UploadQueueAction::SetFile { id: item.id, file }),
+                        Ok(None) => {}
+                        Err(error) => console_error!(format!("{error:?}")),
+                    }
+                }
+            });
+        }
+    };
+
     let n_completed = slice.items_completed_len();
+    let n_needing_file = slice.items_needing_file_len();
+    let needs_permission = !slice.items_needing_permission().is_empty();
     let n_total = slice.queue.len();
     let progress_percentage = f32::floor((n_completed as f32 / n_total as f32) * 100f32);
 
@@ -47,6 +71,17 @@ pub fn upload_progress(_: &UploadProgressProps) -> Html {
                         <IconClose/>
                     </Button>
                 </div>
+                if n_needing_file > 0 {
+                    <div class="upload-progress-notice">
+                        {format!("{n_needing_file} file(s) need to be selected again to finish uploading")}
+                    </div>
+                }
+                if needs_permission {
+                    <div class="upload-progress-notice">
+                        <span>{"Allow access to your files again to finish uploading"}</span>
+                        <Button label={"Resume"} on_click={resume}/>
+                    </div>
+                }
                 <div class="upload-progress-items">
                     {queue_items}
                 </div>
@@ -59,14 +94,18 @@ pub fn upload_progress(_: &UploadProgressProps) -> Html {
 pub struct UploadProgressItemProps {
     pub filename: AttrValue,
     pub status: FileUploadStatus,
-    pub object_url: AttrValue,
+    pub object_url: Option<AttrValue>,
 }
 
 #[function_component(UploadProgressItem)]
 pub fn upload_progress_item(props: &UploadProgressItemProps) -> Html {
     html! {
         <div class="upload-progress-item">
-            <img class="thumb" src={&props.object_url} />
+            if let Some(object_url) = &props.object_url {
+                <img class="thumb" src={object_url} />
+            } else {
+                <div class="thumb" />
+            }
             <span class="filename">{&props.filename}</span>
             <span class="status">{&props.status}</span>
         </div>
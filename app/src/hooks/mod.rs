@@ -8,8 +8,11 @@ pub mod use_on_file_upload_finished;
 pub mod use_overlay;
 pub mod use_photo;
 pub mod use_photo_src;
+pub mod use_possible_duplicates;
 pub mod use_share_album;
 pub mod use_shares;
+pub mod use_trash;
+pub mod use_usage;
 
 pub use use_album::*;
 pub use use_album_share::*;
@@ -21,5 +24,8 @@ pub use use_on_file_upload_finished::*;
 pub use use_overlay::*;
 pub use use_photo::*;
 pub use use_photo_src::*;
+pub use use_possible_duplicates::*;
 pub use use_share_album::*;
 pub use use_shares::*;
+pub use use_trash::*;
+pub use use_usage::*;
@@ -3,6 +3,7 @@ use bounce::use_slice_value;
 use yew::prelude::*;
 
 pub struct FileStatus {
+    pub id: String,
     pub filename: String,
     pub status: FileUploadStatus,
 }
@@ -10,8 +11,7 @@ pub struct FileStatus {
 #[hook]
 pub fn use_on_file_upload_finished(callback: Callback<Vec<FileStatus>>) {
     let slice = use_slice_value::<UploadQueue>();
-    let notified: UseStateHandle<Vec<String>> =
-        use_state(|| slice.queue.iter().map(|qi| qi.filename.to_string()).collect());
+    let notified: UseStateHandle<Vec<String>> = use_state(|| slice.queue.iter().map(|qi| qi.id.to_string()).collect());
 
     use_effect_with_deps(
         move |slice| {
@@ -21,19 +21,17 @@ pub fn use_on_file_upload_finished(callback: Callback<Vec<FileStatus>>) {
                 .filter(|qi| {
                     (matches!(qi.status, FileUploadStatus::Done { .. })
                         || matches!(qi.status, FileUploadStatus::Exists { .. }))
-                        && !(*notified).contains(&qi.filename)
+                        && !(*notified).contains(&qi.id)
                 })
                 .map(|qi| FileStatus {
+                    id: qi.id.to_string(),
                     filename: qi.filename.to_string(),
                     status: qi.status.clone(),
                 })
                 .collect();
 
             if !queue_items_not_notified.is_empty() {
-                let mut just_notified = queue_items_not_notified
-                    .iter()
-                    .map(|qi| qi.filename.to_string())
-                    .collect();
+                let mut just_notified = queue_items_not_notified.iter().map(|qi| qi.id.to_string()).collect();
                 let mut notified_updated = (*notified).clone();
                 notified_updated.append(&mut just_notified);
 
@@ -0,0 +1,25 @@
+use yew::prelude::*;
+
+#[hook]
+pub fn use_possible_duplicates() -> (UseStateHandle<Vec<Vec<crate::models::LibraryPhoto>>>, Callback<()>) {
+    let groups = use_state(Vec::new);
+
+    let refresh_groups = {
+        let groups = groups.clone();
+
+        Callback::from(move |_| {
+            let groups = groups.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                let possible_duplicates = crate::WASM_CLIENT.get_possible_duplicates().await.unwrap();
+                groups.set(possible_duplicates);
+            });
+        })
+    };
+
+    {
+        let refresh_groups = refresh_groups.clone();
+        use_effect_with_deps(move |_| refresh_groups.emit(()), ());
+    }
+
+    (groups, refresh_groups)
+}
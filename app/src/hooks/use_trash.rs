@@ -0,0 +1,26 @@
+use upholi_core::client::TrashEntry;
+use yew::prelude::*;
+
+#[hook]
+pub fn use_trash() -> (UseStateHandle<Vec<TrashEntry>>, Callback<()>) {
+    let trash = use_state(Vec::new);
+
+    let refresh_trash = {
+        let trash = trash.clone();
+
+        Callback::from(move |_| {
+            let trash = trash.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                let entries = crate::WASM_CLIENT.get_trash().await.unwrap();
+                trash.set(entries);
+            });
+        })
+    };
+
+    {
+        let refresh_trash = refresh_trash.clone();
+        use_effect_with_deps(move |_| refresh_trash.emit(()), ());
+    }
+
+    (trash, refresh_trash)
+}
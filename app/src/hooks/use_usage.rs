@@ -0,0 +1,26 @@
+use upholi_lib::http::response::UsageResult;
+use yew::prelude::*;
+
+#[hook]
+pub fn use_usage() -> (UseStateHandle<Option<UsageResult>>, Callback<()>) {
+    let usage = use_state(|| None);
+
+    let refresh_usage = {
+        let usage = usage.clone();
+
+        Callback::from(move |_| {
+            let usage = usage.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                let result = crate::WASM_CLIENT.get_usage().await.unwrap();
+                usage.set(Some(result));
+            });
+        })
+    };
+
+    {
+        let refresh_usage = refresh_usage.clone();
+        use_effect_with_deps(move |_| refresh_usage.emit(()), ());
+    }
+
+    (usage, refresh_usage)
+}
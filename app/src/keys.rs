@@ -1,75 +1,70 @@
-use crate::{encryption, hashing};
 use anyhow::{anyhow, Result};
 use base64::prelude::*;
-use once_cell::sync::Lazy;
 use std::sync::RwLock;
+use upholi_core::keys::KeyStore;
 use wasm_bindgen::UnwrapThrowExt;
 use web_sys::Storage;
 
 const LOCAL_STORAGE_KEY_MASTER_KEY: &str = "master-key";
 const LOCAL_STORAGE_KEY_SHARE_KEY_PREFIX: &str = "share-key";
 
-static MASTER_KEY: Lazy<RwLock<Vec<u8>>> = Lazy::new(|| {
-    let storage = get_local_storage();
-    let stored_key = storage.get_item(LOCAL_STORAGE_KEY_MASTER_KEY).unwrap_throw();
-    let key = match stored_key {
-        Some(key) => BASE64_STANDARD.decode(key).unwrap_throw(),
-        None => vec![],
-    };
-
-    RwLock::new(key)
-});
+/// Keeps keys in the browser's local storage, so they survive a page reload.
+pub struct LocalStorageKeyStore {
+    master_key: RwLock<Vec<u8>>,
+}
 
-/// Get user's master encryption key
-pub fn get_master_key() -> Vec<u8> {
-    MASTER_KEY.read().unwrap_throw().clone()
+impl LocalStorageKeyStore {
+    pub fn new() -> Self {
+        let storage = get_local_storage();
+        let stored_key = storage.get_item(LOCAL_STORAGE_KEY_MASTER_KEY).unwrap_throw();
+        let key = match stored_key {
+            Some(key) => BASE64_STANDARD.decode(key).unwrap_throw(),
+            None => vec![],
+        };
+
+        Self {
+            master_key: RwLock::new(key),
+        }
+    }
 }
 
-/// Set user's master encryption key
-pub fn set_master_key(key: &Vec<u8>) {
-    let key_str = &BASE64_STANDARD.encode(key);
-    let mut master_key = MASTER_KEY.write().unwrap_throw();
-    *master_key = key.clone();
+impl KeyStore for LocalStorageKeyStore {
+    fn get_master_key(&self) -> Vec<u8> {
+        self.master_key.read().unwrap_throw().clone()
+    }
 
-    let storage = get_local_storage();
-    storage.set_item(LOCAL_STORAGE_KEY_MASTER_KEY, key_str).unwrap_throw();
-}
+    fn set_master_key(&self, key: &[u8]) -> Result<()> {
+        let key_str = &BASE64_STANDARD.encode(key);
+        let mut master_key = self.master_key.write().unwrap_throw();
+        *master_key = key.to_vec();
 
-/// Get a share's encryption key
-pub fn get_share_key(share_id: &str) -> Result<Option<Vec<u8>>> {
-    let storage = get_local_storage();
-    let storage_key = get_storage_key_for_share(share_id);
-    match storage.get_item(&storage_key).unwrap_throw() {
-        Some(share_key) => {
-            let share_key = BASE64_STANDARD.decode(share_key)?;
-            Ok(Some(share_key))
-        }
-        None => Ok(None),
+        let storage = get_local_storage();
+        storage
+            .set_item(LOCAL_STORAGE_KEY_MASTER_KEY, key_str)
+            .map_err(|_| anyhow!("Error writing master key to storage"))?;
+        Ok(())
     }
-}
 
-/// Set a share's encryption key
-pub fn set_share_key(share_id: &str, key: &[u8]) -> Result<()> {
-    let storage = get_local_storage();
-    let storage_key = get_storage_key_for_share(share_id);
-    let key_str = &BASE64_STANDARD.encode(key);
-    storage
-        .set_item(&storage_key, key_str)
-        .map_err(|_| anyhow!("Error writing share key to storage"))?;
-    Ok(())
-}
+    fn get_share_key(&self, share_id: &str) -> Result<Option<Vec<u8>>> {
+        let storage = get_local_storage();
+        let storage_key = get_storage_key_for_share(share_id);
+        match storage.get_item(&storage_key).unwrap_throw() {
+            Some(share_key) => {
+                let share_key = BASE64_STANDARD.decode(share_key)?;
+                Ok(Some(share_key))
+            }
+            None => Ok(None),
+        }
+    }
 
-/// Derive a symmetric encryption key from a user's credentials
-pub fn get_key_from_user_credentials(username: &str, password: &str) -> Result<Vec<u8>> {
-    if username.is_empty() {
-        Err(anyhow!("Username is empty"))
-    } else if password.is_empty() {
-        Err(anyhow!("Password is empty"))
-    } else {
-        // The salt is based on username; hash username to ensure minimum length.
-        let salt = &hashing::compute_sha256_hash(username.as_bytes())?[..20];
-        let password_derived_key = encryption::symmetric::derive_key_from_string(password, salt)?;
-        Ok(password_derived_key)
+    fn set_share_key(&self, share_id: &str, key: &[u8]) -> Result<()> {
+        let storage = get_local_storage();
+        let storage_key = get_storage_key_for_share(share_id);
+        let key_str = &BASE64_STANDARD.encode(key);
+        storage
+            .set_item(&storage_key, key_str)
+            .map_err(|_| anyhow!("Error writing share key to storage"))?;
+        Ok(())
     }
 }
 
@@ -85,33 +80,3 @@ fn get_local_storage() -> Storage {
 fn get_storage_key_for_share(share_id: &str) -> String {
     format!("{LOCAL_STORAGE_KEY_SHARE_KEY_PREFIX}-{share_id}")
 }
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn get_key_from_user_credentials_consistency() {
-        let username = "username";
-        let password = "password";
-
-        let key_base = get_key_from_user_credentials(username, password).unwrap();
-
-        // Identical credentials should give same key
-        let key = get_key_from_user_credentials(username, password).unwrap();
-        assert_eq!(key_base, key);
-
-        // Any change in credentials should give a different key
-        let key = get_key_from_user_credentials(username, "other_password").unwrap();
-        assert_ne!(key_base, key);
-        let key = get_key_from_user_credentials("other_username", password).unwrap();
-        assert_ne!(key_base, key);
-    }
-
-    #[test]
-    fn get_key_from_user_credentials_bad_input() {
-        assert!(get_key_from_user_credentials("username", "").is_err());
-        assert!(get_key_from_user_credentials("", "password").is_err());
-        assert!(get_key_from_user_credentials("", "").is_err());
-    }
-}
@@ -1,28 +1,25 @@
 use crate::components::{FileUploader, Overlay};
-use api_client::ApiClient;
 use bounce::BounceRoot;
 use once_cell::sync::Lazy;
-use pages::{AlbumPage, HomePage, LibraryPage, LoginPage, NotFoundPage, RegisterPage, SharePage};
+use pages::{
+    AlbumPage, DuplicatesPage, FavouritesPage, HomePage, LibraryPage, LoginPage, NotFoundPage, RegisterPage,
+    SearchPage, SharePage, TrashPage,
+};
 use serde::{Deserialize, Serialize};
+use upholi_core::{api_client::ApiClient, client::Client};
 use wasm_bindgen::{prelude::wasm_bindgen, UnwrapThrowExt};
-use wasm_client::WasmClient;
 use web_sys::Document;
 use yew::prelude::*;
 use yew_router::{BrowserRouter, Routable, Switch};
 
-mod api_client;
 mod components;
-mod encryption;
-mod exif;
-mod hashing;
 mod hooks;
-mod images;
 mod keys;
 mod models;
-mod multipart;
 mod pages;
-mod repository;
-mod wasm_client;
+mod upload_pool;
+mod upload_store;
+mod upload_worker;
 
 static ORIGIN: Lazy<String> = Lazy::new(|| {
     let window = web_sys::window().expect_throw("Could not find global 'window'.");
@@ -30,9 +27,18 @@ static ORIGIN: Lazy<String> = Lazy::new(|| {
     location.origin().expect_throw("could not determine 'origin'.")
 });
 static API_CLIENT: Lazy<ApiClient> = Lazy::new(|| ApiClient::new(&format!("{}/api", ORIGIN.as_str())));
-static WASM_CLIENT: Lazy<WasmClient> = Lazy::new(|| WasmClient::new(&API_CLIENT));
+static KEY_STORE: Lazy<keys::LocalStorageKeyStore> = Lazy::new(keys::LocalStorageKeyStore::new);
+static WASM_CLIENT: Lazy<Client> = Lazy::new(|| Client::new(&API_CLIENT, &*KEY_STORE));
 
-#[wasm_bindgen]
+// Bundled as a module next to the app, rather than inlined in index.html, so the Content Security Policy can forbid inline scripts.
+#[wasm_bindgen(inline_js = r#"
+export function offerAsFileDownload(filename, src) {
+  const aElement = document.createElement("a");
+  aElement.href = src;
+  aElement.download = filename;
+  aElement.click();
+}
+"#)]
 extern "C" {
     #[wasm_bindgen(js_name = offerAsFileDownload)]
     fn offer_as_file_download(filename: &str, src: &str);
@@ -51,6 +57,14 @@ pub enum Route {
     Home,
     #[at("/library")]
     Library,
+    #[at("/duplicates")]
+    Duplicates,
+    #[at("/favourites")]
+    Favourites,
+    #[at("/trash")]
+    Trash,
+    #[at("/search")]
+    Search,
     #[at("/album/:id")]
     Album { id: String },
     #[at("/s/:id")]
@@ -75,6 +89,10 @@ fn switch(routes: Route) -> Html {
     match routes {
         Route::Home => html! { <HomePage/> },
         Route::Library => html! { <LibraryPage/> },
+        Route::Duplicates => html! { <DuplicatesPage/> },
+        Route::Favourites => html! { <FavouritesPage/> },
+        Route::Trash => html! { <TrashPage/> },
+        Route::Search => html! { <SearchPage/> },
         Route::Album { id } => html! { <AlbumPage id={id}/> },
         Route::Share { id } => html! {<SharePage id={id}/>},
         Route::Login => html! { <LoginPage/> },
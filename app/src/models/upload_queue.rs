@@ -1,63 +1,148 @@
-use crate::components::FileUploadStatus;
+use crate::{
+    components::FileUploadStatus,
+    upload_store::{SelectedFile, StoredUpload},
+};
 use bounce::Slice;
 use std::rc::Rc;
-use web_sys::{File, FileList};
+use upholi_lib::ids::id;
+use wasm_bindgen::JsValue;
+use web_sys::File;
 use yew::Reducible;
 
 #[derive(Slice, PartialEq, Default, Clone)]
 pub struct UploadQueue {
+    /// ID of the user whose uploads these are, once known.
+    pub user_id: Option<String>,
     pub queue: Vec<UploadQueueItem>,
 }
 
 impl UploadQueue {
     pub fn items_completed_len(&self) -> usize {
+        self.queue.iter().filter(|item| item.is_completed()).count()
+    }
+
+    /// Number of items restored after a page reload, for which the file has to be selected again.
+    pub fn items_needing_file_len(&self) -> usize {
         self.queue
             .iter()
-            .filter(|item| {
-                matches!(item.status, FileUploadStatus::Done { .. })
-                    || matches!(item.status, FileUploadStatus::Exists { .. })
-            })
+            .filter(|item| item.status == FileUploadStatus::NeedsFile)
             .count()
     }
+
+    /// Items restored after a page reload, for which the user has to grant access to the file again.
+    pub fn items_needing_permission(&self) -> Vec<UploadQueueItem> {
+        self.queue
+            .iter()
+            .filter(|item| item.status == FileUploadStatus::NeedsPermission)
+            .cloned()
+            .collect()
+    }
 }
 
 #[derive(PartialEq, Clone)]
 pub struct UploadQueueItem {
+    pub id: String,
     pub filename: String,
     pub size: f64,
+    pub last_modified: f64,
     pub status: FileUploadStatus,
-    pub file: web_sys::File,
-    pub object_url: String,
+    /// Missing for items restored after a page reload, until the file is selected again or access to it is granted.
+    pub file: Option<File>,
+    /// File system handle, in browsers that provide them. Allows reading the file again after a page reload.
+    pub handle: Option<JsValue>,
+    pub object_url: Option<String>,
     /// Album to add this file to once uploaded
     pub target_album_id: Option<String>,
+    /// Number of failed attempts to upload this file.
+    pub attempts: u32,
+}
+
+impl UploadQueueItem {
+    pub fn is_completed(&self) -> bool {
+        matches!(self.status, FileUploadStatus::Done { .. }) || matches!(self.status, FileUploadStatus::Exists { .. })
+    }
+
+    /// Whether this item is for given file. Files are identified by name, size and modification time,
+    /// as browsers do not expose their path.
+    fn is_for_file(&self, file: &File) -> bool {
+        self.filename == file.name() && self.size == file.size() && self.last_modified == file.last_modified()
+    }
+
+    pub fn set_file(&mut self, file: File) {
+        self.object_url = web_sys::Url::create_object_url_with_blob(&file).ok();
+        self.file = Some(file);
+        self.status = FileUploadStatus::Queued;
+    }
 }
 
 impl From<File> for UploadQueueItem {
     fn from(file: File) -> Self {
-        let file_name = file.name();
-        let object_url =
-            web_sys::Url::create_object_url_with_blob(&file).expect("Failed to create object url from file");
-
-        Self {
-            filename: file_name,
+        let mut item = Self {
+            id: id(),
+            filename: file.name(),
             size: file.size(),
+            last_modified: file.last_modified(),
             status: FileUploadStatus::Queued,
-            file,
-            object_url,
+            file: None,
+            handle: None,
+            object_url: None,
             target_album_id: None,
+            attempts: 0,
+        };
+        item.set_file(file);
+        item
+    }
+}
+
+impl From<StoredUpload> for UploadQueueItem {
+    fn from(stored: StoredUpload) -> Self {
+        Self {
+            id: stored.id,
+            filename: stored.filename,
+            size: stored.size,
+            last_modified: stored.last_modified,
+            status: match stored.handle {
+                Some(_) => FileUploadStatus::NeedsPermission,
+                None => FileUploadStatus::NeedsFile,
+            },
+            file: None,
+            handle: stored.handle,
+            object_url: None,
+            target_album_id: stored.target_album_id,
+            attempts: 0,
         }
     }
 }
 
 pub enum UploadQueueAction {
     AddToQueue {
-        filelist: FileList,
+        files: Vec<SelectedFile>,
         target_album_id: Option<String>,
     },
+    /// Switch to the queue of given user, or of no user when not logged in.
+    /// Items of the previous user are dropped, so they are never uploaded to another user's library.
+    SetUser {
+        user_id: Option<String>,
+    },
+    /// Add items of given user restored after a page reload.
+    Restore {
+        user_id: String,
+        items: Vec<UploadQueueItem>,
+    },
+    /// Set the file of a restored item, after the user granted access to it.
+    SetFile {
+        id: String,
+        file: File,
+    },
     UpdateItemState {
-        file_name: String,
+        id: String,
         status: FileUploadStatus,
     },
+    /// Mark an item as failed, and count the attempt.
+    Failed {
+        id: String,
+    },
+    /// Remove completed items, and failed ones. Failed items that would have been retried are not anymore.
     RemoveCompleted,
 }
 
@@ -66,44 +151,108 @@ impl Reducible for UploadQueue {
 
     fn reduce(self: Rc<Self>, action: Self::Action) -> Rc<Self> {
         match action {
-            UploadQueueAction::AddToQueue {
-                filelist,
-                target_album_id,
-            } => {
+            UploadQueueAction::AddToQueue { files, target_album_id } => {
                 let mut queue = self.queue.clone();
 
-                for i in 0..filelist.length() {
-                    if let Some(file) = filelist.get(i) {
-                        let mut file: UploadQueueItem = file.into();
-                        file.target_album_id = target_album_id.clone();
-                        let already_in_queue = queue
-                            .iter()
-                            .any(|item| item.filename == file.filename && item.size == file.size);
-                        if !already_in_queue {
-                            queue.push(file);
+                for SelectedFile { file, handle } in files {
+                    match queue.iter_mut().find(|item| item.is_for_file(&file)) {
+                        Some(item) => {
+                            // A file selected again for an item restored after a page reload.
+                            let needs_file = matches!(
+                                item.status,
+                                FileUploadStatus::NeedsFile | FileUploadStatus::NeedsPermission
+                            );
+                            if needs_file {
+                                item.handle = handle.or(item.handle.take());
+                                item.set_file(file);
+                            }
+                        }
+                        None => {
+                            let mut item: UploadQueueItem = file.into();
+                            item.handle = handle;
+                            item.target_album_id = target_album_id.clone();
+                            queue.push(item);
                         }
                     }
                 }
 
-                Self { queue }.into()
+                Self {
+                    user_id: self.user_id.clone(),
+                    queue,
+                }
+                .into()
             }
-            UploadQueueAction::UpdateItemState { file_name, status } => {
+            UploadQueueAction::SetUser { user_id } => {
+                if user_id == self.user_id {
+                    return self;
+                }
+
+                Self { user_id, queue: vec![] }.into()
+            }
+            UploadQueueAction::Restore { user_id, items } => {
+                if self.user_id.as_ref() != Some(&user_id) {
+                    return self;
+                }
+
+                let mut queue = self.queue.clone();
+                for item in items {
+                    if !queue.iter().any(|queue_item| queue_item.id == item.id) {
+                        queue.push(item);
+                    }
+                }
+
+                Self {
+                    user_id: self.user_id.clone(),
+                    queue,
+                }
+                .into()
+            }
+            UploadQueueAction::SetFile { id, file } => {
+                let mut queue = self.queue.clone();
+                if let Some(queue_item) = queue.iter_mut().find(|item| item.id == id) {
+                    queue_item.set_file(file);
+                }
+
+                Self {
+                    user_id: self.user_id.clone(),
+                    queue,
+                }
+                .into()
+            }
+            UploadQueueAction::UpdateItemState { id, status } => {
                 let mut queue = self.queue.clone();
-                if let Some(queue_item) = queue.iter_mut().find(|item| item.filename == file_name) {
+                if let Some(queue_item) = queue.iter_mut().find(|item| item.id == id) {
                     queue_item.status = status;
                 }
 
-                Self { queue }.into()
+                Self {
+                    user_id: self.user_id.clone(),
+                    queue,
+                }
+                .into()
             }
-            UploadQueueAction::RemoveCompleted => {
+            UploadQueueAction::Failed { id } => {
                 let mut queue = self.queue.clone();
+                if let Some(queue_item) = queue.iter_mut().find(|item| item.id == id) {
+                    queue_item.status = FileUploadStatus::Failed;
+                    queue_item.attempts += 1;
+                }
 
-                queue.retain(|item| {
-                    !matches!(item.status, FileUploadStatus::Done { .. })
-                        && !matches!(item.status, FileUploadStatus::Exists { .. })
-                });
+                Self {
+                    user_id: self.user_id.clone(),
+                    queue,
+                }
+                .into()
+            }
+            UploadQueueAction::RemoveCompleted => {
+                let mut queue = self.queue.clone();
+                queue.retain(|item| !item.is_completed() && item.status != FileUploadStatus::Failed);
 
-                Self { queue }.into()
+                Self {
+                    user_id: self.user_id.clone(),
+                    queue,
+                }
+                .into()
             }
         }
     }
@@ -0,0 +1,84 @@
+use crate::{
+    components::{gallery_photo::GalleryPhoto, icons::IconCheck, layouts::PageLayout, BackButton, ConfirmButton},
+    hooks::{use_overlay, use_possible_duplicates},
+    models::LibraryPhoto,
+    WASM_CLIENT,
+};
+use yew::prelude::*;
+
+const THUMBNAIL_SIZE: f32 = 200.0;
+
+#[function_component(DuplicatesPage)]
+pub fn duplicates_page() -> Html {
+    let (groups, refresh_groups) = use_possible_duplicates();
+
+    html! {
+        <PageLayout class="duplicates"
+            title="Possible duplicates"
+            header_actions_left={html! { <BackButton/> }}>
+            if groups.is_empty() {
+                <p>{"No photos found that look alike."}</p>
+            }
+            {groups.iter().map(|group| html! {
+                <DuplicatesGroup photos={group.clone()} on_resolved={refresh_groups.clone()}/>
+            }).collect::<Html>()}
+        </PageLayout>
+    }
+}
+
+#[derive(Properties, PartialEq)]
+struct DuplicatesGroupProps {
+    /// Photos that look alike, with the photo with the highest resolution first.
+    photos: Vec<LibraryPhoto>,
+    on_resolved: Callback<()>,
+}
+
+#[function_component(DuplicatesGroup)]
+fn duplicates_group(props: &DuplicatesGroupProps) -> Html {
+    let (_, set_overlay) = use_overlay();
+    let n_delete = props.photos.len() - 1;
+
+    html! {
+        <div class="duplicates-group">
+            {props.photos.iter().enumerate().map(|(index, photo)| {
+                let keep_id = photo.id.clone();
+                let delete_ids: Vec<String> = props.photos.iter().filter(|other| other.id != keep_id).map(|other| other.id.clone()).collect();
+                let on_resolved = props.on_resolved.clone();
+                let set_overlay = set_overlay.clone();
+
+                let keep = move |_| {
+                    let keep_id = keep_id.clone();
+                    let delete_ids = delete_ids.clone();
+                    let on_resolved = on_resolved.clone();
+                    let set_overlay = set_overlay.clone();
+
+                    set_overlay.emit(true);
+
+                    wasm_bindgen_futures::spawn_local(async move {
+                        WASM_CLIENT.keep_duplicate(&keep_id, &delete_ids).await.unwrap();
+                        set_overlay.emit(false);
+                        on_resolved.emit(());
+                    });
+                };
+
+                html! {
+                    <div class="duplicate">
+                        <GalleryPhoto photo_id={photo.id.clone()} width={THUMBNAIL_SIZE} height={THUMBNAIL_SIZE}/>
+                        <span class="dimensions">
+                            {format!("{} × {}", photo.width, photo.height)}
+                            if index == 0 {
+                                {" (highest resolution)"}
+                            }
+                        </span>
+                        <ConfirmButton label="Keep"
+                            on_click={keep}
+                            confirm_dialog_title="Keep this photo?"
+                            confirm_dialog_body={format!("The other {n_delete} photos will be deleted. This photo takes their place in albums.")}>
+                            <IconCheck/>
+                        </ConfirmButton>
+                    </div>
+                }
+            }).collect::<Html>()}
+        </div>
+    }
+}
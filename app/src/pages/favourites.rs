@@ -0,0 +1,94 @@
+use crate::{
+    components::{
+        buttons::{AddToAlbumButton, Button, FavouritePhotosButton, IconPosition, RatePhotosButton, TagPhotosButton},
+        gallery::Gallery,
+        icons::IconClose,
+        layouts::PageLayout,
+        BackButton, RatingInput,
+    },
+    hooks::use_library_photos::use_library_photos,
+    models::AlbumPhoto,
+};
+use yew::prelude::*;
+
+/// Photos in the library marked as favourite, optionally limited to a minimum rating.
+#[function_component(FavouritesPage)]
+pub fn favourites_page() -> Html {
+    let (photos, refresh_photos) = use_library_photos();
+    let selected_photos = use_state(Vec::<String>::new);
+    let min_rating = use_state(|| 0u8);
+    let photos: Vec<AlbumPhoto> = (*photos)
+        .clone()
+        .into_iter()
+        .filter(|photo| photo.favourite && photo.rating >= *min_rating)
+        .map(|photo| photo.into())
+        .collect();
+
+    let on_changed = {
+        let selected_photos = selected_photos.clone();
+        Callback::from(move |_| {
+            selected_photos.set(vec![]);
+            refresh_photos.emit(());
+        })
+    };
+
+    let on_min_rating_change = {
+        let min_rating = min_rating.clone();
+        Callback::from(move |rating: u8| min_rating.set(rating))
+    };
+
+    let n_photos_selected = (*selected_photos).len();
+    let header_actions_left = match n_photos_selected {
+        0 => Some(html! { <BackButton/> }),
+        _ => Some({
+            let on_added_selected_photos = selected_photos.clone();
+
+            html! {
+                <>
+                    <AddToAlbumButton
+                        photo_ids={(*selected_photos).clone()}
+                        on_added={move |_| {
+                            on_added_selected_photos.set(vec![]);
+                        }}
+                    />
+                    <FavouritePhotosButton
+                        photo_ids={(*selected_photos).clone()}
+                        favourite={false}
+                        on_changed={on_changed.clone()}/>
+                    <RatePhotosButton
+                        photo_ids={(*selected_photos).clone()}
+                        on_rated={on_changed.clone()}/>
+                    <TagPhotosButton
+                        photo_ids={(*selected_photos).clone()}
+                        on_tagged={on_changed}/>
+                </>
+            }
+        }),
+    };
+    let header_actions_right = match n_photos_selected {
+        0 => None,
+        _ => {
+            let selected_photos = selected_photos.clone();
+            Some(html! {
+                <Button label={format!("{n_photos_selected} selected")}
+                    on_click={move |_| selected_photos.set(vec![])}
+                    icon_position={IconPosition::Right}>
+                    <IconClose/>
+                </Button>
+            })
+        }
+    };
+
+    html! {
+        <PageLayout class="favourites"
+            title="Favourites"
+            header_actions_left={header_actions_left}
+            header_actions_right={header_actions_right}>
+            <div class="favourites-filter">
+                <span>{"Minimum rating"}</span>
+                <RatingInput rating={*min_rating} on_change={on_min_rating_change}/>
+            </div>
+            <Gallery photos={photos} selected_photos={selected_photos} />
+        </PageLayout>
+    }
+}
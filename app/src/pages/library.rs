@@ -1,27 +1,51 @@
 use crate::{
     components::{
-        buttons::{AddToAlbumButton, Button, DeletePhotosButton, IconPosition},
+        buttons::{
+            AddToAlbumButton, Button, DeletePhotosButton, FavouritePhotosButton, IconPosition, RatePhotosButton,
+            TagPhotosButton,
+        },
         drop_upload::DropUpload,
         gallery::Gallery,
         icons::IconClose,
         layouts::PageLayout,
-        BackButton, UploadButton,
+        BackButton, StorageUsage, TagFilter, UploadButton,
     },
-    hooks::{use_library_photos::use_library_photos, use_on_file_upload_finished},
+    hooks::{use_library_photos::use_library_photos, use_on_file_upload_finished, use_usage},
     models::AlbumPhoto,
+    Route,
 };
+use upholi_core::models::distinct_tags;
 use use_on_file_upload_finished::FileStatus;
 use yew::prelude::*;
+use yew_router::prelude::use_navigator;
 
 #[function_component(LibraryPage)]
 pub fn library_page() -> Html {
     let (photos, refresh_photos) = use_library_photos();
+    let (usage, refresh_usage) = use_usage();
+    let navigator = use_navigator().unwrap();
     let selected_photos = use_state(Vec::<String>::new);
-    let photos: Vec<AlbumPhoto> = (*photos).clone().into_iter().map(|photo| photo.into()).collect();
+    let tag = use_state(|| None::<String>);
+    let tags = distinct_tags(photos.iter().flat_map(|photo| &photo.tags));
+    let all_selected_favourite = !selected_photos.is_empty()
+        && photos
+            .iter()
+            .filter(|photo| selected_photos.contains(&photo.id))
+            .all(|photo| photo.favourite);
+    let photos: Vec<AlbumPhoto> = (*photos)
+        .clone()
+        .into_iter()
+        .filter(|photo| (*tag).as_ref().is_none_or(|tag| photo.has_tag(tag)))
+        .map(|photo| photo.into())
+        .collect();
 
     {
         let refresh_photos = refresh_photos.clone();
-        use_on_file_upload_finished(Callback::<Vec<FileStatus>>::from(move |_| refresh_photos.emit(())));
+        let refresh_usage = refresh_usage.clone();
+        use_on_file_upload_finished(Callback::<Vec<FileStatus>>::from(move |_| {
+            refresh_photos.emit(());
+            refresh_usage.emit(());
+        }));
     }
 
     let reset_selection = use_memo(
@@ -34,12 +58,25 @@ pub fn library_page() -> Html {
         selected_photos.clone(),
     );
 
+    let on_tag_change = {
+        let tag = tag.clone();
+        Callback::from(move |value: Option<String>| tag.set(value))
+    };
+
     let n_photos_selected = (*selected_photos).len();
     let header_actions_left = match n_photos_selected {
         0 => Some(html! { <BackButton/> }),
         _ => Some({
             let on_added_selected_photos = selected_photos.clone();
             let on_deleted_selected_photos = selected_photos.clone();
+            let on_changed = {
+                let selected_photos = selected_photos.clone();
+                let refresh_photos = refresh_photos.clone();
+                Callback::from(move |_| {
+                    selected_photos.set(vec![]);
+                    refresh_photos.emit(());
+                })
+            };
 
             html! {
                 <>
@@ -49,18 +86,50 @@ pub fn library_page() -> Html {
                             on_added_selected_photos.set(vec![]);
                         }}
                     />
+                    <FavouritePhotosButton
+                        photo_ids={(*selected_photos).clone()}
+                        favourite={!all_selected_favourite}
+                        on_changed={on_changed.clone()}/>
+                    <RatePhotosButton
+                        photo_ids={(*selected_photos).clone()}
+                        on_rated={on_changed.clone()}/>
+                    <TagPhotosButton
+                        photo_ids={(*selected_photos).clone()}
+                        on_tagged={on_changed}/>
                     <DeletePhotosButton
                         selected_photos={(*selected_photos).clone()}
                         on_deleted={move|_| {
                             on_deleted_selected_photos.set(vec![]);
                             refresh_photos.emit(());
+                            refresh_usage.emit(());
                         }}/>
                 </>
             }
         }),
     };
+    let open_duplicates = {
+        let navigator = navigator.clone();
+        move |_| navigator.push(&Route::Duplicates)
+    };
+    let open_search = {
+        let navigator = navigator.clone();
+        move |_| navigator.push(&Route::Search)
+    };
+    let open_favourites = {
+        let navigator = navigator.clone();
+        move |_| navigator.push(&Route::Favourites)
+    };
+    let open_trash = move |_| navigator.push(&Route::Trash);
     let header_actions_right = match n_photos_selected {
-        0 => Some(html! {<UploadButton/>}),
+        0 => Some(html! {
+            <>
+                <Button label="Search" on_click={open_search}/>
+                <Button label="Favourites" on_click={open_favourites}/>
+                <Button label="Duplicates" on_click={open_duplicates}/>
+                <Button label="Trash" on_click={open_trash}/>
+                <UploadButton/>
+            </>
+        }),
         _ => Some(html! {
             <>
                 <Button label={format!("{n_photos_selected} selected")}
@@ -76,6 +145,12 @@ pub fn library_page() -> Html {
         <PageLayout class="library"
             header_actions_left={header_actions_left}
             header_actions_right={header_actions_right}>
+            if let Some(usage) = (*usage).clone() {
+                <StorageUsage usage={usage}/>
+            }
+            if !tags.is_empty() {
+                <TagFilter tags={tags} selected={(*tag).clone()} on_change={on_tag_change}/>
+            }
             <DropUpload>
                 <Gallery photos={photos} selected_photos={selected_photos} />
             </DropUpload>
@@ -1,4 +1,12 @@
-use crate::{components::Form, hooks::use_authenticated, models::AuthStatus, Route, WASM_CLIENT};
+use crate::{
+    components::Form,
+    hooks::use_authenticated,
+    models::{AuthStatus, UploadQueue, UploadQueueAction},
+    Route, WASM_CLIENT,
+};
+use bounce::use_slice_dispatch;
+use upholi_core::api_client::ApiError;
+use upholi_lib::http::response::ErrorCode;
 use web_sys::HtmlInputElement;
 use yew::prelude::*;
 use yew_router::prelude::use_navigator;
@@ -6,16 +14,17 @@ use yew_router::prelude::use_navigator;
 #[function_component(LoginPage)]
 pub fn login_page() -> Html {
     let state = use_authenticated();
-    let auth_attempt_made = use_state(|| false);
+    let status = use_state(String::new);
     let username_ref = use_node_ref();
     let password_ref = use_node_ref();
     let navigator = use_navigator().unwrap();
+    let dispatch_upload_queue = use_slice_dispatch::<UploadQueue>();
 
     let on_click = {
         let state = state.clone();
         let username_ref = username_ref.clone();
         let password_ref = password_ref.clone();
-        let auth_attempt_made = auth_attempt_made.clone();
+        let status = status.clone();
 
         Callback::from(move |_| {
             if let (Some(username_input), Some(password_input)) = (
@@ -27,12 +36,23 @@ pub fn login_page() -> Html {
 
                 if !username.is_empty() && !password.is_empty() {
                     let state = state.clone();
-                    let auth_attempt_made = auth_attempt_made.clone();
+                    let status = status.clone();
+                    let dispatch_upload_queue = dispatch_upload_queue.clone();
 
                     wasm_bindgen_futures::spawn_local(async move {
                         match WASM_CLIENT.login(&username, &password).await {
-                            Ok(_) => state.set(AuthStatus::Authenticated),
-                            Err(_) => auth_attempt_made.set(true),
+                            Ok(_) => {
+                                // Uploads queued by a previous user must not end up in this user's library
+                                let user_id = WASM_CLIENT.get_user_id().await.ok();
+                                dispatch_upload_queue(UploadQueueAction::SetUser { user_id });
+                                state.set(AuthStatus::Authenticated);
+                            }
+                            Err(error) => match error.downcast_ref::<ApiError>() {
+                                Some(error) if error.response.code == ErrorCode::Unauthorized => {
+                                    status.set("Incorrect password".into())
+                                }
+                                _ => status.set(error.to_string()),
+                            },
                         };
                     });
                 }
@@ -55,7 +75,7 @@ pub fn login_page() -> Html {
         if *state != AuthStatus::Fetching {
             <Form title="Login"
                 on_submit={on_click}
-                status={if *auth_attempt_made {"Incorrect password"} else {""}}>
+                status={(*status).clone()}>
                 <label>{"Username"}
                     <input ref={username_ref} type="text"/>
                 </label>
@@ -1,15 +1,23 @@
 pub mod album;
+pub mod duplicates;
+pub mod favourites;
 pub mod home;
 pub mod library;
 pub mod login;
 pub mod not_found;
 pub mod register;
+pub mod search;
 pub mod share;
+pub mod trash;
 
 pub use album::*;
+pub use duplicates::*;
+pub use favourites::*;
 pub use home::*;
 pub use library::*;
 pub use login::*;
 pub use not_found::*;
 pub use register::*;
+pub use search::*;
 pub use share::*;
+pub use trash::*;
@@ -8,6 +8,8 @@ pub fn register() -> Html {
     let state = use_authenticated();
     let username_ref = use_node_ref();
     let password_ref = use_node_ref();
+    let invite_code_ref = use_node_ref();
+    let status = use_state(String::new);
     let navigator = use_navigator().unwrap();
 
     {
@@ -25,20 +27,27 @@ pub fn register() -> Html {
     let on_click_create = {
         let username_ref = username_ref.clone();
         let password_ref = password_ref.clone();
+        let invite_code_ref = invite_code_ref.clone();
+        let status = status.clone();
 
         move |_| {
-            if let (Some(username_input), Some(password_input)) = (
+            if let (Some(username_input), Some(password_input), Some(invite_code_input)) = (
                 username_ref.cast::<HtmlInputElement>(),
                 password_ref.cast::<HtmlInputElement>(),
+                invite_code_ref.cast::<HtmlInputElement>(),
             ) {
                 let username = username_input.value();
                 let password = password_input.value();
+                let invite_code = Some(invite_code_input.value()).filter(|code| !code.is_empty());
                 let navigator = navigator.clone();
+                let status = status.clone();
 
                 if !password.is_empty() && !username.is_empty() {
                     wasm_bindgen_futures::spawn_local(async move {
-                        WASM_CLIENT.register(&username, &password).await.unwrap();
-                        navigator.push(&Route::Home)
+                        match WASM_CLIENT.register(&username, &password, invite_code).await {
+                            Ok(_) => navigator.push(&Route::Home),
+                            Err(error) => status.set(error.to_string()),
+                        }
                     });
                 }
             }
@@ -47,13 +56,16 @@ pub fn register() -> Html {
 
     html! {
         if *state != AuthStatus::Fetching {
-            <Form title="Create new user" on_submit={on_click_create}>
+            <Form title="Create new user" on_submit={on_click_create} status={(*status).clone()}>
                 <label>{"Username"}
                     <input ref={username_ref} type="text"/>
                 </label>
                 <label>{"Password"}
                     <input ref={password_ref} type="password"/>
                 </label>
+                <label>{"Invite code (if required)"}
+                    <input ref={invite_code_ref} type="text"/>
+                </label>
             </Form>
         }
     }
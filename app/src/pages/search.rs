@@ -0,0 +1,99 @@
+use crate::{
+    components::{
+        buttons::{AddToAlbumButton, Button, IconPosition},
+        gallery::Gallery,
+        icons::IconClose,
+        layouts::PageLayout,
+        BackButton, SearchBar,
+    },
+    models::{AlbumPhoto, LibraryPhoto},
+    WASM_CLIENT,
+};
+use yew::prelude::*;
+
+enum SearchState {
+    Idle,
+    Searching,
+    Found(Vec<LibraryPhoto>),
+    Failed(String),
+}
+
+#[function_component(SearchPage)]
+pub fn search_page() -> Html {
+    let state = use_state(|| SearchState::Idle);
+    let selected_photos = use_state(Vec::<String>::new);
+
+    let on_search = {
+        let state = state.clone();
+        let selected_photos = selected_photos.clone();
+        Callback::from(move |query: String| {
+            let state = state.clone();
+            selected_photos.set(vec![]);
+            state.set(SearchState::Searching);
+
+            wasm_bindgen_futures::spawn_local(async move {
+                match WASM_CLIENT.search(&query).await {
+                    Ok(photos) => state.set(SearchState::Found(photos)),
+                    Err(error) => state.set(SearchState::Failed(error.to_string())),
+                }
+            });
+        })
+    };
+
+    let n_photos_selected = (*selected_photos).len();
+    let header_actions_left = match n_photos_selected {
+        0 => Some(html! { <BackButton/> }),
+        _ => {
+            let on_added_selected_photos = selected_photos.clone();
+            Some(html! {
+                <AddToAlbumButton
+                    photo_ids={(*selected_photos).clone()}
+                    on_added={move |_| {
+                        on_added_selected_photos.set(vec![]);
+                    }}
+                />
+            })
+        }
+    };
+    let header_actions_right = match n_photos_selected {
+        0 => None,
+        _ => {
+            let selected_photos = selected_photos.clone();
+            Some(html! {
+                <Button label={format!("{n_photos_selected} selected")}
+                    on_click={move |_| selected_photos.set(vec![])}
+                    icon_position={IconPosition::Right}>
+                    <IconClose/>
+                </Button>
+            })
+        }
+    };
+
+    let results = match &*state {
+        SearchState::Idle => html! {
+            <p class="search-help">
+                {"Search by text, or filter using camera:, year:, date:, tag:, album:, caption: and file:. "}
+                {"Put values with spaces in quotes, like album:\"Holiday 2023\"."}
+            </p>
+        },
+        SearchState::Searching => html! {
+            <p class="search-help">{"Searching... The first search builds the search index, which can take a while."}</p>
+        },
+        SearchState::Failed(error) => html! { <p class="search-error">{error}</p> },
+        SearchState::Found(photos) if photos.is_empty() => html! { <p>{"No photos found."}</p> },
+        SearchState::Found(photos) => {
+            let photos: Vec<AlbumPhoto> = photos.iter().cloned().map(|photo| photo.into()).collect();
+            html! { <Gallery photos={photos} selected_photos={selected_photos} /> }
+        }
+    };
+
+    html! {
+        <PageLayout class="search"
+            title="Search"
+            header_actions_left={header_actions_left}
+            header_actions_right={header_actions_right}>
+            <SearchBar on_search={on_search}/>
+            {results}
+        </PageLayout>
+    }
+}
@@ -0,0 +1,119 @@
+use crate::{
+    components::{
+        buttons::{Button, ConfirmButton, IconPosition},
+        gallery::Gallery,
+        icons::{IconClose, IconDelete, IconRefresh},
+        layouts::PageLayout,
+        BackButton,
+    },
+    hooks::{use_overlay, use_trash},
+    models::AlbumPhoto,
+    WASM_CLIENT,
+};
+use anyhow::Result;
+use futures::future::LocalBoxFuture;
+use yew::prelude::*;
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+#[function_component(TrashPage)]
+pub fn trash_page() -> Html {
+    let (trash, refresh_trash) = use_trash();
+    let (_, set_overlay) = use_overlay();
+    let selected_photos = use_state(Vec::<String>::new);
+    let photos: Vec<AlbumPhoto> = trash.iter().map(|entry| entry.photo.clone().into()).collect();
+
+    // Run an action on the trash, and refresh it once done.
+    let run = {
+        let selected_photos = selected_photos.clone();
+        move |action: LocalBoxFuture<'static, Result<()>>| {
+            let selected_photos = selected_photos.clone();
+            let refresh_trash = refresh_trash.clone();
+            let set_overlay = set_overlay.clone();
+
+            set_overlay.emit(true);
+
+            wasm_bindgen_futures::spawn_local(async move {
+                action.await.unwrap();
+                set_overlay.emit(false);
+                selected_photos.set(vec![]);
+                refresh_trash.emit(());
+            });
+        }
+    };
+
+    let n_photos_selected = (*selected_photos).len();
+    let header_actions_left = match n_photos_selected {
+        0 => Some(html! { <BackButton/> }),
+        _ => Some({
+            let restore_run = run.clone();
+            let restore_ids = (*selected_photos).clone();
+            let delete_run = run.clone();
+            let delete_ids = (*selected_photos).clone();
+
+            html! {
+                <>
+                    <Button label="Restore"
+                        on_click={move |_| {
+                            let ids = restore_ids.clone();
+                            restore_run(Box::pin(async move { WASM_CLIENT.restore_photos(&ids).await }))
+                        }}>
+                        <IconRefresh/>
+                    </Button>
+                    <ConfirmButton label="Delete permanently"
+                        on_click={move |_| {
+                            let ids = delete_ids.clone();
+                            delete_run(Box::pin(async move { WASM_CLIENT.delete_photos_permanently(&ids).await }))
+                        }}
+                        confirm_dialog_title="Delete photos permanently?"
+                        confirm_dialog_body={format!("{n_photos_selected} photos will be deleted. This cannot be undone.")}>
+                        <IconDelete/>
+                    </ConfirmButton>
+                </>
+            }
+        }),
+    };
+    let header_actions_right = match n_photos_selected {
+        0 if trash.is_empty() => None,
+        0 => Some(html! {
+            <ConfirmButton label="Empty trash"
+                on_click={move |_| run(Box::pin(async { WASM_CLIENT.empty_trash().await }))}
+                confirm_dialog_title="Empty trash?"
+                confirm_dialog_body={format!("{} photos will be deleted. This cannot be undone.", trash.len())}>
+                <IconDelete/>
+            </ConfirmButton>
+        }),
+        _ => {
+            let selected_photos = selected_photos.clone();
+            Some(html! {
+                <Button label={format!("{n_photos_selected} selected")}
+                    on_click={move |_| selected_photos.set(vec![])}
+                    icon_position={IconPosition::Right}>
+                    <IconClose/>
+                </Button>
+            })
+        }
+    };
+
+    let retention_days = trash.first().and_then(|entry| {
+        entry
+            .expires_on
+            .map(|expires_on| (expires_on - entry.trashed_on) / SECONDS_PER_DAY)
+    });
+
+    html! {
+        <PageLayout class="trash"
+            title="Trash"
+            header_actions_left={header_actions_left}
+            header_actions_right={header_actions_right}>
+            if trash.is_empty() {
+                <p>{"The trash is empty."}</p>
+            } else if let Some(retention_days) = retention_days {
+                <p class="trash-retention">
+                    {format!("Photos are deleted permanently {retention_days} days after they were moved to the trash.")}
+                </p>
+            }
+            <Gallery photos={photos} selected_photos={selected_photos} />
+        </PageLayout>
+    }
+}
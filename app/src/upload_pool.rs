@@ -0,0 +1,117 @@
+use crate::upload_worker::UploadWorker;
+use anyhow::{anyhow, Result};
+use futures::channel::oneshot;
+use gloo::worker::{Spawnable, WorkerBridge};
+use std::{cell::RefCell, collections::VecDeque, rc::Rc};
+use upholi_core::client::EncryptedPhoto;
+
+/// Path the worker script is served at, see index.html.
+const UPLOAD_WORKER_PATH: &str = "/upload_worker.js";
+
+/// Never spawn more workers than this, regardless of the number of CPU cores.
+const MAX_WORKERS: usize = 4;
+
+type WorkerResponse = Result<EncryptedPhoto, String>;
+
+/// A fixed number of upload workers. Each worker processes one photo at a time,
+/// which bounds the number of photos that are processed concurrently.
+pub struct UploadWorkerPool {
+    workers: Vec<PooledWorker>,
+    idle: RefCell<Vec<usize>>,
+    waiting: RefCell<VecDeque<oneshot::Sender<usize>>>,
+}
+
+struct PooledWorker {
+    bridge: WorkerBridge<UploadWorker>,
+    response: Rc<RefCell<Option<oneshot::Sender<WorkerResponse>>>>,
+}
+
+impl UploadWorkerPool {
+    /// Spawn a worker per CPU core, leaving one core for the UI thread.
+    pub fn new() -> Self {
+        let cores = web_sys::window()
+            .map(|window| window.navigator().hardware_concurrency() as usize)
+            .unwrap_or(1);
+        let size = cores.saturating_sub(1).clamp(1, MAX_WORKERS);
+
+        let workers = (0..size)
+            .map(|_| {
+                let response: Rc<RefCell<Option<oneshot::Sender<WorkerResponse>>>> = Rc::new(RefCell::new(None));
+                let bridge = {
+                    let response = response.clone();
+                    UploadWorker::spawner()
+                        .callback(move |result| {
+                            if let Some(sender) = response.borrow_mut().take() {
+                                let _ = sender.send(result);
+                            }
+                        })
+                        .spawn(UPLOAD_WORKER_PATH)
+                };
+
+                PooledWorker { bridge, response }
+            })
+            .collect();
+
+        Self {
+            workers,
+            idle: RefCell::new((0..size).rev().collect()),
+            waiting: RefCell::new(VecDeque::new()),
+        }
+    }
+
+    /// Wait until a worker is idle, and reserve it. Workers are handed out in the order they were asked for.
+    pub async fn acquire(self: &Rc<Self>) -> Result<UploadWorkerHandle> {
+        let index = self.idle.borrow_mut().pop();
+        let index = match index {
+            Some(index) => index,
+            None => {
+                let (sender, receiver) = oneshot::channel();
+                self.waiting.borrow_mut().push_back(sender);
+                receiver.await.map_err(|_| anyhow!("Upload worker pool was dropped"))?
+            }
+        };
+
+        Ok(UploadWorkerHandle {
+            pool: self.clone(),
+            index,
+        })
+    }
+
+    fn release(&self, index: usize) {
+        // Hand the worker over to the first one waiting that is still interested.
+        while let Some(sender) = self.waiting.borrow_mut().pop_front() {
+            if sender.send(index).is_ok() {
+                return;
+            }
+        }
+
+        self.idle.borrow_mut().push(index);
+    }
+}
+
+/// A reserved worker. The worker is released when this is dropped.
+pub struct UploadWorkerHandle {
+    pool: Rc<UploadWorkerPool>,
+    index: usize,
+}
+
+impl UploadWorkerHandle {
+    /// Process and encrypt the bytes of an image file in the worker.
+    pub async fn encrypt_photo(&self, bytes: Vec<u8>) -> Result<EncryptedPhoto> {
+        let worker = &self.pool.workers[self.index];
+        let (sender, receiver) = oneshot::channel();
+        *worker.response.borrow_mut() = Some(sender);
+        worker.bridge.send(bytes);
+
+        receiver
+            .await
+            .map_err(|_| anyhow!("Upload worker stopped responding"))?
+            .map_err(|error| anyhow!(error))
+    }
+}
+
+impl Drop for UploadWorkerHandle {
+    fn drop(&mut self) {
+        self.pool.release(self.index);
+    }
+}
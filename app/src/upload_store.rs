@@ -0,0 +1,217 @@
+use crate::models::UploadQueueItem;
+use anyhow::{anyhow, Result};
+use js_sys::{Array, Object, Promise, Reflect};
+use std::future::Future;
+use wasm_bindgen::{prelude::wasm_bindgen, JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{DataTransfer, File};
+
+// Persists the upload queue in IndexedDB, so it survives a page reload.
+// File contents are not stored; only file system handles, in browsers that support them.
+// Each item is stored with the ID of the user that queued it, and only restored for that user.
+#[wasm_bindgen(inline_js = r#"
+const DB_NAME = "upholi";
+const STORE_NAME = "uploads";
+let db = null;
+
+function openDb() {
+  db ??= new Promise((resolve, reject) => {
+    const request = indexedDB.open(DB_NAME, 1);
+    request.onupgradeneeded = () => request.result.createObjectStore(STORE_NAME, { keyPath: "id" });
+    request.onsuccess = () => resolve(request.result);
+    request.onerror = () => reject(request.error);
+  });
+  return db;
+}
+
+async function run(mode, action) {
+  const db = await openDb();
+  return new Promise((resolve, reject) => {
+    const transaction = db.transaction(STORE_NAME, mode);
+    const request = action(transaction.objectStore(STORE_NAME));
+    transaction.oncomplete = () => resolve(request.result);
+    transaction.onerror = () => reject(transaction.error);
+  });
+}
+
+export function putUpload(record) {
+  return run("readwrite", (store) => store.put(record));
+}
+
+export function deleteUpload(id) {
+  return run("readwrite", (store) => store.delete(id));
+}
+
+export function getUploads() {
+  return run("readonly", (store) => store.getAll());
+}
+
+export function supportsFilePicker() {
+  return "showOpenFilePicker" in window;
+}
+
+export async function pickFiles() {
+  try {
+    const handles = await window.showOpenFilePicker({
+      multiple: true,
+      types: [{ description: "Photos", accept: { "image/jpeg": [".jpg", ".jpeg"] } }],
+    });
+    return Promise.all(handles.map(async (handle) => ({ file: await handle.getFile(), handle })));
+  } catch (error) {
+    if (error.name === "AbortError") {
+      return [];
+    }
+    throw error;
+  }
+}
+
+// Handles must be requested while handling the drop event, so this returns a promise rather than being async.
+export function getDroppedFiles(dataTransfer) {
+  const items = Array.from(dataTransfer.items || []).filter((item) => item.kind === "file");
+  if (items.length === 0 || !items[0].getAsFileSystemHandle) {
+    return Promise.resolve(Array.from(dataTransfer.files).map((file) => ({ file, handle: null })));
+  }
+
+  const files = items.map((item) => item.getAsFile());
+  return Promise.all(items.map((item) => item.getAsFileSystemHandle())).then((handles) =>
+    files.map((file, i) => ({ file, handle: handles[i] && handles[i].kind === "file" ? handles[i] : null }))
+  );
+}
+
+// Asking for permission requires a user gesture, such as a click.
+export async function getFileFromHandle(handle, requestPermission) {
+  let permission = await handle.queryPermission({ mode: "read" });
+  if (permission !== "granted" && requestPermission) {
+    permission = await handle.requestPermission({ mode: "read" });
+  }
+  return permission === "granted" ? handle.getFile() : null;
+}
+"#)]
+extern "C" {
+    #[wasm_bindgen(js_name = putUpload)]
+    fn put_upload(record: &JsValue) -> Promise;
+    #[wasm_bindgen(js_name = deleteUpload)]
+    fn delete_upload(id: &str) -> Promise;
+    #[wasm_bindgen(js_name = getUploads)]
+    fn get_uploads() -> Promise;
+    /// Whether the browser has a file picker that provides file system handles.
+    #[wasm_bindgen(js_name = supportsFilePicker)]
+    pub fn supports_file_picker() -> bool;
+    #[wasm_bindgen(js_name = pickFiles)]
+    fn pick_files_js() -> Promise;
+    #[wasm_bindgen(js_name = getDroppedFiles)]
+    fn get_dropped_files_js(data_transfer: &DataTransfer) -> Promise;
+    #[wasm_bindgen(js_name = getFileFromHandle)]
+    fn get_file_from_handle(handle: &JsValue, request_permission: bool) -> Promise;
+}
+
+/// A file selected by the user, with its file system handle if the browser provides one.
+pub struct SelectedFile {
+    pub file: File,
+    pub handle: Option<JsValue>,
+}
+
+/// An upload queue item as stored, without its file and user ID.
+pub struct StoredUpload {
+    pub id: String,
+    pub filename: String,
+    pub size: f64,
+    pub last_modified: f64,
+    pub target_album_id: Option<String>,
+    pub handle: Option<JsValue>,
+}
+
+/// Store an item of the upload queue of given user, or update it if it is stored already.
+pub async fn save(user_id: &str, item: &UploadQueueItem) -> Result<()> {
+    let record = Object::new();
+    set(&record, "id", &item.id.as_str().into())?;
+    set(&record, "userId", &user_id.into())?;
+    set(&record, "filename", &item.filename.as_str().into())?;
+    set(&record, "size", &item.size.into())?;
+    set(&record, "lastModified", &item.last_modified.into())?;
+    set(&record, "albumId", &item.target_album_id.as_deref().into())?;
+    set(&record, "handle", item.handle.as_ref().unwrap_or(&JsValue::NULL))?;
+
+    await_promise(put_upload(&record)).await?;
+    Ok(())
+}
+
+pub async fn delete(id: &str) -> Result<()> {
+    await_promise(delete_upload(id)).await?;
+    Ok(())
+}
+
+/// Get the stored items of the upload queue of given user.
+/// Items of other users are deleted, as they cannot be uploaded anymore once another user logged in.
+pub async fn load(user_id: &str) -> Result<Vec<StoredUpload>> {
+    let records: Array = await_promise(get_uploads()).await?.unchecked_into();
+    let mut user_records = vec![];
+    for record in records.iter() {
+        if get(&record, "userId")?.as_string().as_deref() == Some(user_id) {
+            user_records.push(record);
+        } else {
+            let id = get(&record, "id")?.as_string().unwrap_or_default();
+            delete(&id).await?;
+        }
+    }
+
+    user_records
+        .iter()
+        .map(|record| {
+            Ok(StoredUpload {
+                id: get(record, "id")?.as_string().unwrap_or_default(),
+                filename: get(record, "filename")?.as_string().unwrap_or_default(),
+                size: get(record, "size")?.as_f64().unwrap_or_default(),
+                last_modified: get(record, "lastModified")?.as_f64().unwrap_or_default(),
+                target_album_id: get(record, "albumId")?.as_string(),
+                handle: Some(get(record, "handle")?).filter(|handle| !handle.is_null() && !handle.is_undefined()),
+            })
+        })
+        .collect()
+}
+
+/// Let the user pick files using the browser's file picker. See `supports_file_picker`.
+pub async fn pick_files() -> Result<Vec<SelectedFile>> {
+    let selected = await_promise(pick_files_js()).await?;
+    to_selected_files(selected)
+}
+
+/// Get the files of a drop event. Must be called while handling the event,
+/// so this starts getting them right away rather than when the future is first polled.
+pub fn get_dropped_files(data_transfer: &DataTransfer) -> impl Future<Output = Result<Vec<SelectedFile>>> {
+    let dropped = get_dropped_files_js(data_transfer);
+    async move { to_selected_files(await_promise(dropped).await?) }
+}
+
+/// Get the file of a file system handle, if the user granted access to it.
+pub async fn get_file(handle: &JsValue, request_permission: bool) -> Result<Option<File>> {
+    let file = await_promise(get_file_from_handle(handle, request_permission)).await?;
+    Ok(file.dyn_into::<File>().ok())
+}
+
+fn to_selected_files(selected: JsValue) -> Result<Vec<SelectedFile>> {
+    let selected: Array = selected.unchecked_into();
+    selected
+        .iter()
+        .map(|selected| {
+            let file = get(&selected, "file")?
+                .dyn_into::<File>()
+                .map_err(|_| anyhow!("Selected item is not a file"))?;
+            let handle = Some(get(&selected, "handle")?).filter(|handle| !handle.is_null());
+            Ok(SelectedFile { file, handle })
+        })
+        .collect()
+}
+
+async fn await_promise(promise: Promise) -> Result<JsValue> {
+    JsFuture::from(promise).await.map_err(|error| anyhow!("{error:?}"))
+}
+
+fn get(object: &JsValue, key: &str) -> Result<JsValue> {
+    Reflect::get(object, &key.into()).map_err(|error| anyhow!("{error:?}"))
+}
+
+fn set(object: &Object, key: &str, value: &JsValue) -> Result<()> {
+    Reflect::set(object, &key.into(), value).map_err(|error| anyhow!("{error:?}"))?;
+    Ok(())
+}
@@ -0,0 +1,23 @@
+use gloo::worker::{HandlerId, Worker, WorkerScope};
+use upholi_core::client::EncryptedPhoto;
+
+/// Processes and encrypts photos to upload, so this does not block the UI thread.
+/// Receives the bytes of an image file, and responds with the encrypted photo or an error message.
+pub struct UploadWorker;
+
+impl Worker for UploadWorker {
+    type Message = ();
+    type Input = Vec<u8>;
+    type Output = Result<EncryptedPhoto, String>;
+
+    fn create(_scope: &WorkerScope<Self>) -> Self {
+        Self
+    }
+
+    fn update(&mut self, _scope: &WorkerScope<Self>, _msg: Self::Message) {}
+
+    fn received(&mut self, scope: &WorkerScope<Self>, bytes: Self::Input, id: HandlerId) {
+        let result = EncryptedPhoto::try_from_slice(&bytes).map_err(|error| format!("{error:?}"));
+        scope.respond(id, result);
+    }
+}
@@ -0,0 +1,250 @@
+use anyhow::{anyhow, Result};
+use clap::{Parser, Subcommand};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use upholi_core::client::{Client, EncryptedPhoto, PhotoUploadResult, UploadBatch};
+use upholi_core::{
+    api_client::ApiClient,
+    hashing,
+    keys::MemoryKeyStore,
+    models::{Album, Photo},
+};
+use upholi_lib::PhotoVariant;
+use watch::WatchOptions;
+
+mod watch;
+
+/// File extensions of images that are picked up when uploading a directory.
+const IMAGE_EXTENSIONS: [&str; 9] = ["jpg", "jpeg", "png", "gif", "webp", "bmp", "tif", "tiff", "tga"];
+/// Add uploaded photos to the library after this many, so they show up before a large upload is done.
+const UPLOAD_BATCH_SIZE: usize = 25;
+
+#[derive(Parser)]
+#[command(version, about = "upholi command-line client")]
+struct Cli {
+    /// URL of the Upholi server, e.g. https://photos.example.com
+    #[arg(long, env = "UPHOLI_URL")]
+    url: String,
+    #[arg(long, env = "UPHOLI_USERNAME")]
+    username: String,
+    /// Prefer the environment variable, so the password does not show up in the process list.
+    #[arg(long, env = "UPHOLI_PASSWORD", hide_env_values = true)]
+    password: String,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Upload all photos in a directory and its subdirectories. Photos that were uploaded before are skipped.
+    Upload { directory: PathBuf },
+    /// Download the original photos of albums, one subdirectory per album. Photos that exist already are skipped.
+    Download {
+        directory: PathBuf,
+        /// Only download the album with this title.
+        #[arg(long)]
+        album: Option<String>,
+    },
+    /// Keep uploading new photos that appear in one or more directories, until stopped.
+    Watch {
+        #[arg(required = true)]
+        directories: Vec<PathBuf>,
+        /// File to remember uploaded files in, so they are not read again after a restart.
+        #[arg(long, env = "UPHOLI_STATE", default_value = "upholi-state.json")]
+        state: PathBuf,
+        /// Seconds between looking for new files.
+        #[arg(long, default_value_t = 60)]
+        interval: u64,
+        /// Add photos in a subdirectory of a watched directory to an album named after that subdirectory.
+        #[arg(long)]
+        albums: bool,
+    },
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+
+    if let Command::Watch { directories, .. } = &cli.command {
+        watch::check_directories(directories)?;
+    }
+
+    let api_client = ApiClient::new(&format!("{}/api", cli.url.trim_end_matches('/')));
+    let key_store = MemoryKeyStore::default();
+    let client = Client::new(&api_client, &key_store);
+    client.login(&cli.username, &cli.password).await?;
+
+    match cli.command {
+        Command::Upload { directory } => upload(&client, &directory).await,
+        Command::Download { directory, album } => download(&client, &directory, album.as_deref()).await,
+        Command::Watch {
+            directories,
+            state,
+            interval,
+            albums,
+        } => {
+            let options = WatchOptions {
+                directories,
+                state_path: state,
+                interval: Duration::from_secs(interval),
+                albums,
+            };
+            watch::watch(&client, &cli.username, &cli.password, &options).await
+        }
+    }
+}
+
+async fn upload(client: &Client<'_>, directory: &Path) -> Result<()> {
+    let mut paths = vec![];
+    let skipped_directories = find_images(directory, &mut paths)?;
+    paths.sort();
+
+    let mut batch = client.begin_upload_batch().await?;
+    let mut failed = 0;
+    for path in paths {
+        let result = upload_file(client, &mut batch, &path).await;
+        match result {
+            Ok(result) if result.skipped => println!("Skipped {}, already uploaded", path.display()),
+            Ok(_) => println!("Uploaded {}", path.display()),
+            Err(error) => {
+                failed += 1;
+                eprintln!("Failed to upload {}: {error}", path.display());
+            }
+        }
+
+        if batch.len() >= UPLOAD_BATCH_SIZE {
+            client.commit_upload_batch(batch).await?;
+            batch = client.begin_upload_batch().await?;
+        }
+    }
+    client.commit_upload_batch(batch).await?;
+
+    match (failed, skipped_directories) {
+        (0, 0) => Ok(()),
+        (0, _) => Err(anyhow!("{skipped_directories} directory(s) could not be read")),
+        _ => Err(anyhow!("{failed} photo(s) failed to upload")),
+    }
+}
+
+/// Upload an image file as part of given batch, unless a photo with the same hash was uploaded before.
+pub(crate) async fn upload_file(
+    client: &Client<'_>,
+    batch: &mut UploadBatch,
+    path: &Path,
+) -> Result<PhotoUploadResult> {
+    let bytes = tokio::fs::read(path).await?;
+
+    // Check for duplicates before the expensive processing of the image.
+    let photo_hash = hashing::compute_sha256_hash(&bytes)?;
+    if let Some(photo_id) = client.find_photo_by_hash(&photo_hash).await? {
+        return Ok(PhotoUploadResult {
+            skipped: true,
+            photo_id,
+        });
+    }
+
+    let mut encrypted_photo = EncryptedPhoto::try_from_slice(&bytes)?;
+    if let Some(file_name) = path.file_name() {
+        encrypted_photo.photo.file_name = file_name.to_string_lossy().into_owned();
+    }
+    client.upload_photo_in_batch(batch, encrypted_photo, None).await
+}
+
+/// Recursively collect the paths of all images in given directory.
+/// Subdirectories that cannot be read are skipped with a warning, so they do not stop the others.
+/// Returns the number of skipped subdirectories.
+pub(crate) fn find_images(directory: &Path, paths: &mut Vec<PathBuf>) -> Result<usize> {
+    let mut skipped = 0;
+    for entry in std::fs::read_dir(directory)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            match find_images(&path, paths) {
+                Ok(skipped_below) => skipped += skipped_below,
+                Err(error) => {
+                    eprintln!("Skipping {}: {error}", path.display());
+                    skipped += 1;
+                }
+            }
+        } else if is_image(&path) {
+            paths.push(path);
+        }
+    }
+
+    Ok(skipped)
+}
+
+fn is_image(path: &Path) -> bool {
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some(extension) => IMAGE_EXTENSIONS.contains(&extension.to_lowercase().as_str()),
+        None => false,
+    }
+}
+
+async fn download(client: &Client<'_>, directory: &Path, album_title: Option<&str>) -> Result<()> {
+    let albums: Vec<Album> = client
+        .get_albums()
+        .await?
+        .into_iter()
+        .filter(|album| album_title.is_none_or(|title| album.title == title))
+        .collect();
+
+    if let Some(album_title) = album_title {
+        if albums.is_empty() {
+            return Err(anyhow!("Album '{album_title}' not found"));
+        }
+    }
+
+    for album in albums {
+        let album_directory = directory.join(to_file_name(&album.title));
+        tokio::fs::create_dir_all(&album_directory).await?;
+
+        for photo_id in &album.photos {
+            let photo = client.get_photo(photo_id).await?;
+            let extension = photo.content_type.trim_start_matches("image/");
+            let path = album_directory.join(format!("{photo_id}.{extension}"));
+            write_details(&album_directory, &photo).await?;
+            if tokio::fs::try_exists(&path).await? {
+                continue;
+            }
+
+            let bytes = client.get_photo_bytes(&photo, PhotoVariant::Original).await?;
+            tokio::fs::write(&path, bytes).await?;
+            println!("Downloaded {}", path.display());
+        }
+    }
+
+    Ok(())
+}
+
+/// Write the caption, description and tags of a photo to a JSON file next to it, if it has any.
+/// The file is written on every download, as they may have been edited since,
+/// and a file written by an earlier download is removed once they have all been cleared.
+async fn write_details(directory: &Path, photo: &Photo) -> Result<()> {
+    let path = directory.join(format!("{}.json", photo.id));
+
+    if photo.caption.is_empty() && photo.description.is_empty() && photo.tags.is_empty() {
+        return match tokio::fs::remove_file(&path).await {
+            Err(error) if error.kind() != std::io::ErrorKind::NotFound => Err(error.into()),
+            _ => Ok(()),
+        };
+    }
+
+    let details = serde_json::json!({
+        "caption": photo.caption,
+        "description": photo.description,
+        "tags": photo.tags,
+    });
+    tokio::fs::write(&path, serde_json::to_vec_pretty(&details)?).await?;
+    Ok(())
+}
+
+/// Replace characters that are not allowed in file names on common file systems.
+fn to_file_name(title: &str) -> String {
+    title
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c => c,
+        })
+        .collect()
+}
@@ -0,0 +1,315 @@
+use crate::{find_images, upload_file, UPLOAD_BATCH_SIZE};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use upholi_core::client::Client;
+
+/// Files modified more recently than this may still be being written, and are picked up in a later pass.
+const SETTLE_TIME: Duration = Duration::from_secs(10);
+
+/// Options of the watch daemon.
+pub struct WatchOptions {
+    pub directories: Vec<PathBuf>,
+    pub state_path: PathBuf,
+    pub interval: Duration,
+    /// Add photos in a subdirectory of a watched directory to an album named after that subdirectory.
+    pub albums: bool,
+}
+
+/// Files that were uploaded before, so they are not read and uploaded again after a restart.
+#[derive(Default, Serialize, Deserialize)]
+struct WatchState {
+    files: HashMap<PathBuf, FileState>,
+    /// IDs of uploaded photos by the title of the album they still have to be added to.
+    #[serde(default)]
+    pending_albums: HashMap<String, Vec<String>>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq)]
+struct FileState {
+    size: u64,
+    modified: u64,
+    photo_id: String,
+}
+
+impl WatchState {
+    fn load(path: &Path) -> Result<Self> {
+        match std::fs::read(path) {
+            Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    /// Write the state to a temporary file first, so it is never left half-written.
+    fn save(&self, path: &Path) -> Result<()> {
+        let temp_path = path.with_extension("tmp");
+        std::fs::write(&temp_path, serde_json::to_vec(self)?)?;
+        std::fs::rename(&temp_path, path)?;
+        Ok(())
+    }
+
+    fn is_uploaded(&self, path: &Path, size: u64, modified: u64) -> bool {
+        match self.files.get(path) {
+            Some(file) => file.size == size && file.modified == modified,
+            None => false,
+        }
+    }
+}
+
+/// Upload new images in the watched directories, every interval, until the process is stopped.
+pub async fn watch(client: &Client<'_>, username: &str, password: &str, options: &WatchOptions) -> Result<()> {
+    let mut state = WatchState::load(&options.state_path)?;
+    println!(
+        "Watching {} director(y/ies), {} file(s) uploaded before",
+        options.directories.len(),
+        state.files.len()
+    );
+
+    loop {
+        if let Err(error) = run_pass(client, username, password, options, &mut state).await {
+            eprintln!(
+                "Import failed, retrying in {} seconds: {error}",
+                options.interval.as_secs()
+            );
+        }
+
+        tokio::time::sleep(options.interval).await;
+    }
+}
+
+async fn run_pass(
+    client: &Client<'_>,
+    username: &str,
+    password: &str,
+    options: &WatchOptions,
+    state: &mut WatchState,
+) -> Result<()> {
+    let new_files = find_new_files(options, state)?;
+    if new_files.is_empty() && state.pending_albums.is_empty() {
+        return Ok(());
+    }
+
+    // Sessions expire, so log in again when needed.
+    if !client.is_authenticated().await? {
+        client.login(username, password).await?;
+    }
+    client.clear_cache();
+
+    let mut batch = client.begin_upload_batch().await?;
+    for (directory, path, size, modified) in new_files {
+        let result = match upload_file(client, &mut batch, &path).await {
+            Ok(result) => result,
+            Err(error) => {
+                eprintln!("Failed to upload {}: {error}", path.display());
+                continue;
+            }
+        };
+
+        match result.skipped {
+            true => println!("Skipped {}, already uploaded", path.display()),
+            false => println!("Uploaded {}", path.display()),
+        }
+
+        // Album assignments are saved along with the file, so they are not lost if adding to albums fails
+        if options.albums {
+            if let Some(album_title) = get_album_title(directory, &path) {
+                state
+                    .pending_albums
+                    .entry(album_title)
+                    .or_default()
+                    .push(result.photo_id.clone());
+            }
+        }
+
+        state.files.insert(
+            path,
+            FileState {
+                size,
+                modified,
+                photo_id: result.photo_id,
+            },
+        );
+        state.save(&options.state_path)?;
+
+        if batch.len() >= UPLOAD_BATCH_SIZE {
+            client.commit_upload_batch(batch).await?;
+            batch = client.begin_upload_batch().await?;
+        }
+    }
+    client.commit_upload_batch(batch).await?;
+
+    add_to_albums(client, state, &options.state_path).await
+}
+
+/// Find images that are not in the state yet, or that changed since they were uploaded.
+fn find_new_files<'o>(options: &'o WatchOptions, state: &WatchState) -> Result<Vec<(&'o Path, PathBuf, u64, u64)>> {
+    let settled_before = SystemTime::now() - SETTLE_TIME;
+    let mut new_files = vec![];
+
+    for directory in &options.directories {
+        // Directories that cannot be read are skipped, like unreadable files below, and read again in the next pass
+        let mut paths = vec![];
+        if let Err(error) = find_images(directory, &mut paths) {
+            eprintln!("Skipping {}: {error}", directory.display());
+            continue;
+        }
+        paths.sort();
+
+        for path in paths {
+            // Files can vanish or become unreadable between listing and reading them
+            let (size, modified) = match get_size_and_modified(&path) {
+                Ok(size_and_modified) => size_and_modified,
+                Err(error) => {
+                    eprintln!("Skipping {}: {error}", path.display());
+                    continue;
+                }
+            };
+            if modified > settled_before {
+                continue;
+            }
+
+            let modified = modified.duration_since(UNIX_EPOCH)?.as_secs();
+            if !state.is_uploaded(&path, size, modified) {
+                new_files.push((directory.as_path(), path, size, modified));
+            }
+        }
+    }
+
+    Ok(new_files)
+}
+
+fn get_size_and_modified(path: &Path) -> Result<(u64, SystemTime)> {
+    let metadata = std::fs::metadata(path)?;
+    Ok((metadata.len(), metadata.modified()?))
+}
+
+/// Get the name of the top-level subdirectory of the watched directory that contains given file, if any.
+fn get_album_title(directory: &Path, path: &Path) -> Option<String> {
+    let relative_path = path.strip_prefix(directory).ok()?;
+    let mut components = relative_path.components();
+    let first = components.next()?;
+
+    // A file directly in the watched directory has only one component.
+    components.next()?;
+    Some(first.as_os_str().to_string_lossy().into_owned())
+}
+
+/// Add uploaded photos to the albums they are pending for, creating albums that do not exist yet.
+/// Each album is removed from the pending albums in the state once its photos are added.
+async fn add_to_albums(client: &Client<'_>, state: &mut WatchState, state_path: &Path) -> Result<()> {
+    if state.pending_albums.is_empty() {
+        return Ok(());
+    }
+
+    let albums = client.get_albums().await?;
+    let titles: Vec<String> = state.pending_albums.keys().cloned().collect();
+    for title in titles {
+        let photo_ids = &state.pending_albums[&title];
+        match albums.iter().find(|album| album.title == title) {
+            Some(album) => client.add_photos_to_album(&album.id, photo_ids).await?,
+            None => {
+                client.create_album(&title, photo_ids.clone()).await?;
+                println!("Created album '{title}'");
+            }
+        }
+
+        state.pending_albums.remove(&title);
+        state.save(state_path)?;
+    }
+
+    Ok(())
+}
+
+/// Check that all watched directories exist before starting.
+pub fn check_directories(directories: &[PathBuf]) -> Result<()> {
+    match directories.iter().find(|directory| !directory.is_dir()) {
+        Some(directory) => Err(anyhow!("'{}' is not a directory", directory.display())),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn album_title_is_top_level_subdirectory() {
+        let directory = Path::new("/photos");
+
+        assert_eq!(
+            get_album_title(directory, Path::new("/photos/Holiday/img.jpg")),
+            Some("Holiday".to_string())
+        );
+        assert_eq!(
+            get_album_title(directory, Path::new("/photos/Holiday/Day 1/img.jpg")),
+            Some("Holiday".to_string())
+        );
+    }
+
+    #[test]
+    fn no_album_title_outside_subdirectory() {
+        let directory = Path::new("/photos");
+
+        assert_eq!(get_album_title(directory, Path::new("/photos/img.jpg")), None);
+        assert_eq!(get_album_title(directory, Path::new("/other/Holiday/img.jpg")), None);
+    }
+
+    #[test]
+    fn uploaded_only_if_unchanged() {
+        let path = Path::new("/photos/img.jpg");
+        let mut state = WatchState::default();
+        assert!(!state.is_uploaded(path, 100, 1000));
+
+        state.files.insert(
+            path.to_path_buf(),
+            FileState {
+                size: 100,
+                modified: 1000,
+                photo_id: "photo".to_string(),
+            },
+        );
+
+        assert!(state.is_uploaded(path, 100, 1000));
+        assert!(!state.is_uploaded(path, 101, 1000));
+        assert!(!state.is_uploaded(path, 100, 1001));
+        assert!(!state.is_uploaded(Path::new("/photos/other.jpg"), 100, 1000));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unreadable_directories_are_skipped() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let root = std::env::temp_dir().join(format!("upholi-watch-test-{}", std::process::id()));
+        let readable = root.join("Readable");
+        let unreadable = root.join("Unreadable");
+        for directory in [&readable, &unreadable] {
+            std::fs::create_dir_all(directory).unwrap();
+            let file = std::fs::File::create(directory.join("img.jpg")).unwrap();
+            file.set_modified(SystemTime::now() - SETTLE_TIME * 2).unwrap();
+        }
+        std::fs::set_permissions(&unreadable, std::fs::Permissions::from_mode(0o000)).unwrap();
+        // Permissions do not apply to root
+        let permissions_apply = std::fs::read_dir(&unreadable).is_err();
+
+        let options = WatchOptions {
+            directories: vec![root.join("Missing"), root.clone()],
+            state_path: root.join("state.json"),
+            interval: Duration::from_secs(60),
+            albums: false,
+        };
+        let new_files = find_new_files(&options, &WatchState::default());
+
+        std::fs::set_permissions(&unreadable, std::fs::Permissions::from_mode(0o755)).unwrap();
+        std::fs::remove_dir_all(&root).unwrap();
+
+        let paths: Vec<PathBuf> = new_files.unwrap().into_iter().map(|(_, path, _, _)| path).collect();
+        assert!(paths.contains(&readable.join("img.jpg")));
+        if permissions_apply {
+            assert_eq!(paths, vec![readable.join("img.jpg")]);
+        }
+    }
+}
@@ -0,0 +1,492 @@
+use anyhow::{anyhow, Result};
+use reqwest::{RequestBuilder, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use upholi_lib::http::request::{
+    AuthenticateUserRequest, AuthorizeShareRequest, CreateUserRequest, DeleteManyRequest, TrashEntryRequest,
+    TrashRequest, UpsertShareRequest,
+};
+use upholi_lib::http::response::{
+    AuthenticateUserResult, CreatedResult, ErrorResponse, TrashEntryResult, UsageResult, UserResult,
+};
+use upholi_lib::http::{CSRF_COOKIE_NAME, CSRF_HEADER_NAME};
+
+use crate::models::EncryptedItem;
+
+/// Client for all HTTP calls to the API.
+///
+/// In a browser, cookies are handled by the browser. Elsewhere, the client keeps cookies itself,
+/// so it can stay logged in for its lifetime.
+pub struct ApiClient {
+    base_url: String,
+    client: reqwest::Client,
+    #[cfg(not(target_arch = "wasm32"))]
+    cookie_jar: std::sync::Arc<reqwest::cookie::Jar>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct File {
+    pub id: String,
+    pub bytes: Vec<u8>,
+}
+
+/// Error response of the API.
+#[derive(Debug)]
+pub struct ApiError {
+    pub response: ErrorResponse,
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.response.message)
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+impl ApiClient {
+    #[cfg(target_arch = "wasm32")]
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.into(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn new(base_url: &str) -> Self {
+        let cookie_jar = std::sync::Arc::new(reqwest::cookie::Jar::default());
+        let client = reqwest::Client::builder()
+            .cookie_provider(cookie_jar.clone())
+            .build()
+            .expect("Failed to initialize HTTP client");
+
+        Self {
+            base_url: base_url.into(),
+            client,
+            cookie_jar,
+        }
+    }
+
+    /// Start a POST request, including the CSRF token.
+    fn post(&self, url: &str) -> RequestBuilder {
+        self.with_csrf_token(self.client.post(url))
+    }
+
+    /// Start a DELETE request, including the CSRF token.
+    fn delete(&self, url: &str) -> RequestBuilder {
+        self.with_csrf_token(self.client.delete(url))
+    }
+
+    /// Make sure the server has set a CSRF token cookie, which a new client does not have yet.
+    /// The server sets it on any API request, including a GET request that needs no token.
+    async fn ensure_csrf_token(&self) -> Result<()> {
+        if self.get_cookie(CSRF_COOKIE_NAME).is_none() {
+            self.get_user().await?;
+        }
+
+        Ok(())
+    }
+
+    /// Send back the CSRF token the server set as cookie, which the server requires for requests that change state.
+    fn with_csrf_token(&self, request: RequestBuilder) -> RequestBuilder {
+        match self.get_cookie(CSRF_COOKIE_NAME) {
+            Some(csrf_token) => request.header(CSRF_HEADER_NAME, csrf_token),
+            None => request,
+        }
+    }
+
+    pub async fn register(&self, body: &CreateUserRequest) -> Result<()> {
+        self.ensure_csrf_token().await?;
+
+        let url = format!("{}/user", self.base_url).to_owned();
+        let response = self.post(&url).json(&body).send().await?;
+
+        if response.status() == StatusCode::CREATED {
+            Ok(())
+        } else {
+            Err(Self::error(response, "Failed to register user").await)
+        }
+    }
+
+    pub async fn login(&self, username: &str, password: &str) -> Result<AuthenticateUserResult> {
+        self.ensure_csrf_token().await?;
+
+        let url = format!("{}/user/auth", self.base_url).to_owned();
+        let body = AuthenticateUserRequest {
+            username: username.into(),
+            password: password.into(),
+        };
+        let response = self.post(&url).json(&body).send().await?;
+
+        if response.status() == StatusCode::OK {
+            Ok(response.json().await?)
+        } else {
+            Err(Self::error(response, "Login failed").await)
+        }
+    }
+
+    pub async fn clear_password_reset(&self) -> Result<()> {
+        let url = format!("{}/user/password-reset", self.base_url).to_owned();
+        let response = self.delete(&url).send().await?;
+
+        if response.status() == StatusCode::OK {
+            Ok(())
+        } else {
+            Err(Self::error(response, "Failed to clear password reset").await)
+        }
+    }
+
+    pub async fn get_user(&self) -> Result<bool> {
+        let url = format!("{}/user", self.base_url).to_owned();
+        let response = self.client.get(&url).send().await?;
+        Ok(response.status() == StatusCode::OK)
+    }
+
+    /// Get the ID of the current user.
+    pub async fn get_user_id(&self) -> Result<String> {
+        let url = format!("{}/user", self.base_url).to_owned();
+        let response = self.client.get(&url).send().await?;
+
+        if response.status() == StatusCode::OK {
+            let user: UserResult = response.json().await?;
+            Ok(user.id)
+        } else {
+            Err(Self::error(response, "Failed to get user").await)
+        }
+    }
+
+    pub async fn get_usage(&self) -> Result<UsageResult> {
+        let url = format!("{}/user/usage", self.base_url).to_owned();
+        let response = self.client.get(&url).send().await?;
+
+        if response.status() == StatusCode::OK {
+            Ok(response.json().await?)
+        } else {
+            Err(Self::error(response, "Failed to get usage").await)
+        }
+    }
+
+    pub async fn is_authorized_for_share(&self, share_id: &str) -> Result<bool> {
+        let url = format!("{}/share/{share_id}/auth", self.base_url).to_owned();
+        let response = self.client.get(&url).send().await?;
+        Ok(response.status() == StatusCode::OK)
+    }
+
+    pub async fn authorize_share(&self, share_id: &str, password: &str) -> Result<bool> {
+        let url = format!("{}/share/{share_id}/auth", self.base_url).to_owned();
+        let body = AuthorizeShareRequest {
+            password: password.into(),
+        };
+        let response = self.post(&url).json(&body).send().await?;
+
+        if response.status() == StatusCode::OK {
+            Ok(true)
+        } else if response.status() == StatusCode::UNAUTHORIZED {
+            Ok(false)
+        } else {
+            Err(Self::error(response, "Authentication failed").await)
+        }
+    }
+
+    /// Get the IDs of all items of the current user.
+    pub async fn get_item_ids(&self) -> Result<Vec<String>> {
+        let url = format!("{}/item", self.base_url).to_owned();
+        let response = self.client.get(&url).send().await?;
+
+        if response.status() == StatusCode::OK {
+            Ok(response.json().await?)
+        } else {
+            Err(Self::error(response, "Failed to get item IDs").await)
+        }
+    }
+
+    pub async fn get_item(&self, id: &str) -> Result<Option<EncryptedItem>> {
+        let url = format!("{}/item/{id}", self.base_url).to_owned();
+        let response = self.client.get(&url).send().await?;
+
+        if response.status() == StatusCode::OK {
+            Ok(Some(response.json().await?))
+        } else if response.status() == StatusCode::NOT_FOUND {
+            Ok(None)
+        } else {
+            Err(Self::error(response, "Failed to get item").await)
+        }
+    }
+
+    /// Create an item with an ID chosen by the server, and return that ID.
+    pub async fn create_item(&self, body: &EncryptedItem) -> Result<String> {
+        let url = format!("{}/item", self.base_url).to_owned();
+        let response = self.post(&url).json(&body).send().await?;
+
+        if response.status() == StatusCode::CREATED {
+            let result: CreatedResult = response.json().await?;
+            Ok(result.id)
+        } else {
+            Err(Self::error(response, "Failed to create item").await)
+        }
+    }
+
+    pub async fn set_item(&self, id: &str, body: &EncryptedItem) -> Result<()> {
+        let url = format!("{}/item/{id}", self.base_url).to_owned();
+        let response = self.post(&url).json(&body).send().await?;
+
+        if response.status() == StatusCode::OK {
+            Ok(())
+        } else {
+            Err(Self::error(response, "Failed to set item").await)
+        }
+    }
+
+    pub async fn delete_item(&self, id: &str) -> Result<()> {
+        let url = format!("{}/item/{id}", self.base_url).to_owned();
+        let response = self.delete(&url).send().await?;
+
+        let status_code = response.status();
+        if status_code == StatusCode::OK {
+            Ok(())
+        } else {
+            Err(Self::error(response, "Failed to delete item").await)
+        }
+    }
+
+    pub async fn delete_items(&self, ids: Vec<String>) -> Result<()> {
+        let url = format!("{}/item", self.base_url).to_owned();
+        let data = DeleteManyRequest { ids: ids.to_vec() };
+        let response = self.delete(&url).json(&data).send().await?;
+
+        let status_code = response.status();
+        if status_code == StatusCode::OK {
+            Ok(())
+        } else {
+            Err(Self::error(response, "Failed to delete items").await)
+        }
+    }
+
+    pub async fn get_file(&self, id: &str) -> Result<Option<Vec<u8>>> {
+        let url = format!("{}/file/{id}", self.base_url).to_owned();
+        let response = self.client.get(&url).send().await?;
+
+        if response.status() == StatusCode::OK {
+            Ok(Some(response.bytes().await?.to_vec()))
+        } else if response.status() == StatusCode::NOT_FOUND {
+            Ok(None)
+        } else {
+            Err(Self::error(response, "Failed to get file").await)
+        }
+    }
+
+    pub async fn set_files(&self, files: &Vec<File>) -> Result<()> {
+        let url = format!("{}/file", self.base_url).to_owned();
+
+        // Prepare request body
+        let mut multipart_builder = crate::multipart::MultipartBuilder::new();
+        for file in files {
+            multipart_builder = multipart_builder.add_bytes(&file.id, &file.bytes);
+        }
+        let multipart = multipart_builder.build();
+
+        let response = self
+            .post(&url)
+            .body(multipart.body)
+            .header("Content-Type", multipart.content_type)
+            .header("Content-Length", multipart.content_length)
+            .send()
+            .await?;
+
+        if response.status() == StatusCode::OK {
+            let stored_ids: Vec<String> = response.json().await?;
+            if files.iter().all(|file| stored_ids.contains(&file.id)) {
+                Ok(())
+            } else {
+                Err(anyhow!("Not all files were stored"))
+            }
+        } else {
+            Err(Self::error(response, "Failed to set files").await)
+        }
+    }
+
+    pub async fn delete_files(&self, ids: Vec<String>) -> Result<()> {
+        let url = format!("{}/file", self.base_url).to_owned();
+        let data = DeleteManyRequest { ids: ids.to_vec() };
+        let response = self.delete(&url).json(&data).send().await?;
+
+        let status_code = response.status();
+        if status_code == StatusCode::OK {
+            Ok(())
+        } else {
+            Err(Self::error(response, "Failed to delete files").await)
+        }
+    }
+
+    pub async fn get_trash(&self) -> Result<Vec<TrashEntryResult>> {
+        let url = format!("{}/trash", self.base_url).to_owned();
+        let response = self.client.get(&url).send().await?;
+
+        if response.status() == StatusCode::OK {
+            Ok(response.json().await?)
+        } else {
+            Err(Self::error(response, "Failed to get trash").await)
+        }
+    }
+
+    pub async fn add_to_trash(&self, entries: Vec<TrashEntryRequest>) -> Result<()> {
+        let url = format!("{}/trash", self.base_url).to_owned();
+        let data = TrashRequest { entries };
+        let response = self.post(&url).json(&data).send().await?;
+
+        if response.status() == StatusCode::OK {
+            Ok(())
+        } else {
+            Err(Self::error(response, "Failed to move to trash").await)
+        }
+    }
+
+    pub async fn restore_from_trash(&self, ids: Vec<String>) -> Result<()> {
+        let url = format!("{}/trash/restore", self.base_url).to_owned();
+        let data = DeleteManyRequest { ids };
+        let response = self.post(&url).json(&data).send().await?;
+
+        if response.status() == StatusCode::OK {
+            Ok(())
+        } else {
+            Err(Self::error(response, "Failed to restore from trash").await)
+        }
+    }
+
+    pub async fn delete_from_trash(&self, ids: Vec<String>) -> Result<()> {
+        let url = format!("{}/trash", self.base_url).to_owned();
+        let data = DeleteManyRequest { ids };
+        let response = self.delete(&url).json(&data).send().await?;
+
+        if response.status() == StatusCode::OK {
+            Ok(())
+        } else {
+            Err(Self::error(response, "Failed to delete from trash").await)
+        }
+    }
+
+    pub async fn upsert_share(&self, share: UpsertShareRequest) -> Result<()> {
+        let url = format!("{}/share", self.base_url).to_owned();
+        let response = self.post(&url).json(&share).send().await?;
+
+        if response.status() == StatusCode::OK {
+            Ok(())
+        } else {
+            Err(Self::error(response, "Failed to create share").await)
+        }
+    }
+
+    pub async fn delete_share(&self, id: &str) -> Result<()> {
+        let url = format!("{}/share/{id}", self.base_url).to_owned();
+        let response = self.delete(&url).send().await?;
+
+        if response.status() == StatusCode::OK {
+            Ok(())
+        } else {
+            Err(Self::error(response, "Failed to delete share").await)
+        }
+    }
+
+    /// Get the value of a cookie that is readable by scripts.
+    #[cfg(target_arch = "wasm32")]
+    fn get_cookie(&self, name: &str) -> Option<String> {
+        use wasm_bindgen::JsCast;
+
+        let document = web_sys::window()?.document()?;
+        let cookies = document.dyn_into::<web_sys::HtmlDocument>().ok()?.cookie().ok()?;
+        find_cookie(&cookies, name)
+    }
+
+    /// Get the value of a cookie the server set.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn get_cookie(&self, name: &str) -> Option<String> {
+        use reqwest::cookie::CookieStore;
+
+        let url = self.base_url.parse().ok()?;
+        let cookies = self.cookie_jar.cookies(&url)?;
+        find_cookie(cookies.to_str().ok()?, name)
+    }
+
+    /// Get the error of a failed request. Uses the message of the `ErrorResponse` in the body,
+    /// or the fallback message if the body is not an `ErrorResponse`.
+    async fn error(response: Response, fallback: &str) -> anyhow::Error {
+        let status = response.status();
+        match response.json::<ErrorResponse>().await {
+            Ok(response) => ApiError { response }.into(),
+            Err(_) => anyhow!("{fallback} ({status})"),
+        }
+    }
+}
+
+/// Find the value of a cookie in a Cookie header value.
+fn find_cookie(cookies: &str, name: &str) -> Option<String> {
+    cookies.split(';').find_map(|cookie| {
+        let (cookie_name, value) = cookie.trim().split_once('=')?;
+        (cookie_name == name).then(|| value.to_string())
+    })
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+
+    const CSRF_TOKEN: &str = "token";
+
+    /// Serve the requests of a login on a local port, checking the CSRF token like the server does.
+    /// Returns the base URL of the API.
+    fn serve_login() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let mut headers = vec![];
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    headers.push(line.trim().to_lowercase());
+                }
+                let content_length = headers
+                    .iter()
+                    .find_map(|header| header.strip_prefix("content-length: "))
+                    .map_or(0, |length| length.parse().unwrap());
+                reader.read_exact(&mut vec![0; content_length]).unwrap();
+
+                let has_csrf_token = headers.contains(&format!("{CSRF_HEADER_NAME}: {CSRF_TOKEN}"));
+                let (status, body) = match request_line.split_whitespace().take(2).collect::<Vec<_>>()[..] {
+                    ["GET", "/api/user"] => ("401 Unauthorized", "{}"),
+                    ["POST", "/api/user/auth"] if has_csrf_token => ("200 OK", r#"{"password_reset":false}"#),
+                    _ => ("403 Forbidden", "{}"),
+                };
+                let response = format!(
+                    "HTTP/1.1 {status}\r\nSet-Cookie: {CSRF_COOKIE_NAME}={CSRF_TOKEN}; Path=/\r\n\
+                     Content-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+
+        format!("http://{address}/api")
+    }
+
+    #[tokio::test]
+    async fn new_client_can_log_in() {
+        let api_client = ApiClient::new(&serve_login());
+
+        let result = api_client.login("user", "password").await.unwrap();
+
+        assert!(!result.password_reset);
+    }
+}
@@ -0,0 +1,1388 @@
+use crate::api_client::{ApiClient, File};
+use crate::encryption::symmetric::{decrypt_slice, derive_key_from_string, generate_key};
+use crate::exif::Exif;
+use crate::images::Image;
+use crate::keys::{get_key_from_user_credentials, KeyStore};
+use crate::models::Photo;
+use crate::models::{
+    normalize_tags, Album, AlbumHydrated, AlbumPhoto, AlbumShareData, AlbumShareDataPhoto, Library, LibraryAlbum,
+    LibraryPhoto, LibraryShare, SearchIndex, SearchIndexAlbum, SearchIndexPhoto, Share, ShareData, TrashedPhoto,
+    UploadJournal,
+};
+use crate::repository::{ItemVariant, Repository};
+use crate::search::SearchQuery;
+use crate::{encryption, hashing};
+use anyhow::{anyhow, Result};
+use base64::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use upholi_lib::http::request::{CreateUserRequest, TrashEntryRequest, UpsertShareRequest};
+use upholi_lib::http::response::UsageResult;
+use upholi_lib::ids::{id, ID_LIBRARY, ID_MASTER_KEY, ID_UPLOAD_JOURNAL};
+use upholi_lib::PhotoVariant;
+
+pub const KEY_MASTER_KEY: &str = ID_MASTER_KEY;
+pub const KEY_LIBRARY: &str = ID_LIBRARY;
+/// Photos whose perceptual hashes differ in at most this many bits are considered possible duplicates.
+const MAX_DUPLICATE_DISTANCE: u32 = 8;
+/// Highest number of stars a photo can be rated with.
+pub const MAX_RATING: u8 = 5;
+/// Upload journals of other clients that were not stored for this long belong to batches that were abandoned,
+/// e.g. because their client crashed, and are recovered.
+const ABANDONED_UPLOAD_JOURNAL_SECONDS: i64 = 60 * 60;
+
+/// Wrapper struct containing info about bytes to upload.
+pub struct PhotoUploadInfo {
+    pub image: Image,
+    pub exif: Option<Exif>,
+}
+
+/// Photos being uploaded together. They are added to the library, and to albums, once the batch is committed.
+/// See `Client::begin_upload_batch`.
+pub struct UploadBatch {
+    journal_id: String,
+    journal: UploadJournal,
+}
+
+impl UploadBatch {
+    /// Number of photos uploaded in this batch, excluding skipped ones.
+    pub fn len(&self) -> usize {
+        self.journal.photos.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.journal.is_empty()
+    }
+}
+
+/// A photo with its thumbnail, preview and original encrypted, ready to be uploaded.
+///
+/// Creating one is the expensive part of an upload, and needs no access to the server or the user's keys.
+#[derive(Serialize, Deserialize)]
+pub struct EncryptedPhoto {
+    pub photo: Photo,
+    pub key: Vec<u8>,
+    pub files: Vec<File>,
+}
+
+impl EncryptedPhoto {
+    /// Process and encrypt image file bytes, using a newly generated key.
+    pub fn try_from_slice(bytes: &[u8]) -> Result<Self> {
+        let photo_hash = hashing::compute_sha256_hash(bytes)?;
+        let upload_info = PhotoUploadInfo::try_from_slice(bytes)?;
+        let photo_key = generate_key();
+        let photo_id = id();
+
+        // Compute the timestamp to store for this photo
+        let now = chrono::Utc::now().timestamp();
+        let timestamp = if let Some(exif) = &upload_info.exif {
+            exif.date_taken.map_or(now, |dt| dt.timestamp())
+        } else {
+            now
+        };
+
+        let thumbnail_encrypted =
+            crate::encryption::symmetric::encrypt_slice(&photo_key, &upload_info.image.bytes_thumbnail)?;
+        let preview_encrypted =
+            crate::encryption::symmetric::encrypt_slice(&photo_key, &upload_info.image.bytes_preview)?;
+        let original_encrypted =
+            crate::encryption::symmetric::encrypt_slice(&photo_key, &upload_info.image.bytes_original)?;
+
+        let photo = Photo {
+            id: photo_id.clone(),
+            hash: photo_hash,
+            width: upload_info.image.width,
+            height: upload_info.image.height,
+            timestamp,
+            content_type: "image/jpeg".to_string(), // TODO
+            exif: upload_info.exif,
+            nonce_thumbnail: thumbnail_encrypted.nonce,
+            nonce_preview: preview_encrypted.nonce,
+            nonce_original: original_encrypted.nonce,
+            perceptual_hash: Some(upload_info.image.perceptual_hash),
+            file_name: String::new(),
+            caption: String::new(),
+            description: String::new(),
+            tags: vec![],
+        };
+
+        let files = vec![
+            File {
+                id: format!("{photo_id}-thumbnail"),
+                bytes: thumbnail_encrypted.bytes,
+            },
+            File {
+                id: format!("{photo_id}-preview"),
+                bytes: preview_encrypted.bytes,
+            },
+            File {
+                id: format!("{photo_id}-original"),
+                bytes: original_encrypted.bytes,
+            },
+        ];
+
+        Ok(Self {
+            photo,
+            key: photo_key,
+            files,
+        })
+    }
+}
+
+/// A photo in the trash.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrashEntry {
+    pub photo: LibraryPhoto,
+    /// Unix timestamp of when the photo was moved to the trash.
+    pub trashed_on: i64,
+    /// Unix timestamp after which the server deletes the photo, if photos in the trash expire.
+    pub expires_on: Option<i64>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PhotoUploadResult {
+    pub skipped: bool,
+    pub photo_id: String,
+}
+
+impl PhotoUploadInfo {
+    /// Try to construct an object from image file bytes
+    pub fn try_from_slice(bytes: &[u8]) -> Result<Self> {
+        let exif = Exif::parse_from_photo_bytes(bytes)?;
+        let exif_orientation = match &exif {
+            Some(exif) => exif.orientation.unwrap_or(1),
+            None => 1,
+        };
+
+        let image = Image::from_buffer(bytes, exif_orientation as u8)?;
+        Ok(Self { image, exif })
+    }
+}
+
+/// Client for an Upholi server. Encrypts everything before it is sent,
+/// and decrypts everything it receives, using the keys in its key store.
+pub struct Client<'a> {
+    api_client: &'a ApiClient,
+    key_store: &'a dyn KeyStore,
+    repository: Repository<'a>,
+    /// IDs of the upload journals stored by this client that were not committed yet.
+    own_upload_journal_ids: Mutex<HashSet<String>>,
+}
+
+impl<'a> Client<'a> {
+    pub fn new(api_client: &'a ApiClient, key_store: &'a dyn KeyStore) -> Self {
+        Self {
+            api_client,
+            key_store,
+            repository: Repository::new(api_client),
+            own_upload_journal_ids: Mutex::new(HashSet::new()),
+        }
+    }
+
+    pub async fn register(&self, username: &str, password: &str, invite_code: Option<String>) -> Result<()> {
+        let password_derived_key = get_key_from_user_credentials(username, password)?;
+
+        let body = CreateUserRequest {
+            username: username.into(),
+            password: password.into(),
+            invite_code,
+        };
+
+        self.api_client.register(&body).await?;
+        self.create_master_key_and_library(&password_derived_key).await
+    }
+
+    /// Create a new master key and an empty library for the current user.
+    async fn create_master_key_and_library(&self, password_derived_key: &[u8]) -> Result<()> {
+        // This will be the master encryption key of the user.
+        // We encrypt it using the key derived from the user's password,
+        // and the encrypted master key is stored server-side.
+        let master_key = encryption::symmetric::generate_key();
+
+        self.key_store.set_master_key(&master_key)?;
+        self.repository
+            .set(
+                KEY_MASTER_KEY,
+                password_derived_key,
+                ItemVariant::MasterKey(master_key.clone()),
+            )
+            .await?;
+        self.repository
+            .set(KEY_LIBRARY, &master_key, ItemVariant::Library(Library::default()))
+            .await?;
+
+        // Upload journals left behind were encrypted using the previous master key, and cannot be recovered.
+        for journal_id in self.get_upload_journal_ids().await? {
+            self.repository.delete(&journal_id).await?;
+        }
+        self.own_upload_journal_ids.lock().unwrap().clear();
+
+        Ok(())
+    }
+
+    /// Returns the user's master encryption key when login was succesful
+    pub async fn login(&self, username: &str, password: &str) -> Result<()> {
+        let result = self.api_client.login(username, password).await?;
+        let password_derived_key = get_key_from_user_credentials(username, password)?;
+
+        // After an admin reset the user's password, the existing master key cannot be decrypted anymore.
+        // Start over with a new master key and an empty library.
+        if result.password_reset {
+            self.create_master_key_and_library(&password_derived_key).await?;
+            return self.api_client.clear_password_reset().await;
+        }
+
+        let master_key: Vec<u8> = self
+            .repository
+            .get(KEY_MASTER_KEY, &password_derived_key)
+            .await?
+            .ok_or_else(|| anyhow!("Master key missing"))?
+            .try_into()?;
+
+        self.key_store.set_master_key(&master_key)?;
+
+        // Finish upload batches that were interrupted. This is tried again on the next login if it fails,
+        // so it does not stop the user from logging in.
+        if let Err(error) = self.recover_upload_batches().await {
+            log_error(&format!("Recovering interrupted uploads failed: {error:?}"));
+        }
+
+        Ok(())
+    }
+
+    /// Forget all cached items. Long-running clients should call this before working on the library,
+    /// so they do not overwrite changes made by other clients in the meantime.
+    pub fn clear_cache(&self) {
+        self.repository.clear_cache();
+    }
+
+    pub async fn is_authenticated(&self) -> Result<bool> {
+        self.api_client.get_user().await
+    }
+
+    /// Get the ID of the current user.
+    pub async fn get_user_id(&self) -> Result<String> {
+        self.api_client.get_user_id().await
+    }
+
+    /// Get the current user's storage usage and quota.
+    pub async fn get_usage(&self) -> Result<UsageResult> {
+        self.api_client.get_usage().await
+    }
+
+    pub async fn get_library_photos(&self) -> Result<Vec<LibraryPhoto>> {
+        let library = self.get_library().await?;
+        Ok(library.photos.into_iter().rev().collect())
+    }
+
+    /// Get a photo as stored in the library, or None if it is not in the library, e.g. because it is part of a share.
+    pub async fn get_library_photo(&self, id: &str) -> Result<Option<LibraryPhoto>> {
+        let library = self.get_library().await?;
+        Ok(library.photos.into_iter().find(|photo| photo.id == id))
+    }
+
+    /// Mark photos as favourite, or unmark them.
+    pub async fn set_favourite(&self, ids: &[String], favourite: bool) -> Result<()> {
+        self.update_library(&mut |library: &mut Library| {
+            for photo in library.photos.iter_mut().filter(|photo| ids.contains(&photo.id)) {
+                photo.favourite = favourite;
+            }
+            Ok(())
+        })
+        .await
+    }
+
+    /// Rate photos with a number of stars up to `MAX_RATING`, or 0 to clear their rating.
+    pub async fn set_rating(&self, ids: &[String], rating: u8) -> Result<()> {
+        if rating > MAX_RATING {
+            return Err(anyhow!("Rating must be at most {MAX_RATING}"));
+        }
+
+        self.update_library(&mut |library: &mut Library| {
+            for photo in library.photos.iter_mut().filter(|photo| ids.contains(&photo.id)) {
+                photo.rating = rating;
+            }
+            Ok(())
+        })
+        .await
+    }
+
+    /// All tags of photos in the library, sorted alphabetically.
+    pub async fn get_tags(&self) -> Result<Vec<String>> {
+        let library = self.get_library().await?;
+        Ok(library.tags())
+    }
+
+    /// Set the caption, description and tags of a photo in the library.
+    pub async fn update_photo_details(
+        &self,
+        id: &str,
+        caption: &str,
+        description: &str,
+        tags: Vec<String>,
+    ) -> Result<()> {
+        let tags = normalize_tags(tags);
+        let index_entry = self
+            .update_photo(id, &mut |photo: &mut Photo| {
+                photo.caption = caption.trim().to_string();
+                photo.description = description.trim().to_string();
+                photo.tags = tags.clone();
+            })
+            .await?;
+        self.update_search_index(&mut |index: &mut SearchIndex| index.upsert_photos([index_entry.clone()]))
+            .await?;
+
+        self.update_library(&mut |library: &mut Library| {
+            if let Some(photo) = library.photos.iter_mut().find(|photo| photo.id == id) {
+                photo.tags = tags.clone();
+            }
+            Ok(())
+        })
+        .await
+    }
+
+    /// Add tags to photos in the library, keeping the tags they already have.
+    pub async fn add_tags(&self, ids: &[String], tags: Vec<String>) -> Result<()> {
+        let tags = normalize_tags(tags);
+        let mut index_entries = Vec::with_capacity(ids.len());
+        for id in ids {
+            let index_entry = self
+                .update_photo(id, &mut |photo: &mut Photo| {
+                    photo.tags = normalize_tags(photo.tags.iter().chain(&tags).cloned());
+                })
+                .await?;
+            index_entries.push(index_entry);
+        }
+        self.update_search_index(&mut |index: &mut SearchIndex| index.upsert_photos(index_entries.iter().cloned()))
+            .await?;
+
+        self.update_library(&mut |library: &mut Library| {
+            for photo in library.photos.iter_mut().filter(|photo| ids.contains(&photo.id)) {
+                photo.tags = normalize_tags(photo.tags.iter().chain(&tags).cloned());
+            }
+            Ok(())
+        })
+        .await
+    }
+
+    /// Search photos in the library, newest first. See `crate::search` for the query syntax.
+    pub async fn search(&self, query: &str) -> Result<Vec<LibraryPhoto>> {
+        let query = SearchQuery::parse(query)?;
+        let index = self.sync_search_index().await?;
+        let photo_ids: HashSet<&str> = query.find(&index).into_iter().collect();
+
+        let photos = self.get_library_photos().await?;
+        Ok(photos
+            .into_iter()
+            .filter(|photo| photo_ids.contains(photo.id.as_str()))
+            .collect())
+    }
+
+    pub async fn get_photo(&self, id: &str) -> Result<Photo> {
+        let photo_encryption_key = self.determine_photo_key(id).await?;
+        let photo_item = self.repository.get(id, &photo_encryption_key).await?;
+        let photo = photo_item
+            .ok_or_else(|| anyhow!("Photo '{id}' not found"))?
+            .try_into()?;
+
+        Ok(photo)
+    }
+
+    pub async fn get_albums(&self) -> Result<Vec<Album>> {
+        let library = self.get_library().await?;
+        let album_ids = library.albums.into_iter().map(|album| album.id);
+
+        let mut albums = vec![];
+        for album_id in album_ids {
+            let album = self.get_album(&album_id).await?;
+            if let Some(album) = album {
+                albums.push(album);
+            }
+        }
+
+        Ok(albums)
+    }
+
+    async fn get_album(&self, id: &str) -> Result<Option<Album>> {
+        let library = self.get_library().await?;
+        let encryption_key = self.get_item_encryption_key(&library, id)?;
+        self.get_album_using_key(id, encryption_key).await
+    }
+
+    async fn get_album_using_key(&self, id: &str, album_encryption_key: &[u8]) -> Result<Option<Album>> {
+        let item = self.repository.get(id, album_encryption_key).await?;
+        match item {
+            Some(item) => Ok(Some(item.try_into()?)),
+            None => Ok(None),
+        }
+    }
+
+    pub async fn create_album(&self, title: &str, initial_photo_ids: Vec<String>) -> Result<String> {
+        let album_id = id();
+        let album_key = generate_key();
+
+        let album = Album {
+            id: album_id.clone(),
+            key: album_key.clone(),
+            title: title.into(),
+            thumbnail_photo_id: initial_photo_ids.first().map(|s| s.to_owned()),
+            photos: initial_photo_ids,
+        };
+
+        self.update_library(&mut |library: &mut Library| {
+            library.albums.push(LibraryAlbum {
+                id: album_id.clone(),
+                key: album_key.clone(),
+            });
+            Ok(())
+        })
+        .await?;
+
+        self.repository.set(&album_id, &album_key, album.into()).await?;
+
+        Ok(album_id)
+    }
+
+    pub async fn get_album_full(&self, id: &str) -> Result<AlbumHydrated> {
+        let album = self
+            .get_album(id)
+            .await?
+            .ok_or_else(|| anyhow!("Album '{id}' not found"))?;
+        let photos = self.get_library_photos().await?;
+        let album_photos = photos.into_iter().map(|p| p.into()).collect();
+        self.inflate_album(album, album_photos)
+    }
+
+    pub async fn delete_album(&self, id: &str) -> Result<()> {
+        let shares = self.get_shares().await?;
+        let shares_for_album = shares.iter().filter(|share| share.album_id == id);
+        for share in shares_for_album {
+            self.delete_share(&share.id).await?;
+        }
+
+        self.repository.delete(id).await?;
+
+        self.update_library(&mut |library: &mut Library| {
+            library.albums.retain(|ik| ik.id != id);
+            Ok(())
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    fn inflate_album(&self, album: Album, photos: Vec<AlbumPhoto>) -> Result<AlbumHydrated> {
+        let mut photos_in_album: Vec<AlbumPhoto> = vec![];
+        for photo in &photos {
+            if album.photos.contains(&photo.id) {
+                photos_in_album.push(photo.clone());
+            }
+        }
+
+        let album = AlbumHydrated {
+            id: album.id.clone(),
+            title: album.title.clone(),
+            photos: photos_in_album,
+            thumbnail_photo: match album.thumbnail_photo_id.clone() {
+                Some(thumbnail_photo_id) => {
+                    let photo = photos
+                        .into_iter()
+                        .find(|photo| photo.id == thumbnail_photo_id)
+                        .ok_or_else(|| anyhow!("Photo not found for thumbnail of album {}", &album.id))?;
+                    Some(photo)
+                }
+                None => None,
+            },
+        };
+
+        Ok(album)
+    }
+
+    pub async fn upload_photo(&self, bytes: &[u8]) -> Result<PhotoUploadResult> {
+        // Check for duplicates before the expensive processing of the image.
+        let photo_hash = hashing::compute_sha256_hash(bytes)?;
+        if let Some(photo_id) = self.find_photo_by_hash(&photo_hash).await? {
+            return Ok(PhotoUploadResult {
+                skipped: true,
+                photo_id,
+            });
+        }
+
+        let encrypted_photo = EncryptedPhoto::try_from_slice(bytes)?;
+        self.upload_encrypted_photo(encrypted_photo).await
+    }
+
+    /// Upload a photo that was processed and encrypted beforehand, possibly elsewhere, such as in a Web Worker.
+    pub async fn upload_encrypted_photo(&self, encrypted_photo: EncryptedPhoto) -> Result<PhotoUploadResult> {
+        if let Some(photo_id) = self.find_photo_by_hash(&encrypted_photo.photo.hash).await? {
+            // No error, but no need to upload.
+            return Ok(PhotoUploadResult {
+                skipped: true,
+                photo_id,
+            });
+        }
+
+        let EncryptedPhoto { photo, key, files } = encrypted_photo;
+        let photo_id = photo.id.clone();
+        self.api_client.set_files(&files).await?;
+        self.repository
+            .set(&photo_id, &key, ItemVariant::Photo(photo.clone()))
+            .await?;
+
+        self.update_library(&mut |library: &mut Library| {
+            library.photos.push(LibraryPhoto::from(&photo, key.clone()));
+            Ok(())
+        })
+        .await?;
+
+        Ok(PhotoUploadResult {
+            skipped: false,
+            photo_id,
+        })
+    }
+
+    /// Start uploading a batch of photos. Call `upload_photo_in_batch` for each photo, and `commit_upload_batch` when done.
+    /// Adding the photos to the library and albums only once per batch is much cheaper than once per photo,
+    /// as it rewrites the whole library, and every album and share involved.
+    ///
+    /// Each batch has its own journal, so clients uploading at the same time do not interfere.
+    /// Finishes earlier batches of this client first, if committing them failed.
+    pub async fn begin_upload_batch(&self) -> Result<UploadBatch> {
+        let own_journal_ids: Vec<String> = self.own_upload_journal_ids.lock().unwrap().iter().cloned().collect();
+        for journal_id in own_journal_ids {
+            self.recover_upload_journal(&journal_id).await?;
+        }
+
+        Ok(UploadBatch {
+            journal_id: format!("{ID_UPLOAD_JOURNAL}-{}", id()),
+            journal: UploadJournal::default(),
+        })
+    }
+
+    /// Upload a photo that was processed and encrypted beforehand, as part of a batch.
+    /// The photo is not added to the library, or to given album, until the batch is committed.
+    pub async fn upload_photo_in_batch(
+        &self,
+        batch: &mut UploadBatch,
+        encrypted_photo: EncryptedPhoto,
+        album_id: Option<&str>,
+    ) -> Result<PhotoUploadResult> {
+        let photo_hash = &encrypted_photo.photo.hash;
+        let existing_photo_id = match batch.journal.photos.iter().find(|photo| &photo.hash == photo_hash) {
+            Some(photo) => Some(photo.id.clone()),
+            None => self.find_photo_by_hash(photo_hash).await?,
+        };
+
+        let result = match existing_photo_id {
+            Some(photo_id) => PhotoUploadResult {
+                skipped: true,
+                photo_id,
+            },
+            None => {
+                let EncryptedPhoto { photo, key, files } = encrypted_photo;
+
+                // Store the photo in the journal before uploading it, so it is never uploaded without being in either
+                // the journal or the library. Otherwise, its key would be lost.
+                batch.journal.photos.push(LibraryPhoto::from(&photo, key.clone()));
+                batch.journal.updated_on = chrono::Utc::now().timestamp();
+                let master_key = self.key_store.get_master_key();
+                let uploaded = async {
+                    self.own_upload_journal_ids
+                        .lock()
+                        .unwrap()
+                        .insert(batch.journal_id.clone());
+                    self.repository
+                        .set(&batch.journal_id, &master_key, batch.journal.clone().into())
+                        .await?;
+                    self.api_client.set_files(&files).await?;
+                    self.repository
+                        .set(&photo.id, &key, ItemVariant::Photo(photo.clone()))
+                        .await
+                }
+                .await;
+
+                if let Err(error) = uploaded {
+                    batch
+                        .journal
+                        .photos
+                        .retain(|library_photo| library_photo.id != photo.id);
+                    return Err(error);
+                }
+
+                PhotoUploadResult {
+                    skipped: false,
+                    photo_id: photo.id,
+                }
+            }
+        };
+
+        if let Some(album_id) = album_id {
+            batch.journal.add_to_album(album_id, &result.photo_id);
+        }
+
+        Ok(result)
+    }
+
+    /// Add the photos of a batch to the library, and to their albums.
+    pub async fn commit_upload_batch(&self, batch: UploadBatch) -> Result<()> {
+        let journal_stored = self.own_upload_journal_ids.lock().unwrap().contains(&batch.journal_id);
+        if batch.journal.is_empty() && !journal_stored {
+            return Ok(());
+        }
+
+        self.commit_upload_journal(&batch.journal_id, batch.journal).await
+    }
+
+    /// Commit the upload journals of batches that were interrupted: those of this client,
+    /// and those of other clients that were abandoned. Batches that other clients are still uploading are left alone.
+    async fn recover_upload_batches(&self) -> Result<()> {
+        let master_key = self.key_store.get_master_key();
+        let abandoned_before = chrono::Utc::now().timestamp() - ABANDONED_UPLOAD_JOURNAL_SECONDS;
+
+        for journal_id in self.get_upload_journal_ids().await? {
+            let own_journal = self.own_upload_journal_ids.lock().unwrap().contains(&journal_id);
+            if !own_journal {
+                let journal: UploadJournal = match self.repository.get(&journal_id, &master_key).await? {
+                    Some(item) => item.try_into()?,
+                    None => continue,
+                };
+                if journal.updated_on > abandoned_before {
+                    continue;
+                }
+            }
+
+            self.recover_upload_journal(&journal_id).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Get the IDs of all upload journals of the current user, including one stored by an earlier version.
+    async fn get_upload_journal_ids(&self) -> Result<Vec<String>> {
+        let prefix = format!("{ID_UPLOAD_JOURNAL}-");
+        let item_ids = self.api_client.get_item_ids().await?;
+
+        Ok(item_ids
+            .into_iter()
+            .filter(|id| id == ID_UPLOAD_JOURNAL || id.starts_with(&prefix))
+            .collect())
+    }
+
+    /// Commit the upload journal with given ID, if it exists.
+    /// Only photos that were uploaded completely are added to the library.
+    async fn recover_upload_journal(&self, journal_id: &str) -> Result<()> {
+        let master_key = self.key_store.get_master_key();
+        let journal: UploadJournal = match self.repository.get(journal_id, &master_key).await? {
+            Some(item) => item.try_into()?,
+            None => {
+                self.own_upload_journal_ids.lock().unwrap().remove(journal_id);
+                return Ok(());
+            }
+        };
+
+        // The photo item is uploaded last, so if it exists, so do its files.
+        let mut uploaded_photos = vec![];
+        for photo in journal.photos {
+            if self.api_client.get_item(&photo.id).await?.is_some() {
+                uploaded_photos.push(photo);
+            }
+        }
+
+        self.commit_upload_journal(
+            journal_id,
+            UploadJournal {
+                photos: uploaded_photos,
+                ..journal
+            },
+        )
+        .await
+    }
+
+    async fn commit_upload_journal(&self, journal_id: &str, journal: UploadJournal) -> Result<()> {
+        let UploadJournal { photos, albums, .. } = journal;
+
+        self.update_library(&mut |library: &mut Library| {
+            for photo in &photos {
+                if !library.photos.iter().any(|library_photo| library_photo.id == photo.id) {
+                    library.photos.push(photo.clone());
+                }
+            }
+            Ok(())
+        })
+        .await?;
+
+        // Albums may have been deleted in the meantime.
+        let library = self.get_library().await?;
+        for (album_id, photo_ids) in albums {
+            if library.albums.iter().any(|album| album.id == album_id) {
+                self.add_photos_to_album(&album_id, &photo_ids).await?;
+            }
+        }
+
+        self.repository.delete(journal_id).await?;
+        self.own_upload_journal_ids.lock().unwrap().remove(journal_id);
+        Ok(())
+    }
+
+    /// Get the ID of the photo in the library with given hash, if any.
+    pub async fn find_photo_by_hash(&self, photo_hash: &str) -> Result<Option<String>> {
+        let library = self.get_library().await?;
+        let existing_photo = library.photos.into_iter().find(|photo| photo.hash == photo_hash);
+        Ok(existing_photo.map(|photo| photo.id))
+    }
+
+    /// Get groups of photos that look alike, with the photo with the highest resolution first.
+    /// Photos uploaded before perceptual hashes were computed are not included.
+    pub async fn get_possible_duplicates(&self) -> Result<Vec<Vec<LibraryPhoto>>> {
+        let library = self.get_library().await?;
+        Ok(library.find_possible_duplicates(MAX_DUPLICATE_DISTANCE))
+    }
+
+    /// Keep one photo of a group of duplicates and move the others to the trash.
+    /// The kept photo takes the place of the deleted ones in albums, and as album cover.
+    pub async fn keep_duplicate(&self, keep_id: &str, delete_ids: &[String]) -> Result<()> {
+        let albums = self.get_albums().await?;
+        for album in albums {
+            self.update_album(&album.id, &mut |album: &mut Album| {
+                let mut modified = false;
+                if !album.photos.iter().any(|id| id == keep_id) {
+                    if let Some(position) = album.photos.iter().position(|id| delete_ids.contains(id)) {
+                        album.photos[position] = keep_id.to_string();
+                        modified = true;
+                    }
+                }
+                if album
+                    .thumbnail_photo_id
+                    .as_ref()
+                    .is_some_and(|id| delete_ids.contains(id))
+                {
+                    album.thumbnail_photo_id = Some(keep_id.to_string());
+                    modified = true;
+                }
+                modified
+            })
+            .await?;
+        }
+
+        self.delete_photos(delete_ids).await
+    }
+
+    pub async fn get_photo_image_src(&self, photo_id: &str, photo_variant: PhotoVariant) -> Result<String> {
+        if photo_id.is_empty() {
+            Ok(String::new())
+        } else {
+            let photo = self.get_photo(photo_id).await?;
+            let photo_bytes = self.get_photo_bytes(&photo, photo_variant).await?;
+            let photo_base64 = BASE64_STANDARD.encode(photo_bytes);
+
+            let src = format!("data:{};base64,{}", photo.content_type, photo_base64);
+            Ok(src)
+        }
+    }
+
+    /// Download and decrypt the bytes of a variant of a photo.
+    pub async fn get_photo_bytes(&self, photo: &Photo, photo_variant: PhotoVariant) -> Result<Vec<u8>> {
+        let encryption_key = self.determine_photo_key(&photo.id).await?;
+        let nonce = match photo_variant {
+            PhotoVariant::Thumbnail => &photo.nonce_thumbnail,
+            PhotoVariant::Preview => &photo.nonce_preview,
+            PhotoVariant::Original => &photo.nonce_original,
+        };
+
+        let file_id = format!("{}-{photo_variant}", photo.id);
+        let encrypted_bytes = self
+            .api_client
+            .get_file(&file_id)
+            .await?
+            .ok_or_else(|| anyhow!("File '{file_id}' not found"))?;
+        decrypt_slice(&encryption_key, nonce.as_bytes(), &encrypted_bytes)
+    }
+
+    /// Move photos to the trash. They are removed from albums and the library, but can be restored
+    /// until the server deletes them once they expire.
+    pub async fn delete_photos(&self, ids: &[String]) -> Result<()> {
+        let albums = self.get_albums().await?;
+        let mut album_ids_by_photo_id: HashMap<String, Vec<String>> = HashMap::new();
+
+        // Remove photo from all albums that contain them, and update their shares (if any)
+        for album in albums {
+            for photo_id in album.photos.iter().filter(|photo_id| ids.contains(photo_id)) {
+                album_ids_by_photo_id
+                    .entry(photo_id.clone())
+                    .or_default()
+                    .push(album.id.clone());
+            }
+
+            self.update_album(&album.id, &mut |album: &mut Album| {
+                let album_needs_updating = album.photos.iter().any(|photo_id| ids.contains(photo_id));
+
+                if album_needs_updating {
+                    album.photos.retain(|photo_id| !ids.contains(photo_id));
+
+                    if let Some(id) = &album.thumbnail_photo_id {
+                        if ids.contains(id) {
+                            album.thumbnail_photo_id = None;
+                        }
+                    }
+                }
+
+                album_needs_updating
+            })
+            .await?;
+        }
+
+        // Move photos from library to trash
+        let trashed_on = chrono::Utc::now().timestamp();
+        self.update_library(&mut |library: &mut Library| {
+            let (trashed, kept): (Vec<LibraryPhoto>, Vec<LibraryPhoto>) = std::mem::take(&mut library.photos)
+                .into_iter()
+                .partition(|photo| ids.contains(&photo.id));
+            library.photos = kept;
+            library.trash.extend(trashed.into_iter().map(|photo| TrashedPhoto {
+                albums: album_ids_by_photo_id.remove(&photo.id).unwrap_or_default(),
+                photo,
+                trashed_on,
+            }));
+            Ok(())
+        })
+        .await?;
+
+        self.api_client
+            .add_to_trash(ids.iter().map(|id| Self::trash_entry_request(id)).collect())
+            .await
+    }
+
+    /// Get the photos in the trash, oldest first.
+    ///
+    /// Photos the server deleted because they expired are removed from the trash.
+    pub async fn get_trash(&self) -> Result<Vec<TrashEntry>> {
+        let library = self.get_library().await?;
+        if library.trash.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let item_ids: HashSet<String> = self.api_client.get_item_ids().await?.into_iter().collect();
+        let (trash, expired): (Vec<TrashedPhoto>, Vec<TrashedPhoto>) = library
+            .trash
+            .into_iter()
+            .partition(|trashed_photo| item_ids.contains(&trashed_photo.photo.id));
+        if !expired.is_empty() {
+            self.update_library(&mut |library: &mut Library| {
+                library
+                    .trash
+                    .retain(|trashed_photo| item_ids.contains(&trashed_photo.photo.id));
+                Ok(())
+            })
+            .await?;
+        }
+
+        // Photos may lack an entry on the server if storing it failed when they were moved to the trash.
+        // Store their entries again, so they still expire.
+        let mut entries = self.api_client.get_trash().await?;
+        let ids_without_entry: Vec<&str> = trash
+            .iter()
+            .map(|trashed_photo| trashed_photo.photo.id.as_str())
+            .filter(|id| !entries.iter().any(|entry| entry.id == *id))
+            .collect();
+        if !ids_without_entry.is_empty() {
+            self.api_client
+                .add_to_trash(
+                    ids_without_entry
+                        .iter()
+                        .map(|id| Self::trash_entry_request(id))
+                        .collect(),
+                )
+                .await?;
+            entries = self.api_client.get_trash().await?;
+        }
+
+        let mut trash: Vec<TrashEntry> = trash
+            .into_iter()
+            .map(|trashed_photo| TrashEntry {
+                expires_on: entries
+                    .iter()
+                    .find(|entry| entry.id == trashed_photo.photo.id)
+                    .and_then(|entry| entry.expires_on),
+                photo: trashed_photo.photo,
+                trashed_on: trashed_photo.trashed_on,
+            })
+            .collect();
+        trash.sort_by_key(|entry| entry.trashed_on);
+
+        Ok(trash)
+    }
+
+    /// Restore photos from the trash to the library, and to the albums they were in that still exist.
+    pub async fn restore_photos(&self, ids: &[String]) -> Result<()> {
+        // Take the photos out of the server's trash first, so they are not deleted at expiry
+        // while the library already refers to them again.
+        self.api_client.restore_from_trash(ids.to_vec()).await?;
+
+        let mut restored: Vec<TrashedPhoto> = vec![];
+        self.update_library(&mut |library: &mut Library| {
+            let (restore, keep): (Vec<TrashedPhoto>, Vec<TrashedPhoto>) = std::mem::take(&mut library.trash)
+                .into_iter()
+                .partition(|trashed_photo| ids.contains(&trashed_photo.photo.id));
+            library.trash = keep;
+            library
+                .photos
+                .extend(restore.iter().map(|trashed_photo| trashed_photo.photo.clone()));
+            restored = restore;
+            Ok(())
+        })
+        .await?;
+
+        let mut photo_ids_by_album_id: HashMap<&str, Vec<String>> = HashMap::new();
+        for trashed_photo in &restored {
+            for album_id in &trashed_photo.albums {
+                photo_ids_by_album_id
+                    .entry(album_id)
+                    .or_default()
+                    .push(trashed_photo.photo.id.clone());
+            }
+        }
+        for (album_id, photo_ids) in photo_ids_by_album_id {
+            if self.get_album(album_id).await?.is_some() {
+                self.add_photos_to_album(album_id, &photo_ids).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Delete photos in the trash permanently, including their files.
+    pub async fn delete_photos_permanently(&self, ids: &[String]) -> Result<()> {
+        // Storing the trash entries may have failed when the photos were moved to the trash,
+        // and the server only deletes what the entries refer to.
+        self.api_client
+            .add_to_trash(ids.iter().map(|id| Self::trash_entry_request(id)).collect())
+            .await?;
+        self.api_client.delete_from_trash(ids.to_vec()).await?;
+
+        self.update_library(&mut |library: &mut Library| {
+            library
+                .trash
+                .retain(|trashed_photo| !ids.contains(&trashed_photo.photo.id));
+            Ok(())
+        })
+        .await
+    }
+
+    /// Delete all photos in the trash permanently.
+    pub async fn empty_trash(&self) -> Result<()> {
+        let library = self.get_library().await?;
+        let ids: Vec<String> = library
+            .trash
+            .into_iter()
+            .map(|trashed_photo| trashed_photo.photo.id)
+            .collect();
+
+        if ids.is_empty() {
+            Ok(())
+        } else {
+            self.delete_photos_permanently(&ids).await
+        }
+    }
+
+    /// The trash entry of a photo, which deletes the photo and its files when it expires.
+    fn trash_entry_request(photo_id: &str) -> TrashEntryRequest {
+        TrashEntryRequest {
+            id: photo_id.to_string(),
+            item_ids: vec![photo_id.to_string()],
+            file_ids: vec![
+                format!("{photo_id}-{}", PhotoVariant::Thumbnail),
+                format!("{photo_id}-{}", PhotoVariant::Preview),
+                format!("{photo_id}-{}", PhotoVariant::Original),
+            ],
+        }
+    }
+
+    pub async fn update_album_title(&self, id: &str, title: &str) -> Result<()> {
+        self.update_album(id, &mut |album: &mut Album| {
+            album.title = title.to_string();
+            true
+        })
+        .await?;
+        Ok(())
+    }
+
+    pub async fn update_album_cover(&self, id: &str, thumbnail_photo_id: &str) -> Result<()> {
+        self.update_album(id, &mut |album: &mut Album| {
+            album.thumbnail_photo_id = Some(thumbnail_photo_id.into());
+            true
+        })
+        .await?;
+        Ok(())
+    }
+
+    pub async fn add_photos_to_album(&self, id: &str, photo_ids: &[String]) -> Result<()> {
+        self.update_album(id, &mut |album: &mut Album| {
+            let any_photos_not_already_in = photo_ids.iter().any(|id| !album.photos.contains(id));
+
+            if any_photos_not_already_in {
+                for id in photo_ids {
+                    if !album.photos.contains(id) {
+                        album.photos.push(id.to_owned());
+                    }
+                }
+            }
+
+            any_photos_not_already_in
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// Remove given photo IDs from album.
+    /// Unsets the album's thumbnail if the current thumbnail is one of the photos to remove from album.
+    pub async fn remove_photos_from_album(&self, id: &str, photos: &[String]) -> Result<()> {
+        self.update_album(id, &mut |album: &mut Album| {
+            album.photos.retain(|id| !photos.contains(id));
+
+            if let Some(thumb_photo_id) = &album.thumbnail_photo_id {
+                if photos.contains(thumb_photo_id) {
+                    album.thumbnail_photo_id = None;
+                }
+            }
+
+            true
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// Creates or updates a share.
+    ///
+    /// * `item_id` - ID of the item (e.g. an album) to create a share for.
+    pub async fn upsert_share(&self, item_id: &str, password: &str) -> anyhow::Result<String> {
+        let library = self.get_library().await?;
+        let existing_share = library.shares.iter().find(|s| s.album_id == item_id);
+
+        let album = self
+            .get_album(item_id)
+            .await?
+            .ok_or_else(|| anyhow!("Album not found"))?;
+        let share_id = match existing_share {
+            Some(existing_share) => existing_share.id.clone(),
+            None => id(),
+        };
+        let share_key = derive_key_from_string(password, &share_id)?;
+        let share = Share {
+            data: ShareData::Album(AlbumShareData {
+                album_id: item_id.into(),
+                album_key: album.key,
+                photos: album
+                    .photos
+                    .iter()
+                    .map(|photo_id| {
+                        let photo = library
+                            .photos
+                            .iter()
+                            .find(|p| &p.id == photo_id)
+                            .ok_or_else(|| anyhow!("Photo with ID '{photo_id}' not found."))?;
+                        let key = self.get_item_encryption_key(&library, photo_id)?;
+
+                        Ok(AlbumShareDataPhoto {
+                            id: photo_id.to_string(),
+                            key: key.to_vec(),
+                            width: photo.width,
+                            height: photo.height,
+                        })
+                    })
+                    .collect::<Result<_>>()?,
+            }),
+        };
+
+        let mut share_item_ids = vec![album.id.clone()];
+        share_item_ids.extend(album.photos.iter().flat_map(|id| {
+            vec![
+                format!("{id}"),
+                format!("{id}-{}", PhotoVariant::Thumbnail),
+                format!("{id}-{}", PhotoVariant::Preview),
+                format!("{id}-{}", PhotoVariant::Original),
+            ]
+        }));
+
+        self.update_library(&mut |library: &mut Library| {
+            library.shares.retain(|s| s.id != share_id);
+            library.shares.push(LibraryShare {
+                id: share_id.clone(),
+                key: share_key.clone(),
+                password: password.into(),
+                album_id: album.id.clone(),
+            });
+            Ok(())
+        })
+        .await?;
+
+        self.repository
+            .set(&share_id, &share_key, ItemVariant::Share(share))
+            .await?;
+
+        self.api_client
+            .upsert_share(UpsertShareRequest {
+                id: share_id.clone(),
+                password: password.into(),
+                items: share_item_ids,
+            })
+            .await?;
+
+        Ok(share_id)
+    }
+
+    /// Get a share by decrypting it using owner's key.
+    pub async fn get_shares(&self) -> Result<Vec<LibraryShare>> {
+        let library = self.get_library().await?;
+        Ok(library.shares)
+    }
+
+    /// Get the share that belongs to given album_id, if any share exists for that album
+    pub async fn get_share_for_album(&self, album_id: &str) -> Result<Option<LibraryShare>> {
+        let library = self.get_library().await?;
+        match library.shares.iter().find(|share| share.album_id == album_id) {
+            Some(album) => Ok(Some(album.to_owned())),
+            None => Ok(None),
+        }
+    }
+
+    /// Get the album for given share_id.
+    pub async fn get_share_album(&self, share_id: &str) -> Result<AlbumHydrated> {
+        let share_key = &self
+            .key_store
+            .get_share_key(share_id)?
+            .ok_or_else(|| anyhow!("No key found for share '{share_id}'."))?;
+        let share: Share = self
+            .repository
+            .get(share_id, share_key)
+            .await?
+            .ok_or_else(|| anyhow!("Share '{share_id}' not found."))?
+            .try_into()?;
+
+        let ShareData::Album(album_data) = share.data;
+        let album_id = album_data.album_id;
+        let album = self
+            .get_album_using_key(&album_id, &album_data.album_key)
+            .await?
+            .ok_or_else(|| anyhow!("Album '{album_id}' not found."))?;
+        let album = self.inflate_album(album, album_data.photos.into_iter().map(|p| p.into()).collect())?;
+        Ok(album)
+    }
+
+    pub async fn delete_share(&self, id: &str) -> Result<()> {
+        self.api_client.delete_share(id).await?;
+
+        self.repository.delete(id).await?;
+
+        self.update_library(&mut |library: &mut Library| {
+            library.shares.retain(|share| share.id != id);
+            Ok(())
+        })
+        .await
+    }
+
+    pub async fn is_authorized_for_share(&self, id: &str) -> Result<bool> {
+        let already_authorized = self.api_client.is_authorized_for_share(id).await?;
+
+        if already_authorized {
+            Ok(true)
+        } else {
+            // Not yet authorized, but check if share is publicly accessible without requiring a password:
+            self.authorize_share(id, "").await
+        }
+    }
+
+    pub async fn authorize_share(&self, id: &str, password: &str) -> Result<bool> {
+        let authorized = self.api_client.authorize_share(id, password).await?;
+
+        if authorized {
+            let share_key = derive_key_from_string(password, id)?;
+            self.key_store.set_share_key(id, &share_key)?
+        }
+
+        Ok(authorized)
+    }
+
+    /// Determine encryption key to use for given photo ID.
+    ///
+    /// * `photo_id` - ID of photo to determine encryption key for.
+    async fn determine_photo_key(&self, photo_id: &str) -> Result<Vec<u8>> {
+        // TODO: properly determine if we need to check library or shares,
+        //  not it relies on an error.
+
+        match &self.get_library().await {
+            Ok(library) => Ok(self.get_item_encryption_key(library, photo_id)?.clone()),
+            Err(_) => {
+                let shares = self.repository.get_cached_shares()?;
+                let photos: Vec<AlbumShareDataPhoto> = shares
+                    .into_iter()
+                    .map(|s| match s.data {
+                        ShareData::Album(share) => share,
+                    })
+                    .flat_map(|f| f.photos)
+                    .collect();
+                let photo = photos
+                    .iter()
+                    .find(|photo| photo.id == photo_id)
+                    .ok_or_else(|| anyhow!("Photo '{photo_id}' not available in any of the cached shares."))?;
+                Ok(photo.key.clone())
+            }
+        }
+    }
+
+    async fn get_library(&self) -> Result<Library> {
+        let master_key = self.key_store.get_master_key();
+        let library = self
+            .repository
+            .get_or(KEY_LIBRARY, &master_key, &|| Library::default().into())
+            .await?;
+
+        library.try_into()
+    }
+
+    /// Modify a photo and return its new search index entry.
+    /// The search index is not updated here, so an operation on many photos can write it once, using `update_search_index`.
+    async fn update_photo(&self, id: &str, modify_photo: &mut dyn FnMut(&mut Photo)) -> Result<SearchIndexPhoto> {
+        let library = self.get_library().await?;
+        let photo_key = self.get_item_encryption_key(&library, id)?.clone();
+        let mut photo: Photo = self
+            .repository
+            .get(id, &photo_key)
+            .await?
+            .ok_or_else(|| anyhow!("Photo '{id}' not found"))?
+            .try_into()?;
+
+        modify_photo(&mut photo);
+        let index_entry = SearchIndexPhoto::from(&photo);
+        self.repository.set(id, &photo_key, photo.into()).await?;
+
+        Ok(index_entry)
+    }
+
+    /// Bring the search index up to date with the library, creating it if it does not exist yet.
+    /// Photos and albums that are not in the index yet are fetched and added; ones no longer in the library are removed.
+    async fn sync_search_index(&self) -> Result<SearchIndex> {
+        let library = self.get_library().await?;
+        let index_id = match &library.search_index_id {
+            Some(index_id) => index_id.clone(),
+            None => {
+                let index_id = id();
+                self.update_library(&mut |library: &mut Library| {
+                    library.search_index_id = Some(index_id.clone());
+                    Ok(())
+                })
+                .await?;
+                index_id
+            }
+        };
+
+        let master_key = self.key_store.get_master_key();
+        let (mut index, mut modified) = match self.repository.get(&index_id, &master_key).await? {
+            Some(item) => (item.try_into()?, false),
+            None => (SearchIndex::default(), true),
+        };
+
+        modified |= index.retain_library(&library);
+
+        let indexed_photo_ids: HashSet<&str> = index.photos.iter().map(|photo| photo.id.as_str()).collect();
+        let mut new_photos = vec![];
+        for library_photo in &library.photos {
+            if !indexed_photo_ids.contains(library_photo.id.as_str()) {
+                if let Some(item) = self.repository.get(&library_photo.id, &library_photo.key).await? {
+                    let photo: Photo = item.try_into()?;
+                    new_photos.push(SearchIndexPhoto::from(&photo));
+                }
+            }
+        }
+
+        let indexed_album_ids: HashSet<&str> = index.albums.iter().map(|album| album.id.as_str()).collect();
+        let mut new_albums = vec![];
+        for library_album in &library.albums {
+            if !indexed_album_ids.contains(library_album.id.as_str()) {
+                if let Some(album) = self.get_album_using_key(&library_album.id, &library_album.key).await? {
+                    new_albums.push(SearchIndexAlbum::from(&album));
+                }
+            }
+        }
+
+        modified |= !new_photos.is_empty() || !new_albums.is_empty();
+        index.photos.extend(new_photos);
+        index.albums.extend(new_albums);
+
+        if modified {
+            self.repository
+                .set(&index_id, &master_key, index.clone().into())
+                .await?;
+        }
+
+        Ok(index)
+    }
+
+    /// Modify the search index, if it exists. If it does not, it is built when the library is first searched.
+    async fn update_search_index(&self, modify_index: &mut dyn FnMut(&mut SearchIndex)) -> Result<()> {
+        let library = self.get_library().await?;
+        let master_key = self.key_store.get_master_key();
+        if let Some(index_id) = &library.search_index_id {
+            if let Some(item) = self.repository.get(index_id, &master_key).await? {
+                let mut index: SearchIndex = item.try_into()?;
+                modify_index(&mut index);
+                self.repository.set(index_id, &master_key, index.into()).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn update_library(&self, modify_library: &mut dyn FnMut(&mut Library) -> Result<()>) -> Result<()> {
+        let mut library = self.get_library().await?;
+        modify_library(&mut library)?;
+
+        let master_key = self.key_store.get_master_key();
+        self.repository.set(KEY_LIBRARY, &master_key, library.into()).await?;
+
+        Ok(())
+    }
+
+    /// Update an album
+    ///
+    /// * `modify_album` - function that modifies given album. Should return a boolean to indicate whether album was modified or not.
+    async fn update_album(&self, id: &str, modify_album: &mut dyn FnMut(&mut Album) -> bool) -> Result<()> {
+        let library = self.get_library().await?;
+        let mut album = self.get_album(id).await?.ok_or_else(|| anyhow!("Album not found"))?;
+
+        let modified = modify_album(&mut album);
+
+        if modified {
+            let album_key = self.get_item_encryption_key(&library, &album.id)?;
+            let index_entry = SearchIndexAlbum::from(&album);
+            self.repository.set(id, album_key, album.into()).await?;
+            self.update_search_index(&mut |index: &mut SearchIndex| index.upsert_album(index_entry.clone()))
+                .await?;
+
+            // If a share exists for this album, then update it.
+            let share_for_album = self.get_share_for_album(id).await?;
+            if let Some(share) = share_for_album {
+                // TODO: This can be optimized. This only needs to update the item representing the share,
+                // not the share.holding the authentication info.
+                self.upsert_share(id, &share.password).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_item_encryption_key<'s>(&'s self, library: &'s Library, item_id: &str) -> Result<&'s Vec<u8>> {
+        library
+            .find_encryption_key(item_id)
+            .ok_or_else(|| anyhow!("No key found for item '{}'", item_id))
+    }
+}
+
+/// Report an error that does not stop the current operation, on the browser console or on stderr.
+fn log_error(message: &str) {
+    #[cfg(target_arch = "wasm32")]
+    web_sys::console::error_1(&message.into());
+    #[cfg(not(target_arch = "wasm32"))]
+    eprintln!("{message}");
+}
@@ -112,9 +112,9 @@ impl Exif {
     fn convert_exif_to_datetime(entry: &rexif::ExifEntry) -> Option<chrono::DateTime<Utc>> {
         match &entry.value {
             TagValue::Ascii(val) => {
-                let result = Utc.datetime_from_str(val, "%Y:%m:%d %H:%M:%S");
+                let result = NaiveDateTime::parse_from_str(val, "%Y:%m:%d %H:%M:%S");
                 if let Ok(datetime) = result {
-                    Some(datetime)
+                    Some(datetime.and_utc())
                 } else {
                     None
                 }
@@ -0,0 +1,66 @@
+use anyhow::Result;
+use core::fmt::Write;
+use image::{imageops::FilterType, DynamicImage};
+use sha2::{Digest, Sha256};
+
+pub fn compute_sha256_hash(bytes: &[u8]) -> Result<String> {
+    let mut hasher = Sha256::new();
+    hasher.update(bytes);
+    let hash = hasher.finalize();
+
+    // Convert hash bytes to hex string
+    let hash = hash.as_slice();
+    let mut hash_hex = String::with_capacity(2 * hash.len());
+    for byte in hash {
+        write!(hash_hex, "{byte:02x}")?;
+    }
+
+    Ok(hash_hex)
+}
+
+/// Compute the difference hash (dHash) of an image. Images that look alike have hashes that differ in few bits,
+/// even when they were resized or compressed differently.
+pub fn compute_difference_hash(image: &DynamicImage) -> u64 {
+    let image = image.resize_exact(9, 8, FilterType::Triangle).into_luma8();
+
+    let mut hash = 0;
+    for y in 0..8 {
+        for x in 0..8 {
+            hash <<= 1;
+            if image.get_pixel(x, y)[0] < image.get_pixel(x + 1, y)[0] {
+                hash |= 1;
+            }
+        }
+    }
+
+    hash
+}
+
+/// Number of bits in which two perceptual hashes differ.
+pub fn hamming_distance(hash_a: u64, hash_b: u64) -> u32 {
+    (hash_a ^ hash_b).count_ones()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compute_difference_hash, hamming_distance};
+    use image::{DynamicImage, GrayImage, Luma};
+
+    fn gradient(width: u32, height: u32, brightness: f32) -> DynamicImage {
+        let image = GrayImage::from_fn(width, height, |x, y| {
+            let value = ((x * 255 / width) as f32 * 0.7 + (y * 255 / height) as f32 * 0.3) * brightness;
+            Luma([value as u8])
+        });
+        DynamicImage::ImageLuma8(image)
+    }
+
+    #[test]
+    fn difference_hash_ignores_size_and_brightness() {
+        let original = compute_difference_hash(&gradient(300, 200, 1.0));
+        let resized = compute_difference_hash(&gradient(90, 60, 0.8));
+        let mirrored = compute_difference_hash(&gradient(300, 200, 1.0).fliph());
+
+        assert!(hamming_distance(original, resized) <= 4);
+        assert!(hamming_distance(original, mirrored) > 32);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::hashing::compute_difference_hash;
 use anyhow::{anyhow, Result};
 use image::{DynamicImage, ImageFormat};
 
@@ -10,6 +11,8 @@ pub struct Image {
     pub bytes_original: Vec<u8>,
     pub bytes_thumbnail: Vec<u8>,
     pub bytes_preview: Vec<u8>,
+    /// Difference hash of the thumbnail.
+    pub perceptual_hash: u64,
 }
 
 impl Image {
@@ -51,6 +54,7 @@ impl Image {
             bytes_original: bytes.to_vec(),
             bytes_thumbnail: Self::get_image_bytes(&image_thumbnail)?,
             bytes_preview: Self::get_image_bytes(&image_preview)?,
+            perceptual_hash: compute_difference_hash(&image_thumbnail),
         })
     }
 
@@ -0,0 +1,99 @@
+use crate::{encryption, hashing};
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+/// Storage for the encryption keys of the current user.
+pub trait KeyStore: Send + Sync {
+    /// Get user's master encryption key
+    fn get_master_key(&self) -> Vec<u8>;
+
+    /// Set user's master encryption key
+    fn set_master_key(&self, key: &[u8]) -> Result<()>;
+
+    /// Get a share's encryption key
+    fn get_share_key(&self, share_id: &str) -> Result<Option<Vec<u8>>>;
+
+    /// Set a share's encryption key
+    fn set_share_key(&self, share_id: &str, key: &[u8]) -> Result<()>;
+}
+
+/// Keeps keys in memory only, for clients that log in on every run.
+#[derive(Default)]
+pub struct MemoryKeyStore {
+    master_key: RwLock<Vec<u8>>,
+    share_keys: RwLock<HashMap<String, Vec<u8>>>,
+}
+
+impl KeyStore for MemoryKeyStore {
+    fn get_master_key(&self) -> Vec<u8> {
+        self.master_key.read().expect("Key store lock poisoned").clone()
+    }
+
+    fn set_master_key(&self, key: &[u8]) -> Result<()> {
+        let mut master_key = self
+            .master_key
+            .write()
+            .map_err(|_| anyhow!("Key store lock poisoned"))?;
+        *master_key = key.to_vec();
+        Ok(())
+    }
+
+    fn get_share_key(&self, share_id: &str) -> Result<Option<Vec<u8>>> {
+        let share_keys = self.share_keys.read().map_err(|_| anyhow!("Key store lock poisoned"))?;
+        Ok(share_keys.get(share_id).cloned())
+    }
+
+    fn set_share_key(&self, share_id: &str, key: &[u8]) -> Result<()> {
+        let mut share_keys = self
+            .share_keys
+            .write()
+            .map_err(|_| anyhow!("Key store lock poisoned"))?;
+        share_keys.insert(share_id.into(), key.to_vec());
+        Ok(())
+    }
+}
+
+/// Derive a symmetric encryption key from a user's credentials
+pub fn get_key_from_user_credentials(username: &str, password: &str) -> Result<Vec<u8>> {
+    if username.is_empty() {
+        Err(anyhow!("Username is empty"))
+    } else if password.is_empty() {
+        Err(anyhow!("Password is empty"))
+    } else {
+        // The salt is based on username; hash username to ensure minimum length.
+        let salt = &hashing::compute_sha256_hash(username.as_bytes())?[..20];
+        let password_derived_key = encryption::symmetric::derive_key_from_string(password, salt)?;
+        Ok(password_derived_key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_key_from_user_credentials_consistency() {
+        let username = "username";
+        let password = "password";
+
+        let key_base = get_key_from_user_credentials(username, password).unwrap();
+
+        // Identical credentials should give same key
+        let key = get_key_from_user_credentials(username, password).unwrap();
+        assert_eq!(key_base, key);
+
+        // Any change in credentials should give a different key
+        let key = get_key_from_user_credentials(username, "other_password").unwrap();
+        assert_ne!(key_base, key);
+        let key = get_key_from_user_credentials("other_username", password).unwrap();
+        assert_ne!(key_base, key);
+    }
+
+    #[test]
+    fn get_key_from_user_credentials_bad_input() {
+        assert!(get_key_from_user_credentials("username", "").is_err());
+        assert!(get_key_from_user_credentials("", "password").is_err());
+        assert!(get_key_from_user_credentials("", "").is_err());
+    }
+}
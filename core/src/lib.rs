@@ -0,0 +1,14 @@
+//! Platform-neutral core of the upholi clients: encryption, image processing and the client-side data model,
+//! used by both the web app and the command-line client.
+
+pub mod api_client;
+pub mod client;
+pub mod encryption;
+pub mod exif;
+pub mod hashing;
+pub mod images;
+pub mod keys;
+pub mod models;
+mod multipart;
+pub mod repository;
+pub mod search;
@@ -1,10 +1,11 @@
-use super::{AlbumShareDataPhoto, LibraryPhoto};
+use super::{base64_bytes, AlbumShareDataPhoto, LibraryPhoto};
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Album {
     pub id: String,
+    #[serde(with = "base64_bytes")]
     pub key: Vec<u8>,
     pub title: String,
     pub thumbnail_photo_id: Option<String>,
@@ -0,0 +1,69 @@
+//! Serializes byte fields of items, such as keys, as base64 strings in JSON, rather than as arrays of numbers.
+//! Use with `#[serde(with = "base64_bytes")]`.
+//!
+//! Other formats, such as the bincode of items stored before items were stored as JSON, are not affected.
+use base64::prelude::*;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    if serializer.is_human_readable() {
+        serializer.serialize_str(&BASE64_STANDARD.encode(bytes))
+    } else {
+        bytes.serialize(serializer)
+    }
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+    // Arrays of numbers are read too, as written by the first version of the JSON format.
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Encoded {
+        Base64(String),
+        Bytes(Vec<u8>),
+    }
+
+    if deserializer.is_human_readable() {
+        match Encoded::deserialize(deserializer)? {
+            Encoded::Base64(encoded) => BASE64_STANDARD.decode(encoded).map_err(de::Error::custom),
+            Encoded::Bytes(bytes) => Ok(bytes),
+        }
+    } else {
+        Vec::<u8>::deserialize(deserializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Item {
+        #[serde(with = "super")]
+        key: Vec<u8>,
+    }
+
+    #[test]
+    fn json_uses_base64() {
+        let item = Item { key: vec![1, 2, 255] };
+        let json = serde_json::to_string(&item).unwrap();
+
+        assert_eq!(json, r#"{"key":"AQL/"}"#);
+        assert_eq!(serde_json::from_str::<Item>(&json).unwrap(), item);
+    }
+
+    #[test]
+    fn json_reads_arrays() {
+        let item: Item = serde_json::from_str(r#"{"key":[1,2,255]}"#).unwrap();
+
+        assert_eq!(item.key, vec![1, 2, 255]);
+    }
+
+    #[test]
+    fn bincode_is_unchanged() {
+        let key = vec![1, 2, 255];
+        let bytes = bincode::serialize(&Item { key: key.clone() }).unwrap();
+
+        assert_eq!(bytes, bincode::serialize(&key).unwrap());
+        assert_eq!(bincode::deserialize::<Item>(&bytes).unwrap().key, key);
+    }
+}
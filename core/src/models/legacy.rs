@@ -0,0 +1,212 @@
+//! Layouts of items as they were stored using bincode, before items were stored as JSON.
+//!
+//! Bincode does not store field names, so these must not change. Items are converted to the current
+//! models when they are read, and stored as JSON the next time they are saved.
+
+use super::{Album, LibraryAlbum, LibraryShare, Share};
+use crate::exif::Exif;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+#[derive(Deserialize)]
+pub enum ItemVariant {
+    MasterKey(Vec<u8>),
+    Library(Library),
+    Photo(Photo),
+    Album(Album),
+    Share(Share),
+    UploadJournal(UploadJournal),
+}
+
+impl From<ItemVariant> for crate::repository::ItemVariant {
+    fn from(value: ItemVariant) -> Self {
+        match value {
+            ItemVariant::MasterKey(key) => Self::MasterKey(key),
+            ItemVariant::Library(library) => Self::Library(library.into()),
+            ItemVariant::Photo(photo) => Self::Photo(photo.into()),
+            ItemVariant::Album(album) => Self::Album(album),
+            ItemVariant::Share(share) => Self::Share(share),
+            ItemVariant::UploadJournal(journal) => Self::UploadJournal(journal.into()),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct Library {
+    pub photos: Vec<LibraryPhoto>,
+    pub albums: Vec<LibraryAlbum>,
+    pub shares: Vec<LibraryShare>,
+}
+
+impl From<Library> for super::Library {
+    fn from(value: Library) -> Self {
+        Self {
+            photos: value.photos.into_iter().map(Into::into).collect(),
+            albums: value.albums,
+            shares: value.shares,
+            trash: vec![],
+            search_index_id: None,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct LibraryPhoto {
+    pub id: String,
+    pub key: Vec<u8>,
+    pub hash: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl From<LibraryPhoto> for super::LibraryPhoto {
+    fn from(value: LibraryPhoto) -> Self {
+        Self {
+            id: value.id,
+            key: value.key,
+            hash: value.hash,
+            width: value.width,
+            height: value.height,
+            perceptual_hash: None,
+            favourite: false,
+            rating: 0,
+            tags: vec![],
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct Photo {
+    pub id: String,
+    pub hash: String,
+    pub width: u32,
+    pub height: u32,
+    pub timestamp: i64,
+    pub content_type: String,
+    pub exif: Option<Exif>,
+    pub nonce_thumbnail: String,
+    pub nonce_preview: String,
+    pub nonce_original: String,
+}
+
+impl From<Photo> for super::Photo {
+    fn from(value: Photo) -> Self {
+        Self {
+            id: value.id,
+            hash: value.hash,
+            width: value.width,
+            height: value.height,
+            timestamp: value.timestamp,
+            content_type: value.content_type,
+            exif: value.exif,
+            nonce_thumbnail: value.nonce_thumbnail,
+            nonce_preview: value.nonce_preview,
+            nonce_original: value.nonce_original,
+            perceptual_hash: None,
+            file_name: String::new(),
+            caption: String::new(),
+            description: String::new(),
+            tags: vec![],
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct UploadJournal {
+    pub photos: Vec<LibraryPhoto>,
+    pub albums: HashMap<String, Vec<String>>,
+}
+
+impl From<UploadJournal> for super::UploadJournal {
+    fn from(value: UploadJournal) -> Self {
+        Self {
+            photos: value.photos.into_iter().map(Into::into).collect(),
+            albums: value.albums,
+            updated_on: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        encryption::symmetric::{encrypt_slice, generate_key},
+        models::EncryptedItem,
+        repository,
+    };
+    use base64::prelude::*;
+    use serde::Serialize;
+
+    /// The layout of a library as it was stored using bincode.
+    #[derive(Serialize)]
+    enum StoredItemVariant {
+        #[allow(dead_code)]
+        MasterKey(Vec<u8>),
+        Library {
+            photos: Vec<(String, Vec<u8>, String, u32, u32)>,
+            albums: Vec<(String, Vec<u8>)>,
+            shares: Vec<(String, Vec<u8>, String, String)>,
+        },
+    }
+
+    #[test]
+    fn decrypt_legacy_library() {
+        let key = generate_key();
+        let stored = StoredItemVariant::Library {
+            photos: vec![("photo".into(), vec![1, 2, 3], "hash".into(), 20, 10)],
+            albums: vec![("album".into(), vec![4, 5, 6])],
+            shares: vec![],
+        };
+        let encrypted = encrypt_slice(&key, &bincode::serialize(&stored).unwrap()).unwrap();
+        let item = EncryptedItem {
+            base64: BASE64_STANDARD.encode(encrypted.bytes),
+            nonce: encrypted.nonce,
+        };
+
+        let decrypted = item
+            .decrypt_or_legacy::<repository::ItemVariant, super::ItemVariant>(&key)
+            .unwrap();
+
+        let repository::ItemVariant::Library(library) = decrypted else {
+            panic!("Expected a library");
+        };
+        assert_eq!(library.photos.len(), 1);
+        assert_eq!(library.photos[0].id, "photo");
+        assert_eq!(library.photos[0].width, 20);
+        assert_eq!(library.photos[0].perceptual_hash, None);
+        assert!(!library.photos[0].favourite);
+        assert_eq!(library.photos[0].rating, 0);
+        assert!(library.photos[0].tags.is_empty());
+        assert_eq!(library.albums[0].key, vec![4, 5, 6]);
+    }
+
+    #[test]
+    fn decrypt_current_library() {
+        let key = generate_key();
+        let mut library = crate::models::Library::default();
+        library.photos.push(crate::models::LibraryPhoto {
+            id: "photo".into(),
+            key: vec![],
+            hash: "hash".into(),
+            width: 20,
+            height: 10,
+            perceptual_hash: Some(42),
+            favourite: true,
+            rating: 4,
+            tags: vec!["beach".into()],
+        });
+        let item = EncryptedItem::from(&key, &repository::ItemVariant::Library(library)).unwrap();
+
+        let decrypted = item
+            .decrypt_or_legacy::<repository::ItemVariant, super::ItemVariant>(&key)
+            .unwrap();
+
+        let repository::ItemVariant::Library(library) = decrypted else {
+            panic!("Expected a library");
+        };
+        assert_eq!(library.photos[0].perceptual_hash, Some(42));
+        assert!(library.photos[0].favourite);
+        assert_eq!(library.photos[0].rating, 4);
+        assert_eq!(library.photos[0].tags, vec!["beach".to_string()]);
+    }
+}
@@ -0,0 +1,291 @@
+use super::{base64_bytes, Photo};
+use crate::hashing::hamming_distance;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct Library {
+    pub photos: Vec<LibraryPhoto>,
+    pub albums: Vec<LibraryAlbum>,
+    pub shares: Vec<LibraryShare>,
+    /// Photos moved to the trash. They are no longer part of the library or albums, but can be restored.
+    #[serde(default)]
+    pub trash: Vec<TrashedPhoto>,
+    /// ID of the item containing the search index, or None if the library has not been searched yet.
+    #[serde(default)]
+    pub search_index_id: Option<String>,
+}
+
+impl Library {
+    /// Find the encryption key for given item ID.
+    pub fn find_encryption_key(&self, item_id: &str) -> Option<&Vec<u8>> {
+        let find_as_album = || self.albums.iter().find(|i| i.id == item_id).map(|i| &i.key);
+        let find_as_photo = || self.photos.iter().find(|i| i.id == item_id).map(|i| &i.key);
+        let find_as_share = || self.shares.iter().find(|i| i.id == item_id).map(|i| &i.key);
+        let find_as_trashed_photo = || self.trash.iter().find(|i| i.photo.id == item_id).map(|i| &i.photo.key);
+
+        None.or_else(find_as_album)
+            .or_else(find_as_photo)
+            .or_else(find_as_share)
+            .or_else(find_as_trashed_photo)
+    }
+
+    /// All tags of photos in the library, sorted alphabetically.
+    pub fn tags(&self) -> Vec<String> {
+        distinct_tags(self.photos.iter().flat_map(|photo| &photo.tags))
+    }
+
+    /// Find groups of photos whose perceptual hashes differ in at most `max_distance` bits.
+    /// Photos in a group are ordered by resolution, highest first.
+    pub fn find_possible_duplicates(&self, max_distance: u32) -> Vec<Vec<LibraryPhoto>> {
+        let photos: Vec<(&LibraryPhoto, u64)> = self
+            .photos
+            .iter()
+            .filter_map(|photo| photo.perceptual_hash.map(|hash| (photo, hash)))
+            .collect();
+
+        // Index of the group each photo belongs to, if any
+        let mut photo_groups: Vec<Option<usize>> = vec![None; photos.len()];
+        let mut groups: Vec<Vec<usize>> = vec![];
+        for i in 0..photos.len() {
+            for j in (i + 1)..photos.len() {
+                if hamming_distance(photos[i].1, photos[j].1) > max_distance {
+                    continue;
+                }
+
+                match (photo_groups[i], photo_groups[j]) {
+                    (Some(group_i), Some(group_j)) if group_i != group_j => {
+                        let moved = std::mem::take(&mut groups[group_j]);
+                        for &index in &moved {
+                            photo_groups[index] = Some(group_i);
+                        }
+                        groups[group_i].extend(moved);
+                    }
+                    (Some(_), Some(_)) => {}
+                    (Some(group), None) => {
+                        photo_groups[j] = Some(group);
+                        groups[group].push(j);
+                    }
+                    (None, Some(group)) => {
+                        photo_groups[i] = Some(group);
+                        groups[group].push(i);
+                    }
+                    (None, None) => {
+                        photo_groups[i] = Some(groups.len());
+                        photo_groups[j] = Some(groups.len());
+                        groups.push(vec![i, j]);
+                    }
+                }
+            }
+        }
+
+        groups
+            .into_iter()
+            .filter(|group| !group.is_empty())
+            .map(|mut group| {
+                group.sort_by_key(|&index| {
+                    let photo = photos[index].0;
+                    (std::cmp::Reverse(photo.width as u64 * photo.height as u64), index)
+                });
+                group.into_iter().map(|index| photos[index].0.clone()).collect()
+            })
+            .collect()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryPhoto {
+    pub id: String,
+    #[serde(with = "base64_bytes")]
+    pub key: Vec<u8>,
+    pub hash: String,
+    pub width: u32,
+    pub height: u32,
+    #[serde(default)]
+    pub perceptual_hash: Option<u64>,
+    #[serde(default)]
+    pub favourite: bool,
+    /// Number of stars given to the photo, from 1 to 5, or 0 if it is not rated.
+    #[serde(default)]
+    pub rating: u8,
+    /// Copy of the tags of the photo, so photos can be filtered without fetching each of them.
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+impl LibraryPhoto {
+    pub fn from(photo: &Photo, key: Vec<u8>) -> Self {
+        Self {
+            id: photo.id.clone(),
+            key,
+            hash: photo.hash.clone(),
+            width: photo.width,
+            height: photo.height,
+            perceptual_hash: photo.perceptual_hash,
+            favourite: false,
+            rating: 0,
+            tags: photo.tags.clone(),
+        }
+    }
+
+    /// Whether the photo has given tag, ignoring case.
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.to_lowercase() == tag.to_lowercase())
+    }
+}
+
+/// Trim tags and remove empty and duplicate ones, ignoring case. The first occurrence of a tag is kept.
+pub fn normalize_tags(tags: impl IntoIterator<Item = String>) -> Vec<String> {
+    let mut normalized: Vec<String> = vec![];
+    for tag in tags {
+        let tag = tag.trim();
+        if !tag.is_empty() && !normalized.iter().any(|t| t.to_lowercase() == tag.to_lowercase()) {
+            normalized.push(tag.to_string());
+        }
+    }
+    normalized
+}
+
+/// Distinct tags, ignoring case, sorted alphabetically.
+pub fn distinct_tags<'a>(tags: impl IntoIterator<Item = &'a String>) -> Vec<String> {
+    let mut tags = normalize_tags(tags.into_iter().cloned());
+    tags.sort_by_key(|tag| tag.to_lowercase());
+    tags
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashedPhoto {
+    pub photo: LibraryPhoto,
+    /// IDs of the albums the photo was in, to add it back to when it is restored.
+    pub albums: Vec<String>,
+    /// Unix timestamp of when the photo was moved to the trash.
+    pub trashed_on: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LibraryAlbum {
+    pub id: String,
+    #[serde(with = "base64_bytes")]
+    pub key: Vec<u8>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryShare {
+    pub id: String,
+    #[serde(with = "base64_bytes")]
+    pub key: Vec<u8>,
+    pub password: String,
+    pub album_id: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{normalize_tags, Library, LibraryAlbum, LibraryPhoto, LibraryShare, TrashedPhoto};
+
+    #[test]
+    fn library_find_encryption_key() {
+        let mut library = Library::default();
+
+        let album_id = "album";
+        let photo_id = "photo";
+        let share_id = "share";
+        let album_key = b"album".to_vec();
+        let photo_key = b"photo".to_vec();
+        let share_key = b"share".to_vec();
+
+        library.albums.push(LibraryAlbum {
+            id: album_id.into(),
+            key: album_key.clone(),
+        });
+        library.photos.push(LibraryPhoto {
+            id: photo_id.into(),
+            key: photo_key.clone(),
+            hash: String::new(),
+            height: 0,
+            width: 0,
+            perceptual_hash: None,
+            favourite: false,
+            rating: 0,
+            tags: vec![],
+        });
+        library.shares.push(LibraryShare {
+            id: share_id.into(),
+            key: share_key.clone(),
+            album_id: String::new(),
+            password: String::new(),
+        });
+
+        assert_eq!(library.find_encryption_key(album_id).unwrap().to_owned(), album_key);
+        assert_eq!(library.find_encryption_key(photo_id).unwrap().to_owned(), photo_key);
+        assert_eq!(library.find_encryption_key(share_id).unwrap().to_owned(), share_key);
+        assert_eq!(library.find_encryption_key("does-not-exist"), None);
+    }
+
+    #[test]
+    fn library_find_encryption_key_of_trashed_photo() {
+        let mut library = Library::default();
+        let photo_key = b"photo".to_vec();
+        library.trash.push(TrashedPhoto {
+            photo: photo("photo", 100, None),
+            albums: vec![],
+            trashed_on: 0,
+        });
+        library.trash[0].photo.key = photo_key.clone();
+
+        assert_eq!(library.find_encryption_key("photo").unwrap().to_owned(), photo_key);
+    }
+
+    fn photo(id: &str, width: u32, perceptual_hash: Option<u64>) -> LibraryPhoto {
+        LibraryPhoto {
+            id: id.into(),
+            key: vec![],
+            hash: id.into(),
+            width,
+            height: width,
+            perceptual_hash,
+            favourite: false,
+            rating: 0,
+            tags: vec![],
+        }
+    }
+
+    #[test]
+    fn library_find_possible_duplicates() {
+        let mut library = Library::default();
+        library.photos.push(photo("small", 100, Some(0b0000)));
+        library.photos.push(photo("unrelated", 100, Some(u64::MAX)));
+        library.photos.push(photo("large", 400, Some(0b0011)));
+        library.photos.push(photo("no-hash", 100, None));
+        library.photos.push(photo("medium", 200, Some(0b0111)));
+
+        let groups = library.find_possible_duplicates(2);
+        let ids: Vec<Vec<&str>> = groups
+            .iter()
+            .map(|group| group.iter().map(|photo| photo.id.as_str()).collect())
+            .collect();
+
+        assert_eq!(ids, vec![vec!["large", "medium", "small"]]);
+    }
+
+    #[test]
+    fn normalize_tags_trims_and_removes_duplicates() {
+        let tags = normalize_tags(["  Beach ", "", "beach", "Summer", " "].map(String::from));
+
+        assert_eq!(tags, vec!["Beach".to_string(), "Summer".to_string()]);
+    }
+
+    #[test]
+    fn library_tags() {
+        let mut library = Library::default();
+        library.photos.push(photo("one", 100, None));
+        library.photos.push(photo("two", 100, None));
+        library.photos[0].tags = vec!["summer".into(), "Beach".into()];
+        library.photos[1].tags = vec!["beach".into(), "city".into()];
+
+        assert_eq!(library.tags(), vec!["Beach", "city", "summer"]);
+        assert!(library.photos[1].has_tag("BEACH"));
+        assert!(!library.photos[1].has_tag("summer"));
+    }
+}
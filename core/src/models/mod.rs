@@ -0,0 +1,91 @@
+pub use album::*;
+use anyhow::Result;
+use base64::prelude::*;
+pub use library::*;
+pub use photo::*;
+pub use search_index::*;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+pub use share::*;
+pub use upload_journal::*;
+
+mod album;
+pub(crate) mod base64_bytes;
+pub(crate) mod legacy;
+mod library;
+mod photo;
+mod search_index;
+mod share;
+mod upload_journal;
+
+/// First byte of the serialized bytes of items stored as JSON.
+///
+/// Items used to be stored using bincode, which cannot read items with fields added or removed since they were stored.
+/// Serialized as bincode, items start with the `u32` index of their `ItemVariant`, so their first byte is never this one.
+const ITEM_FORMAT_JSON: u8 = 0x80;
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct EncryptedItem {
+    pub base64: String,
+    pub nonce: String,
+}
+
+impl EncryptedItem {
+    pub fn from<T: Serialize>(key: &[u8], item: &T) -> Result<Self> {
+        let mut bytes = vec![ITEM_FORMAT_JSON];
+        serde_json::to_writer(&mut bytes, item)?;
+        let encrypt_result = crate::encryption::symmetric::encrypt_slice(key, &bytes)?;
+        let base64 = BASE64_STANDARD.encode(encrypt_result.bytes);
+        Ok(Self {
+            base64,
+            nonce: encrypt_result.nonce,
+        })
+    }
+
+    pub fn decrypt<TDecrypted: DeserializeOwned>(&self, key: &[u8]) -> Result<TDecrypted> {
+        self.decrypt_or_legacy::<TDecrypted, TDecrypted>(key)
+    }
+
+    /// Decrypt an item, which is laid out as `TLegacy` if it was stored before items were stored as JSON.
+    pub fn decrypt_or_legacy<TDecrypted, TLegacy>(&self, key: &[u8]) -> Result<TDecrypted>
+    where
+        TDecrypted: DeserializeOwned,
+        TLegacy: DeserializeOwned + Into<TDecrypted>,
+    {
+        let nonce = self.nonce.as_bytes();
+        let bytes = BASE64_STANDARD.decode(&self.base64)?;
+        let bytes = crate::encryption::symmetric::decrypt_slice(key, nonce, &bytes)?;
+        match bytes.split_first() {
+            Some((&ITEM_FORMAT_JSON, json)) => Ok(serde_json::from_slice(json)?),
+            _ => Ok(bincode::deserialize::<TLegacy>(&bytes)?.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        encryption::symmetric::generate_key,
+        models::{EncryptedItem, Library},
+    };
+
+    #[test]
+    fn encrypt_decrypt_text_item_bytes() {
+        let key = generate_key();
+        let item = EncryptedItem::from(&key, &key).unwrap();
+        let decrypted: Vec<u8> = item.decrypt(&key).unwrap();
+
+        assert_eq!(key, decrypted);
+    }
+
+    #[test]
+    fn encrypt_decrypt_text_item_instance() {
+        let key = generate_key();
+        let library = Library::default();
+        let item = EncryptedItem::from(&key, &library).unwrap();
+        let decrypted: Library = item.decrypt(&key).unwrap();
+
+        assert_eq!(library.photos.len(), decrypted.photos.len());
+        assert_eq!(library.albums.len(), decrypted.albums.len());
+        assert_eq!(library.shares.len(), decrypted.shares.len());
+    }
+}
@@ -0,0 +1,37 @@
+use crate::exif::Exif;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Photo {
+    pub id: String,
+    /// Hash string of original file bytes
+    pub hash: String,
+    /// Width of photo
+    pub width: u32,
+    /// Height of photo
+    pub height: u32,
+    /// A timestamp of the photo used for sorting purposes.
+    /// To be filles with the datetime a photo was taken on, or uploaded on.
+    pub timestamp: i64,
+    pub content_type: String,
+    pub exif: Option<Exif>,
+    pub nonce_thumbnail: String,
+    pub nonce_preview: String,
+    pub nonce_original: String,
+    /// Difference hash of the thumbnail, to find near-identical photos.
+    /// Missing for photos uploaded before it was computed.
+    #[serde(default)]
+    pub perceptual_hash: Option<u64>,
+    /// Name of the file the photo was uploaded from.
+    /// Empty for photos uploaded before it was stored.
+    #[serde(default)]
+    pub file_name: String,
+    #[serde(default)]
+    pub caption: String,
+    #[serde(default)]
+    pub description: String,
+    /// Free-form tags, also stored in the library to filter photos by.
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
@@ -0,0 +1,132 @@
+use super::{Album, Library, Photo};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Searchable properties of the photos and albums in the library.
+///
+/// The server cannot search encrypted items, so the client builds this index and stores it as an item of its own,
+/// with the ID kept in `Library::search_index_id`.
+/// It is brought up to date with the library before searching; see `Client::search`.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct SearchIndex {
+    pub photos: Vec<SearchIndexPhoto>,
+    pub albums: Vec<SearchIndexAlbum>,
+}
+
+impl SearchIndex {
+    /// Add or replace the entries of photos.
+    pub fn upsert_photos(&mut self, photos: impl IntoIterator<Item = SearchIndexPhoto>) {
+        let mut positions: HashMap<String, usize> = self
+            .photos
+            .iter()
+            .enumerate()
+            .map(|(position, photo)| (photo.id.clone(), position))
+            .collect();
+
+        for photo in photos {
+            match positions.get(&photo.id) {
+                Some(&position) => self.photos[position] = photo,
+                None => {
+                    positions.insert(photo.id.clone(), self.photos.len());
+                    self.photos.push(photo);
+                }
+            }
+        }
+    }
+
+    /// Add or replace the entry of an album.
+    pub fn upsert_album(&mut self, album: SearchIndexAlbum) {
+        match self.albums.iter_mut().find(|a| a.id == album.id) {
+            Some(existing) => *existing = album,
+            None => self.albums.push(album),
+        }
+    }
+
+    /// Remove entries of photos and albums that are no longer in the library.
+    /// Returns whether any entries were removed.
+    pub fn retain_library(&mut self, library: &Library) -> bool {
+        let photo_ids: HashSet<&str> = library.photos.iter().map(|photo| photo.id.as_str()).collect();
+        let album_ids: HashSet<&str> = library.albums.iter().map(|album| album.id.as_str()).collect();
+
+        let (n_photos, n_albums) = (self.photos.len(), self.albums.len());
+        self.photos.retain(|photo| photo_ids.contains(photo.id.as_str()));
+        self.albums.retain(|album| album_ids.contains(album.id.as_str()));
+        self.photos.len() != n_photos || self.albums.len() != n_albums
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchIndexPhoto {
+    pub id: String,
+    pub timestamp: i64,
+    pub camera_make: Option<String>,
+    pub camera_model: Option<String>,
+    pub file_name: String,
+    pub caption: String,
+    pub tags: Vec<String>,
+}
+
+impl From<&Photo> for SearchIndexPhoto {
+    fn from(photo: &Photo) -> Self {
+        let exif = photo.exif.as_ref();
+        Self {
+            id: photo.id.clone(),
+            timestamp: photo.timestamp,
+            camera_make: exif.and_then(|exif| exif.manufactorer.clone()),
+            camera_model: exif.and_then(|exif| exif.model.clone()),
+            file_name: photo.file_name.clone(),
+            caption: photo.caption.clone(),
+            tags: photo.tags.clone(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SearchIndexAlbum {
+    pub id: String,
+    pub title: String,
+    pub photos: Vec<String>,
+}
+
+impl From<&Album> for SearchIndexAlbum {
+    fn from(album: &Album) -> Self {
+        Self {
+            id: album.id.clone(),
+            title: album.title.clone(),
+            photos: album.photos.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn photo(id: &str, caption: &str) -> SearchIndexPhoto {
+        SearchIndexPhoto {
+            id: id.to_string(),
+            timestamp: 0,
+            camera_make: None,
+            camera_model: None,
+            file_name: format!("{id}.jpg"),
+            caption: caption.to_string(),
+            tags: vec![],
+        }
+    }
+
+    #[test]
+    fn upsert_photos_replaces_and_adds_entries() {
+        let mut index = SearchIndex {
+            photos: vec![photo("a", "old"), photo("b", "")],
+            albums: vec![],
+        };
+
+        index.upsert_photos([photo("a", "new"), photo("c", ""), photo("c", "again")]);
+
+        assert_eq!(
+            index.photos,
+            vec![photo("a", "new"), photo("b", ""), photo("c", "again")]
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use super::base64_bytes;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -14,6 +15,7 @@ pub enum ShareData {
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct AlbumShareData {
     pub album_id: String,
+    #[serde(with = "base64_bytes")]
     pub album_key: Vec<u8>,
     pub photos: Vec<AlbumShareDataPhoto>,
 }
@@ -21,6 +23,7 @@ pub struct AlbumShareData {
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct AlbumShareDataPhoto {
     pub id: String,
+    #[serde(with = "base64_bytes")]
     pub key: Vec<u8>,
     pub width: u32,
     pub height: u32,
@@ -0,0 +1,30 @@
+use super::LibraryPhoto;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Photos of an upload batch that are not yet added to the library, and the albums to add photos to.
+///
+/// Stored before each photo is uploaded, so the photos of a batch that was interrupted can still be added
+/// to the library later. Photos in the journal may not have been uploaded completely.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct UploadJournal {
+    pub photos: Vec<LibraryPhoto>,
+    /// Photo IDs to add per album ID.
+    pub albums: HashMap<String, Vec<String>>,
+    /// Unix timestamp of when the journal was last stored. Journals of earlier versions have 0.
+    #[serde(default)]
+    pub updated_on: i64,
+}
+
+impl UploadJournal {
+    pub fn is_empty(&self) -> bool {
+        self.photos.is_empty() && self.albums.is_empty()
+    }
+
+    pub fn add_to_album(&mut self, album_id: &str, photo_id: &str) {
+        let photo_ids = self.albums.entry(album_id.to_string()).or_default();
+        if !photo_ids.iter().any(|id| id == photo_id) {
+            photo_ids.push(photo_id.to_string());
+        }
+    }
+}
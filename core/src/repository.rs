@@ -0,0 +1,248 @@
+use crate::api_client::ApiClient;
+use crate::models::{legacy, *};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, sync::RwLock};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[allow(clippy::large_enum_variant)]
+pub enum ItemVariant {
+    MasterKey(#[serde(with = "crate::models::base64_bytes")] Vec<u8>),
+    Library(Library),
+    Photo(Photo),
+    Album(Album),
+    Share(Share),
+    UploadJournal(UploadJournal),
+    SearchIndex(SearchIndex),
+}
+
+impl TryFrom<ItemVariant> for Vec<u8> {
+    type Error = anyhow::Error;
+
+    fn try_from(value: ItemVariant) -> Result<Self, Self::Error> {
+        if let ItemVariant::MasterKey(key) = value {
+            Ok(key)
+        } else {
+            Err(anyhow!("ItemVariant is not a master key"))
+        }
+    }
+}
+
+impl TryFrom<ItemVariant> for Library {
+    type Error = anyhow::Error;
+
+    fn try_from(value: ItemVariant) -> Result<Self, Self::Error> {
+        if let ItemVariant::Library(library) = value {
+            Ok(library)
+        } else {
+            Err(anyhow!("ItemVariant is not a library"))
+        }
+    }
+}
+
+impl TryFrom<ItemVariant> for Photo {
+    type Error = anyhow::Error;
+
+    fn try_from(value: ItemVariant) -> Result<Self, Self::Error> {
+        if let ItemVariant::Photo(photo) = value {
+            Ok(photo)
+        } else {
+            Err(anyhow!("ItemVariant is not a photo"))
+        }
+    }
+}
+
+impl TryFrom<ItemVariant> for Album {
+    type Error = anyhow::Error;
+
+    fn try_from(value: ItemVariant) -> Result<Self, Self::Error> {
+        if let ItemVariant::Album(album) = value {
+            Ok(album)
+        } else {
+            Err(anyhow!("ItemVariant is not an album"))
+        }
+    }
+}
+
+impl TryFrom<ItemVariant> for Share {
+    type Error = anyhow::Error;
+
+    fn try_from(value: ItemVariant) -> Result<Self, Self::Error> {
+        if let ItemVariant::Share(share) = value {
+            Ok(share)
+        } else {
+            Err(anyhow!("ItemVariant is not a share"))
+        }
+    }
+}
+
+impl TryFrom<ItemVariant> for UploadJournal {
+    type Error = anyhow::Error;
+
+    fn try_from(value: ItemVariant) -> Result<Self, Self::Error> {
+        if let ItemVariant::UploadJournal(journal) = value {
+            Ok(journal)
+        } else {
+            Err(anyhow!("ItemVariant is not an upload journal"))
+        }
+    }
+}
+
+impl TryFrom<ItemVariant> for SearchIndex {
+    type Error = anyhow::Error;
+
+    fn try_from(value: ItemVariant) -> Result<Self, Self::Error> {
+        if let ItemVariant::SearchIndex(index) = value {
+            Ok(index)
+        } else {
+            Err(anyhow!("ItemVariant is not a search index"))
+        }
+    }
+}
+
+impl From<Vec<u8>> for ItemVariant {
+    fn from(value: Vec<u8>) -> Self {
+        ItemVariant::MasterKey(value)
+    }
+}
+
+impl From<Library> for ItemVariant {
+    fn from(value: Library) -> Self {
+        ItemVariant::Library(value)
+    }
+}
+
+impl From<Photo> for ItemVariant {
+    fn from(value: Photo) -> Self {
+        ItemVariant::Photo(value)
+    }
+}
+
+impl From<Album> for ItemVariant {
+    fn from(value: Album) -> Self {
+        ItemVariant::Album(value)
+    }
+}
+
+impl From<Share> for ItemVariant {
+    fn from(value: Share) -> Self {
+        ItemVariant::Share(value)
+    }
+}
+
+impl From<UploadJournal> for ItemVariant {
+    fn from(value: UploadJournal) -> Self {
+        ItemVariant::UploadJournal(value)
+    }
+}
+
+impl From<SearchIndex> for ItemVariant {
+    fn from(value: SearchIndex) -> Self {
+        ItemVariant::SearchIndex(value)
+    }
+}
+
+/// Gets and sets encrypted items through the API, and caches them decrypted.
+pub struct Repository<'a> {
+    api_client: &'a ApiClient,
+    cache: RwLock<HashMap<String, ItemVariant>>,
+}
+
+impl<'a> Repository<'a> {
+    pub fn new(api_client: &'a ApiClient) -> Self {
+        Self {
+            api_client,
+            cache: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub async fn get(&self, item_id: &str, key: &[u8]) -> Result<Option<ItemVariant>> {
+        let is_cached = self.cache.read().unwrap().contains_key(item_id);
+
+        // Try to fetch it from API if it is not in the cache
+        if !is_cached {
+            if let Some(item) = self.api_client.get_item(item_id).await? {
+                let item = item.decrypt_or_legacy::<ItemVariant, legacy::ItemVariant>(key)?;
+                let mut cache = self.cache.write().unwrap();
+                cache.insert(item_id.to_string(), item);
+            }
+        }
+
+        // Get the item from cache and return it
+        let cache = self.cache.read().unwrap();
+        let item = cache.get(item_id);
+        match item {
+            Some(item) => Ok(Some(item.to_owned())),
+            None => Ok(None),
+        }
+    }
+
+    pub async fn get_or(&self, item_id: &str, key: &[u8], create: &dyn Fn() -> ItemVariant) -> Result<ItemVariant> {
+        let item = self.get(item_id, key).await?;
+
+        let item = match item {
+            Some(item) => item,
+            None => {
+                let item = create();
+                self.set(item_id, key, item.clone()).await?;
+                item
+            }
+        };
+
+        Ok(item)
+    }
+
+    pub async fn set(&self, item_id: &str, key: &[u8], item: ItemVariant) -> Result<()> {
+        let text_item = EncryptedItem::from(key, &item)?;
+        self.api_client.set_item(item_id, &text_item).await?;
+
+        let mut cache = self.cache.write().unwrap();
+        cache.insert(item_id.to_string(), item);
+
+        Ok(())
+    }
+
+    pub async fn delete(&self, item_id: &str) -> Result<()> {
+        self.cache.write().unwrap().remove(item_id);
+        self.api_client.delete_item(item_id).await?;
+
+        Ok(())
+    }
+
+    pub async fn delete_many(&self, item_ids: &[String]) -> Result<()> {
+        let existing_items = self.get_existing_items(item_ids);
+        self.api_client.delete_items(existing_items).await?;
+
+        Ok(())
+    }
+
+    /// Forget all cached items, so changes made by other clients are picked up.
+    pub fn clear_cache(&self) {
+        self.cache.write().unwrap().clear();
+    }
+
+    pub fn get_cached_shares(&self) -> Result<Vec<Share>> {
+        let cache = self.cache.read().unwrap();
+        let shares = cache
+            .values()
+            .filter_map(|item| {
+                if let ItemVariant::Share(share) = item {
+                    Some(share.to_owned())
+                } else {
+                    None
+                }
+            })
+            .collect();
+        Ok(shares)
+    }
+
+    /// Filter given list of item IDs and return the ones that exist.
+    fn get_existing_items(&self, item_ids: &[String]) -> Vec<String> {
+        let cache = self.cache.read().unwrap();
+        item_ids
+            .iter()
+            .filter(|&id| cache.contains_key(id))
+            .map(|id| id.to_owned())
+            .collect()
+    }
+}
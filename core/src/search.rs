@@ -0,0 +1,231 @@
+//! Searching the library using the client-built `SearchIndex`.
+//!
+//! A query consists of terms separated by whitespace. A term is either free text, or a filter written as
+//! `name:value`, such as `camera:Pixel year:2023 tag:beach`. Values containing whitespace are put in
+//! double quotes: `album:"Holiday 2023"`. A photo matches a query if it matches all of its terms.
+
+use crate::models::{SearchIndex, SearchIndexAlbum, SearchIndexPhoto};
+use anyhow::{anyhow, Result};
+use chrono::{TimeZone, Utc};
+
+#[derive(Debug, PartialEq)]
+pub enum SearchTerm {
+    /// Camera make or model containing the value.
+    Camera(String),
+    /// Year the photo was taken, or uploaded if unknown.
+    Year(i32),
+    /// Date the photo was taken, as `YYYY-MM-DD`, or a prefix of it such as `YYYY-MM`.
+    Date(String),
+    Tag(String),
+    /// Title of an album containing the photo containing the value.
+    Album(String),
+    Caption(String),
+    /// Original file name containing the value.
+    File(String),
+    /// Text contained in any of the above, other than dates.
+    Text(String),
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct SearchQuery {
+    pub terms: Vec<SearchTerm>,
+}
+
+impl SearchQuery {
+    pub fn parse(query: &str) -> Result<Self> {
+        let terms = tokenize(query)?
+            .into_iter()
+            .map(|token| parse_term(&token))
+            .collect::<Result<Vec<SearchTerm>>>()?;
+
+        Ok(Self { terms })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    /// IDs of the photos in the index matching this query.
+    pub fn find<'i>(&self, index: &'i SearchIndex) -> Vec<&'i str> {
+        index
+            .photos
+            .iter()
+            .filter(|photo| {
+                let albums: Vec<&SearchIndexAlbum> = index
+                    .albums
+                    .iter()
+                    .filter(|album| album.photos.contains(&photo.id))
+                    .collect();
+                self.matches(photo, &albums)
+            })
+            .map(|photo| photo.id.as_str())
+            .collect()
+    }
+
+    /// Whether a photo, in given albums, matches all terms of this query.
+    pub fn matches(&self, photo: &SearchIndexPhoto, albums: &[&SearchIndexAlbum]) -> bool {
+        let date = Utc
+            .timestamp_opt(photo.timestamp, 0)
+            .single()
+            .map(|date| date.format("%Y-%m-%d").to_string());
+        let camera = [&photo.camera_make, &photo.camera_model]
+            .into_iter()
+            .flatten()
+            .cloned()
+            .collect::<Vec<String>>()
+            .join(" ");
+
+        self.terms.iter().all(|term| match term {
+            SearchTerm::Camera(value) => contains(&camera, value),
+            SearchTerm::Year(year) => date
+                .as_ref()
+                .is_some_and(|date| date.starts_with(&format!("{year:04}-"))),
+            SearchTerm::Date(value) => date.as_ref().is_some_and(|date| date.starts_with(value.as_str())),
+            SearchTerm::Tag(value) => photo.tags.iter().any(|tag| tag.to_lowercase() == *value),
+            SearchTerm::Album(value) => albums.iter().any(|album| contains(&album.title, value)),
+            SearchTerm::Caption(value) => contains(&photo.caption, value),
+            SearchTerm::File(value) => contains(&photo.file_name, value),
+            SearchTerm::Text(value) => {
+                contains(&camera, value)
+                    || contains(&photo.caption, value)
+                    || contains(&photo.file_name, value)
+                    || photo.tags.iter().any(|tag| contains(tag, value))
+                    || albums.iter().any(|album| contains(&album.title, value))
+            }
+        })
+    }
+}
+
+/// Whether `text` contains `value`, which must be lowercase, ignoring case.
+fn contains(text: &str, value: &str) -> bool {
+    text.to_lowercase().contains(value)
+}
+
+/// Split a query on whitespace outside of double quotes, and remove the quotes.
+fn tokenize(query: &str) -> Result<Vec<String>> {
+    let mut tokens = vec![];
+    let mut token = String::new();
+    let mut in_quotes = false;
+
+    for c in query.chars() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            c if c.is_whitespace() && !in_quotes => {
+                if !token.is_empty() {
+                    tokens.push(std::mem::take(&mut token));
+                }
+            }
+            c => token.push(c),
+        }
+    }
+
+    if in_quotes {
+        return Err(anyhow!("Missing closing quote"));
+    }
+    if !token.is_empty() {
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+fn parse_term(token: &str) -> Result<SearchTerm> {
+    let Some((name, value)) = token.split_once(':') else {
+        return Ok(SearchTerm::Text(token.to_lowercase()));
+    };
+
+    let name = name.to_lowercase();
+    let value = value.trim().to_lowercase();
+    if value.is_empty() {
+        return Err(anyhow!("No value given for '{name}'"));
+    }
+
+    match name.as_str() {
+        "camera" => Ok(SearchTerm::Camera(value)),
+        "year" => value
+            .parse()
+            .map(SearchTerm::Year)
+            .map_err(|_| anyhow!("Invalid year '{value}'")),
+        "date" => Ok(SearchTerm::Date(value)),
+        "tag" => Ok(SearchTerm::Tag(value)),
+        "album" => Ok(SearchTerm::Album(value)),
+        "caption" => Ok(SearchTerm::Caption(value)),
+        "file" => Ok(SearchTerm::File(value)),
+        _ => Err(anyhow!("Unknown filter '{name}'")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SearchQuery, SearchTerm};
+    use crate::models::{SearchIndex, SearchIndexAlbum, SearchIndexPhoto};
+
+    #[test]
+    fn parse_query() {
+        let query = SearchQuery::parse(r#"camera:Pixel  year:2023 tag:Beach album:"Holiday 2023" Sunset"#).unwrap();
+
+        assert_eq!(
+            query.terms,
+            vec![
+                SearchTerm::Camera("pixel".into()),
+                SearchTerm::Year(2023),
+                SearchTerm::Tag("beach".into()),
+                SearchTerm::Album("holiday 2023".into()),
+                SearchTerm::Text("sunset".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_query_rejects_invalid_terms() {
+        assert!(SearchQuery::parse("year:last").is_err());
+        assert!(SearchQuery::parse("color:red").is_err());
+        assert!(SearchQuery::parse("tag:").is_err());
+        assert!(SearchQuery::parse(r#"album:"Holiday"#).is_err());
+        assert!(SearchQuery::parse("  ").unwrap().is_empty());
+    }
+
+    #[test]
+    fn find_photos() {
+        let index = SearchIndex {
+            photos: vec![
+                photo(
+                    "beach",
+                    1_688_169_600,
+                    Some("Google"),
+                    Some("Pixel 7"),
+                    &["Beach", "summer"],
+                ),
+                photo("city", 1_688_169_600, Some("Canon"), Some("EOS R6"), &["city"]),
+                photo("old", 1_262_304_000, None, None, &["beach"]),
+            ],
+            albums: vec![SearchIndexAlbum {
+                id: "album".into(),
+                title: "Holiday 2023".into(),
+                photos: vec!["beach".into(), "city".into()],
+            }],
+        };
+        let find = |query: &str| SearchQuery::parse(query).unwrap().find(&index);
+
+        assert_eq!(find("camera:pixel year:2023 tag:beach"), vec!["beach"]);
+        assert_eq!(find("tag:beach"), vec!["beach", "old"]);
+        assert_eq!(find("date:2023-07"), vec!["beach", "city"]);
+        assert_eq!(find("year:2010"), vec!["old"]);
+        assert_eq!(find(r#"album:"holiday 2023" canon"#), vec!["city"]);
+        assert_eq!(find("file:old.jpg"), vec!["old"]);
+        assert_eq!(find("summer"), vec!["beach"]);
+        assert_eq!(find(""), vec!["beach", "city", "old"]);
+    }
+
+    fn photo(id: &str, timestamp: i64, make: Option<&str>, model: Option<&str>, tags: &[&str]) -> SearchIndexPhoto {
+        SearchIndexPhoto {
+            id: id.into(),
+            timestamp,
+            camera_make: make.map(String::from),
+            camera_model: model.map(String::from),
+            file_name: format!("{id}.jpg"),
+            caption: String::new(),
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+        }
+    }
+}
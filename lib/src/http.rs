@@ -1,11 +1,34 @@
+use serde::{Deserialize, Serialize};
+
+/// Name of the cookie holding the CSRF token of a session. Unlike the session cookie, it is readable by the app.
+pub const CSRF_COOKIE_NAME: &str = ".uph-csrf";
+/// Header in which the CSRF token must be sent back with every API request that is not a GET request.
+pub const CSRF_HEADER_NAME: &str = "x-csrf-token";
+
+/// What an API token may be used for.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenScope {
+    /// Get items and files
+    Read,
+    /// Create and update items and files, update shares of albums, and delete upload journals
+    Upload,
+    /// Use the admin API, if the token's user is an admin
+    Admin,
+}
+
 /// API HTTP request models
 pub mod request {
+    use super::TokenScope;
     use serde::{Deserialize, Serialize};
 
     #[derive(Serialize, Deserialize)]
     pub struct CreateUserRequest {
         pub username: String,
         pub password: String,
+        /// Required when open registration is disabled
+        #[serde(default)]
+        pub invite_code: Option<String>,
     }
 
     #[derive(Serialize, Deserialize)]
@@ -14,6 +37,12 @@ pub mod request {
         pub password: String,
     }
 
+    #[derive(Serialize, Deserialize)]
+    pub struct DeleteUserRequest {
+        /// Current password of the user, to confirm the deletion
+        pub password: String,
+    }
+
     #[derive(Serialize, Deserialize)]
     pub struct AuthorizeShareRequest {
         pub password: String,
@@ -31,14 +60,155 @@ pub mod request {
     pub struct DeleteManyRequest {
         pub ids: Vec<String>,
     }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct TrashRequest {
+        pub entries: Vec<TrashEntryRequest>,
+    }
+
+    /// Items and files to delete together once they expire, such as a photo and its files
+    #[derive(Serialize, Deserialize)]
+    pub struct TrashEntryRequest {
+        /// ID of the entry, such as the ID of the photo
+        pub id: String,
+        pub item_ids: Vec<String>,
+        pub file_ids: Vec<String>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct UpdateUserRequest {
+        /// Disable or enable logging in. Omit to leave unchanged.
+        pub disabled: Option<bool>,
+        /// Grant or revoke admin rights. Omit to leave unchanged.
+        pub is_admin: Option<bool>,
+        /// Set the user's own storage quota in bytes, or 0 to remove it so the default quota applies.
+        /// Omit to leave unchanged.
+        #[serde(default)]
+        pub quota_bytes: Option<u64>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct ResetPasswordRequest {
+        pub password: String,
+        /// Must be true. All data of the user is encrypted with keys derived from their current password,
+        /// so after a reset none of it can be read anymore, unless the password is set back to what it was.
+        pub confirm_data_loss: bool,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct CreateApiTokenRequest {
+        /// Describes what the token is used for, e.g. the name of the device using it
+        pub name: String,
+        pub scopes: Vec<TokenScope>,
+    }
 }
 
 /// API HTTP response models
 pub mod response {
+    use super::TokenScope;
     use serde::{Deserialize, Serialize};
 
+    /// Code identifying the kind of error in an `ErrorResponse`
+    #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+    #[serde(rename_all = "snake_case")]
+    pub enum ErrorCode {
+        BadRequest,
+        InvalidId,
+        Unauthorized,
+        Forbidden,
+        AccountDisabled,
+        InviteRequired,
+        NotFound,
+        Conflict,
+        UsernameTaken,
+        IdInUse,
+        QuotaExceeded,
+        InvalidCsrfToken,
+        Internal,
+    }
+
+    /// Body of every API response with an error status code
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+    pub struct ErrorResponse {
+        pub code: ErrorCode,
+        /// Description of the error, suitable for showing to users
+        pub message: String,
+    }
+
     #[derive(Serialize, Deserialize)]
     pub struct CreatedResult {
         pub id: String,
     }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct AuthenticateUserResult {
+        /// The user's password was reset by an admin, and their existing data can no longer be decrypted.
+        /// New keys must be created, after which the reset can be cleared.
+        pub password_reset: bool,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+    pub struct UserResult {
+        pub id: String,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+    pub struct UsageResult {
+        /// Total size in bytes of all files stored by the user
+        pub bytes_used: u64,
+        /// Storage quota in bytes, if any quota applies to the user
+        pub bytes_quota: Option<u64>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct UserDeletionResult {
+        /// Number of files the user had when the deletion was requested
+        pub files_total: u64,
+        pub files_deleted: u64,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct AdminUserResult {
+        pub id: String,
+        pub username: String,
+        pub is_admin: bool,
+        pub disabled: bool,
+        /// The user's password was reset, and they have not logged in since
+        pub password_reset: bool,
+        pub usage: UsageResult,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct InviteResult {
+        pub code: String,
+        /// Unix timestamp of when the invite was created
+        pub created_on: i64,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct ApiTokenResult {
+        pub id: String,
+        pub name: String,
+        pub scopes: Vec<TokenScope>,
+        /// Unix timestamp of when the token was created
+        pub created_on: i64,
+        /// Unix timestamp of when the token was last used, if ever
+        pub last_used_on: Option<i64>,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+    pub struct TrashEntryResult {
+        pub id: String,
+        /// Unix timestamp of when the entry was moved to the trash
+        pub trashed_on: i64,
+        /// Unix timestamp after which the entry is deleted, if entries expire
+        pub expires_on: Option<i64>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct CreatedApiTokenResult {
+        pub id: String,
+        /// The token itself. Only its hash is stored, so it cannot be retrieved again.
+        pub token: String,
+    }
 }
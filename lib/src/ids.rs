@@ -6,6 +6,19 @@ const NANOID_ALPHABET: [char; 36] = [
     'n', 'o', 'p', 'q', 'r', 's', 't', 'u', 'v', 'w', 'x', 'y', 'z',
 ];
 
+/// Maximum length of an ID. Leaves room for a suffix after a generated ID, e.g. '{id}-thumbnail'.
+pub const MAX_ID_LENGTH: usize = 64;
+
+/// ID of the item containing a user's encrypted master key.
+pub const ID_MASTER_KEY: &str = "master-key";
+/// ID of the item containing a user's library.
+pub const ID_LIBRARY: &str = "library";
+/// Prefix of the IDs of items containing photos that were uploaded, but not yet added to the library.
+/// Each upload batch has its own item, '{prefix}-{id}'. Earlier versions used the prefix itself as ID.
+pub const ID_UPLOAD_JOURNAL: &str = "upload-journal";
+/// IDs of items that every user has one of. All other IDs are unique across users.
+pub const PER_USER_IDS: [&str; 2] = [ID_MASTER_KEY, ID_LIBRARY];
+
 /// Generate a new unique ID
 pub fn id() -> String {
     nanoid!(NANOID_LENGTH, &NANOID_ALPHABET)
@@ -15,6 +28,15 @@ pub fn id_with_length(length: usize) -> String {
     nanoid!(length, &NANOID_ALPHABET)
 }
 
+/// Check whether an ID is valid: at most `MAX_ID_LENGTH` characters from the ID alphabet,
+/// in one or more parts separated by single hyphens.
+pub fn is_valid_id(id: &str) -> bool {
+    id.len() <= MAX_ID_LENGTH
+        && id
+            .split('-')
+            .all(|part| !part.is_empty() && part.chars().all(|ch| NANOID_ALPHABET.contains(&ch)))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -52,4 +74,40 @@ mod tests {
             assert_eq!(id.len(), i);
         }
     }
+
+    #[test]
+    fn is_valid_id_accepts_generated_ids() {
+        for _ in 0..100 {
+            assert!(is_valid_id(&id()));
+            assert!(is_valid_id(&format!("{}-thumbnail", id())));
+        }
+    }
+
+    #[test]
+    fn is_valid_id_accepts_per_user_ids() {
+        for id in PER_USER_IDS {
+            assert!(is_valid_id(id));
+        }
+    }
+
+    #[test]
+    fn is_valid_id_rejects_invalid_characters() {
+        for id in ["../abc", "abc/def", "abc\\def", "ABC", "abc def", "abc.def", "abc_def"] {
+            assert!(!is_valid_id(id), "{id}");
+        }
+    }
+
+    #[test]
+    fn is_valid_id_rejects_invalid_hyphens() {
+        for id in ["-abc", "abc-", "abc--def", "-"] {
+            assert!(!is_valid_id(id), "{id}");
+        }
+    }
+
+    #[test]
+    fn is_valid_id_checks_length() {
+        assert!(!is_valid_id(""));
+        assert!(is_valid_id(&id_with_length(MAX_ID_LENGTH)));
+        assert!(!is_valid_id(&id_with_length(MAX_ID_LENGTH + 1)));
+    }
 }
@@ -0,0 +1,133 @@
+use crate::database;
+use crate::error::ApiError;
+use crate::model::Session;
+use axum::http::{header, HeaderMap, Method};
+use cookie::time::OffsetDateTime;
+use sha2::{Digest, Sha256};
+use upholi_lib::http::TokenScope;
+use upholi_lib::ids::{id_with_length, ID_UPLOAD_JOURNAL};
+
+/// Prefix of all API tokens, which makes them recognizable, e.g. by secret scanners.
+const TOKEN_PREFIX: &str = "uph_";
+const TOKEN_RANDOM_LENGTH: usize = 40;
+
+/// Generate a new API token. Returns the token and its hash.
+pub fn generate_token() -> (String, String) {
+    let token = format!("{TOKEN_PREFIX}{}", id_with_length(TOKEN_RANDOM_LENGTH));
+    let token_hash = hash_token(&token);
+    (token, token_hash)
+}
+
+/// Tokens are long and random, so a fast hash suffices and allows looking them up by hash.
+fn hash_token(token: &str) -> String {
+    Sha256::digest(token.as_bytes())
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// Get the API token from the Authorization header of a request, if any.
+pub fn get_bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+}
+
+/// Get the scope a token needs for a request, or None if the request cannot be made with a token at all,
+/// such as managing shares, sessions or tokens.
+fn required_scope(method: &Method, path: &str) -> Option<TokenScope> {
+    let path = path.strip_prefix("/api").unwrap_or(path);
+
+    if path.starts_with("/admin/") {
+        Some(TokenScope::Admin)
+    } else if method == Method::GET || method == Method::HEAD {
+        Some(TokenScope::Read)
+    } else if is_upload_request(method, path) {
+        Some(TokenScope::Upload)
+    } else {
+        None
+    }
+}
+
+/// Check whether a request is one that uploading photos makes, other than reading: storing items and files,
+/// updating the shares of albums that photos are added to, and deleting the upload journal of a committed batch.
+fn is_upload_request(method: &Method, path: &str) -> bool {
+    if method == Method::POST {
+        path == "/item" || path.starts_with("/item/") || path == "/file" || path == "/share"
+    } else if method == Method::DELETE {
+        path.strip_prefix("/item/")
+            .is_some_and(|id| id == ID_UPLOAD_JOURNAL || id.starts_with(&format!("{ID_UPLOAD_JOURNAL}-")))
+    } else {
+        false
+    }
+}
+
+/// Authenticate a request made with an API token.
+/// Such requests get a session of their own, which is not stored and has no access to shares.
+pub async fn authenticate(token: &str, method: &Method, path: &str) -> Result<Session, ApiError> {
+    let api_token = database::get_api_token_by_hash(&hash_token(token))
+        .await?
+        .ok_or(ApiError::Unauthorized)?;
+
+    let scope = required_scope(method, path).ok_or(ApiError::Forbidden)?;
+    if !api_token.scopes.contains(&scope) {
+        return Err(ApiError::Forbidden);
+    }
+
+    let user = database::get_user_by_id(&api_token.user_id)
+        .await?
+        .ok_or(ApiError::Unauthorized)?;
+    if user.disabled {
+        return Err(ApiError::AccountDisabled);
+    }
+
+    let now = OffsetDateTime::now_utc().unix_timestamp();
+    database::set_api_token_last_used_on(&api_token.id, now).await?;
+
+    Ok(Session {
+        id: api_token.id,
+        user_id: Some(user.id),
+        shares: vec![],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn upload_flow_is_allowed_with_read_and_upload_scopes() {
+        // The requests made by uploading a photo into an album using a batch, and committing the batch
+        let requests = [
+            (Method::GET, "/api/item"),
+            (Method::GET, "/api/item/library"),
+            (Method::POST, "/api/item/upload-journal-abc"),
+            (Method::POST, "/api/file"),
+            (Method::POST, "/api/item/photo"),
+            (Method::POST, "/api/item/library"),
+            (Method::GET, "/api/item/album"),
+            (Method::POST, "/api/item/album"),
+            (Method::POST, "/api/share"),
+            (Method::DELETE, "/api/item/upload-journal-abc"),
+            (Method::DELETE, "/api/item/upload-journal"),
+        ];
+
+        for (method, path) in requests {
+            let scope = required_scope(&method, path);
+            assert!(
+                matches!(scope, Some(TokenScope::Read | TokenScope::Upload)),
+                "{method} {path} requires {scope:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn upload_scope_cannot_delete_other_items() {
+        assert_eq!(required_scope(&Method::DELETE, "/api/item/library"), None);
+        assert_eq!(required_scope(&Method::DELETE, "/api/item"), None);
+        assert_eq!(required_scope(&Method::DELETE, "/api/file/photo-original"), None);
+        assert_eq!(required_scope(&Method::DELETE, "/api/share/share"), None);
+    }
+}
@@ -0,0 +1,129 @@
+use super::*;
+use crate::database::get_all_documents;
+use crate::model::File;
+use crate::storage;
+use anyhow::{anyhow, Result};
+use bson::Document;
+use cookie::time::OffsetDateTime;
+use flate2::{write::GzEncoder, Compression};
+use std::path::Path;
+
+/// Write a snapshot of all users, items, files, shares, invites, account deletions and stored files to a gzipped tar archive.
+///
+/// All data is written as-is, so everything that is encrypted stays encrypted; no user keys are needed.
+/// Database documents are read first, and only the stored files referenced by those documents are included,
+/// so the archive's records and files agree with each other even if the instance is in use.
+pub async fn run(path: &Path) -> Result<()> {
+    // Create the archive first, failing if it already exists, so nothing is read for a backup that cannot be written
+    let archive_file = match std::fs::File::options().write(true).create_new(true).open(path) {
+        Ok(file) => file,
+        Err(error) if error.kind() == std::io::ErrorKind::AlreadyExists => {
+            return Err(anyhow!("File {} already exists", path.display()))
+        }
+        Err(error) => return Err(error.into()),
+    };
+
+    // Do not leave an incomplete archive behind, which would look like a usable backup
+    let result = write_archive(archive_file).await;
+    if result.is_err() {
+        std::fs::remove_file(path)?;
+    }
+    let (files, missing_files) = result?;
+
+    println!(
+        "Backup written to {} ({files} files, {missing_files} missing)",
+        path.display()
+    );
+    Ok(())
+}
+
+/// Write the database documents and stored files to the archive.
+/// Returns the number of stored files written, and the number of files that were missing from storage.
+async fn write_archive(archive_file: std::fs::File) -> Result<(usize, usize)> {
+    let mut collections: Vec<(&str, Vec<Document>)> = vec![];
+    for collection_name in backup_collection_names() {
+        let documents = get_all_documents(collection_name).await?;
+        println!("Read {} documents from '{collection_name}'", documents.len());
+        collections.push((collection_name, documents));
+    }
+
+    let mut archive = tar::Builder::new(GzEncoder::new(archive_file, Compression::default()));
+
+    for (collection_name, documents) in &collections {
+        let mut bytes: Vec<u8> = vec![];
+        for document in documents {
+            document.to_writer(&mut bytes)?;
+        }
+        append_bytes(
+            &mut archive,
+            &format!("{BACKUP_DIR_DATABASE}/{collection_name}.bson"),
+            &bytes,
+        )?;
+    }
+
+    let file_documents = collections
+        .iter()
+        .find(|(collection_name, _)| *collection_name == File::collection_name())
+        .map(|(_, documents)| documents.as_slice())
+        .unwrap_or_default();
+
+    let mut files = 0;
+    let mut missing_files = vec![];
+    for document in file_documents {
+        let file_id = document.get_str("id")?;
+        let user_id = document.get_str("user_id")?;
+
+        match storage::get_file(file_id, user_id).await {
+            Ok(Some(bytes)) => {
+                append_bytes(
+                    &mut archive,
+                    &format!("{BACKUP_DIR_STORAGE}/{user_id}/{file_id}"),
+                    &bytes,
+                )?;
+                files += 1;
+            }
+            Ok(None) => {
+                println!("Stored file not found for file '{file_id}' of user '{user_id}'");
+                missing_files.push(file_id.to_string());
+            }
+            Err(error) => {
+                return Err(anyhow!(
+                    "Failed to read stored file '{file_id}' of user '{user_id}': {error}"
+                ))
+            }
+        }
+    }
+
+    let manifest = BackupManifest {
+        format_version: BACKUP_FORMAT_VERSION,
+        created_on: OffsetDateTime::now_utc().unix_timestamp(),
+        collections: collections
+            .iter()
+            .map(|(collection_name, documents)| BackupManifestCollection {
+                name: collection_name.to_string(),
+                documents: documents.len(),
+            })
+            .collect(),
+        files,
+        missing_files,
+    };
+    append_bytes(
+        &mut archive,
+        BACKUP_PATH_MANIFEST,
+        &serde_json::to_vec_pretty(&manifest)?,
+    )?;
+
+    archive.into_inner()?.finish()?;
+
+    Ok((files, manifest.missing_files.len()))
+}
+
+fn append_bytes<W: std::io::Write>(archive: &mut tar::Builder<W>, path: &str, bytes: &[u8]) -> Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(bytes.len() as u64);
+    header.set_mode(0o600);
+    header.set_mtime(OffsetDateTime::now_utc().unix_timestamp() as u64);
+    header.set_cksum();
+    archive.append_data(&mut header, path, bytes)?;
+    Ok(())
+}
@@ -0,0 +1,36 @@
+use crate::maintenance::fsck;
+use anyhow::Result;
+
+/// Cross-check file records against storage contents and print any inconsistencies found.
+///
+/// * `repair` - Delete orphaned stored files and dangling file records, and correct users' storage usage.
+pub async fn run(repair: bool) -> Result<()> {
+    let report = fsck(repair).await?;
+
+    for file in &report.orphaned_files {
+        let owner = file.owner_user_id.as_deref().unwrap_or("unknown");
+        println!("Orphaned file '{}' (owner: {owner})", file.file_id);
+    }
+    for record in &report.dangling_records {
+        println!(
+            "Dangling file record '{}' (user: {}); stored file is missing",
+            record.file_id, record.user_id
+        );
+    }
+
+    for usage in &report.usage_mismatches {
+        println!(
+            "Usage of user '{}' recorded as {} bytes, actual usage is {} bytes",
+            usage.user_id, usage.recorded_bytes, usage.actual_bytes
+        );
+    }
+
+    println!("{report}");
+    let any_issues =
+        !report.orphaned_files.is_empty() || !report.dangling_records.is_empty() || !report.usage_mismatches.is_empty();
+    if !repair && any_issues {
+        println!("Run with --repair to fix them");
+    }
+
+    Ok(())
+}
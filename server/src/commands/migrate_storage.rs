@@ -0,0 +1,8 @@
+use crate::maintenance::migrate_legacy_files;
+use anyhow::Result;
+
+/// Move legacy files of the disk storage provider into per-user directories.
+/// The server also does this when it starts, so this is only needed to migrate without starting it.
+pub async fn run() -> Result<()> {
+    migrate_legacy_files().await
+}
@@ -0,0 +1,56 @@
+use crate::database::{
+    COLLECTION_NAME_API_TOKENS, COLLECTION_NAME_INVITES, COLLECTION_NAME_PURGES, COLLECTION_NAME_SHARES,
+    COLLECTION_NAME_TRASH, COLLECTION_NAME_USERS,
+};
+use crate::model::{DbItem, EncryptedData, File};
+use serde::{Deserialize, Serialize};
+
+pub mod backup;
+pub mod fsck;
+pub mod migrate_storage;
+pub mod restore;
+pub mod set_admin;
+
+/// Version of the backup archive layout. Increment when the layout changes in a breaking way.
+const BACKUP_FORMAT_VERSION: u32 = 1;
+/// Path within the archive of the manifest describing its contents.
+const BACKUP_PATH_MANIFEST: &str = "manifest.json";
+/// Directory within the archive containing one file of concatenated BSON documents per collection.
+const BACKUP_DIR_DATABASE: &str = "database";
+/// Directory within the archive containing stored files, as 'storage/{user_id}/{file_id}'.
+const BACKUP_DIR_STORAGE: &str = "storage";
+
+/// Database collections included in a backup, in the order they are restored.
+/// Sessions are deliberately left out; they are short-lived and tied to a running instance.
+/// Unfinished account deletions are included, so they are resumed after a restore.
+fn backup_collection_names() -> [&'static str; 8] {
+    [
+        COLLECTION_NAME_USERS,
+        EncryptedData::collection_name(),
+        File::collection_name(),
+        COLLECTION_NAME_SHARES,
+        COLLECTION_NAME_INVITES,
+        COLLECTION_NAME_API_TOKENS,
+        COLLECTION_NAME_TRASH,
+        COLLECTION_NAME_PURGES,
+    ]
+}
+
+/// Describes the contents of a backup archive.
+#[derive(Serialize, Deserialize)]
+struct BackupManifest {
+    format_version: u32,
+    /// Unix timestamp of when the backup was created.
+    created_on: i64,
+    collections: Vec<BackupManifestCollection>,
+    /// Number of stored files included in the archive.
+    files: usize,
+    /// IDs of file records for which no stored file could be found when creating the backup.
+    missing_files: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BackupManifestCollection {
+    name: String,
+    documents: usize,
+}
@@ -0,0 +1,139 @@
+use super::*;
+use crate::database::{get_document_ids, upsert_documents};
+use crate::model::User;
+use crate::storage::{self, init_storage_for_user};
+use anyhow::{anyhow, Result};
+use bson::Document;
+use flate2::read::GzDecoder;
+use std::collections::{HashMap, HashSet};
+use std::io::{Cursor, Read};
+use std::path::Path;
+
+/// Restore a backup archive created by the 'backup' command.
+///
+/// Only restores into an instance whose users, items, files, shares and invites all come from the same backup,
+/// to avoid mixing the backup with existing data. Documents and files are written over existing ones,
+/// so a restore that failed partway can be run again.
+pub async fn run(path: &Path) -> Result<()> {
+    let manifest = read_manifest(path)?;
+    if manifest.format_version != BACKUP_FORMAT_VERSION {
+        return Err(anyhow!(
+            "Unsupported backup format version {}, expected {BACKUP_FORMAT_VERSION}",
+            manifest.format_version
+        ));
+    }
+
+    let collections = read_collections(path)?;
+    for collection_name in backup_collection_names() {
+        let backup_ids: HashSet<String> = collections
+            .get(collection_name)
+            .into_iter()
+            .flatten()
+            .filter_map(|document| document.get("_id").map(|id| id.to_string()))
+            .collect();
+        let any_other_documents = get_document_ids(collection_name)
+            .await?
+            .iter()
+            .any(|id| !backup_ids.contains(&id.to_string()));
+        if any_other_documents {
+            return Err(anyhow!(
+                "Collection '{collection_name}' contains documents that are not in the backup; restoring is only possible into an empty instance"
+            ));
+        }
+    }
+
+    for collection_name in backup_collection_names() {
+        let documents = collections.get(collection_name).map(Vec::as_slice).unwrap_or_default();
+        upsert_documents(collection_name, documents).await?;
+        println!("Restored {} documents to '{collection_name}'", documents.len());
+
+        if collection_name == crate::database::COLLECTION_NAME_USERS {
+            for document in documents {
+                let user: User = bson::from_document(document.clone())?;
+                init_storage_for_user(&user).await?;
+            }
+        }
+    }
+
+    let mut archive = open_archive(path)?;
+    let mut files = 0;
+
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let entry_path = entry.path()?.to_string_lossy().to_string();
+
+        let path_parts: Vec<&str> = entry_path.split('/').collect();
+        if let [BACKUP_DIR_STORAGE, user_id, file_id] = path_parts.as_slice() {
+            let mut bytes: Vec<u8> = vec![];
+            entry.read_to_end(&mut bytes)?;
+            storage::import_file(file_id, user_id, &bytes).await?;
+            files += 1;
+        }
+    }
+
+    if files != manifest.files {
+        return Err(anyhow!(
+            "Backup manifest lists {} files, but {files} were restored",
+            manifest.files
+        ));
+    }
+
+    println!("Backup {} restored ({files} files)", path.display());
+    Ok(())
+}
+
+fn open_archive(path: &Path) -> Result<tar::Archive<GzDecoder<std::fs::File>>> {
+    let file = std::fs::File::open(path)?;
+    Ok(tar::Archive::new(GzDecoder::new(file)))
+}
+
+fn read_manifest(path: &Path) -> Result<BackupManifest> {
+    let mut archive = open_archive(path)?;
+    for entry in archive.entries()? {
+        let entry = entry?;
+        if entry.path()?.to_string_lossy() == BACKUP_PATH_MANIFEST {
+            return Ok(serde_json::from_reader(entry)?);
+        }
+    }
+
+    Err(anyhow!("Backup {} does not contain a manifest", path.display()))
+}
+
+/// Read the documents of each collection in the archive, and check that the archive contains nothing unexpected.
+fn read_collections(path: &Path) -> Result<HashMap<&'static str, Vec<Document>>> {
+    let mut archive = open_archive(path)?;
+    let mut collections = HashMap::new();
+
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let entry_path = entry.path()?.to_string_lossy().to_string();
+
+        let path_parts: Vec<&str> = entry_path.split('/').collect();
+        match path_parts.as_slice() {
+            [BACKUP_DIR_DATABASE, file_name] => {
+                let collection_name = file_name
+                    .strip_suffix(".bson")
+                    .and_then(|name| backup_collection_names().into_iter().find(|c| *c == name))
+                    .ok_or_else(|| anyhow!("Unexpected file in backup: {entry_path}"))?;
+                let mut bytes: Vec<u8> = vec![];
+                entry.read_to_end(&mut bytes)?;
+                collections.insert(collection_name, parse_documents(&bytes)?);
+            }
+            [BACKUP_DIR_STORAGE, _, _] | [BACKUP_PATH_MANIFEST] => {}
+            _ => return Err(anyhow!("Unexpected file in backup: {entry_path}")),
+        }
+    }
+
+    Ok(collections)
+}
+
+/// Parse a buffer of concatenated BSON documents.
+fn parse_documents(bytes: &[u8]) -> Result<Vec<Document>> {
+    let mut cursor = Cursor::new(bytes);
+    let mut documents = vec![];
+    while (cursor.position() as usize) < bytes.len() {
+        documents.push(Document::from_reader(&mut cursor)?);
+    }
+
+    Ok(documents)
+}
@@ -0,0 +1,18 @@
+use crate::database::{get_user_by_username, set_user_is_admin};
+use anyhow::{anyhow, Result};
+
+/// Grant or revoke admin rights of a user.
+pub async fn run(username: &str, is_admin: bool) -> Result<()> {
+    let user = get_user_by_username(username)
+        .await?
+        .ok_or_else(|| anyhow!("User '{username}' not found"))?;
+
+    set_user_is_admin(&user.id, is_admin).await?;
+
+    if is_admin {
+        println!("User '{username}' is now an admin");
+    } else {
+        println!("User '{username}' is no longer an admin");
+    }
+    Ok(())
+}
@@ -1,17 +1,23 @@
-use crate::model::{DbItem, EncryptedData, File, Session, Share, User};
-use anyhow::Result;
+use crate::model::{ApiToken, DbItem, EncryptedData, File, Invite, Purge, Session, Share, TrashEntry, User};
+use anyhow::{anyhow, Result};
 use async_once::AsyncOnce;
 use bson::{doc, Document};
+use futures::TryStreamExt;
 use lazy_static::lazy_static;
 use mongodb::{
-    options::{ClientOptions, ReplaceOptions},
+    options::{ClientOptions, FindOptions, ReplaceOptions},
     Client,
 };
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::sync::Arc;
 
-const COLLECTION_NAME_USERS: &str = "users";
-const COLLECTION_NAME_SESSIONS: &str = "sessions";
-const COLLECTION_NAME_SHARES: &str = "shares";
+pub const COLLECTION_NAME_USERS: &str = "users";
+pub const COLLECTION_NAME_SESSIONS: &str = "sessions";
+pub const COLLECTION_NAME_SHARES: &str = "shares";
+pub const COLLECTION_NAME_INVITES: &str = "invites";
+pub const COLLECTION_NAME_PURGES: &str = "purges";
+pub const COLLECTION_NAME_API_TOKENS: &str = "api_tokens";
+pub const COLLECTION_NAME_TRASH: &str = "trash";
 
 #[derive(Serialize, Deserialize)]
 struct ItemContainer<TData> {
@@ -26,9 +32,10 @@ lazy_static! {
     /// A reference to the database that can be used to execute queries etc
     static ref DB: AsyncOnce<mongodb::Database> = AsyncOnce::new(async {
         let connection_string = &crate::SETTINGS.database.connection_string;
-        let client_options = ClientOptions::parse(connection_string)
+        let mut client_options = ClientOptions::parse(connection_string)
             .await
             .expect("Failed to parse database connection string");
+        client_options.command_event_handler = Some(Arc::new(crate::metrics::DatabaseMetrics));
 
         let client =
             Client::with_options(client_options).expect("Failed to initialize database client");
@@ -39,6 +46,23 @@ lazy_static! {
     });
 }
 
+/// Connect to the database, failing instead of panicking if the connection string is not valid.
+pub async fn init() -> Result<()> {
+    let client_options = ClientOptions::parse(&crate::SETTINGS.database.connection_string).await?;
+    if client_options.default_database.is_none() {
+        return Err(anyhow!("No default database found in connection string"));
+    }
+
+    DB.get().await;
+    Ok(())
+}
+
+/// Check that the database can be reached.
+pub async fn ping() -> Result<()> {
+    DB.get().await.run_command(doc! {"ping": 1}, None).await?;
+    Ok(())
+}
+
 pub async fn insert_user(user: &User) -> Result<()> {
     insert(COLLECTION_NAME_USERS, user).await
 }
@@ -47,6 +71,196 @@ pub async fn get_user_by_username(username: &str) -> Result<Option<User>> {
     get(COLLECTION_NAME_USERS, "username", username).await
 }
 
+pub async fn get_user_by_id(id: &str) -> Result<Option<User>> {
+    get(COLLECTION_NAME_USERS, "id", id).await
+}
+
+/// Get all users, ordered by username.
+pub async fn get_users() -> Result<Vec<User>> {
+    let collection = DB.get().await.collection::<User>(COLLECTION_NAME_USERS);
+    let cursor = collection
+        .find(None, FindOptions::builder().sort(doc! { "username": 1 }).build())
+        .await?;
+    let users = cursor.try_collect().await?;
+
+    Ok(users)
+}
+
+pub async fn set_user_disabled(user_id: &str, disabled: bool) -> Result<()> {
+    set_user_fields(user_id, doc! { "disabled": disabled }).await
+}
+
+pub async fn set_user_is_admin(user_id: &str, is_admin: bool) -> Result<()> {
+    set_user_fields(user_id, doc! { "is_admin": is_admin }).await
+}
+
+/// Set the storage quota of a user, or None to apply the default quota.
+pub async fn set_user_quota_bytes(user_id: &str, quota_bytes: Option<u64>) -> Result<()> {
+    set_user_fields(user_id, doc! { "quota_bytes": bson::to_bson(&quota_bytes)? }).await
+}
+
+pub async fn set_user_password(user_id: &str, password_phc: &str, password_reset: bool) -> Result<()> {
+    set_user_fields(
+        user_id,
+        doc! {
+            "password_phc": password_phc,
+            "password_reset": password_reset,
+        },
+    )
+    .await
+}
+
+pub async fn clear_user_password_reset(user_id: &str) -> Result<()> {
+    set_user_fields(user_id, doc! { "password_reset": false }).await
+}
+
+/// Delete a user and all of their items, file records, shares, sessions and API tokens.
+/// Stored files are not deleted; see `purge::start_user_purge` to delete a user completely.
+pub async fn delete_user(user_id: &str) -> Result<()> {
+    let db = DB.get().await;
+    let collection_names = [
+        EncryptedData::collection_name(),
+        File::collection_name(),
+        COLLECTION_NAME_SHARES,
+        COLLECTION_NAME_SESSIONS,
+        COLLECTION_NAME_API_TOKENS,
+        COLLECTION_NAME_TRASH,
+        COLLECTION_NAME_USERS,
+    ];
+    for collection_name in collection_names {
+        let filter = match collection_name {
+            COLLECTION_NAME_USERS => doc! { "id": user_id },
+            _ => doc! { "user_id": user_id },
+        };
+        db.collection::<Document>(collection_name)
+            .delete_many(filter, None)
+            .await?;
+    }
+
+    Ok(())
+}
+
+pub async fn insert_purge(purge: &Purge) -> Result<()> {
+    insert(COLLECTION_NAME_PURGES, purge).await
+}
+
+pub async fn get_purge(user_id: &str) -> Result<Option<Purge>> {
+    get(COLLECTION_NAME_PURGES, "user_id", user_id).await
+}
+
+pub async fn get_purges() -> Result<Vec<Purge>> {
+    let collection = DB.get().await.collection::<Purge>(COLLECTION_NAME_PURGES);
+    let cursor = collection.find(None, None).await?;
+    let purges = cursor.try_collect().await?;
+
+    Ok(purges)
+}
+
+pub async fn set_purge_files_deleted(user_id: &str, files_deleted: u64) -> Result<()> {
+    let collection = DB.get().await.collection::<Purge>(COLLECTION_NAME_PURGES);
+    collection
+        .update_one(
+            doc! {
+                "user_id": user_id,
+            },
+            doc! {
+                "$set": {
+                    "files_deleted": files_deleted as i64
+                }
+            },
+            None,
+        )
+        .await?;
+
+    Ok(())
+}
+
+pub async fn delete_purge(user_id: &str) -> Result<()> {
+    let collection = DB.get().await.collection::<Purge>(COLLECTION_NAME_PURGES);
+    collection.delete_one(doc! { "user_id": user_id }, None).await?;
+
+    Ok(())
+}
+
+/// Add given number of bytes, which may be negative, to the total size of files stored by a user.
+pub async fn add_user_bytes_stored(user_id: &str, bytes: i64) -> Result<()> {
+    let collection = DB.get().await.collection::<User>(COLLECTION_NAME_USERS);
+    collection
+        .update_one(
+            doc! {
+                "id": user_id,
+            },
+            doc! {
+                "$inc": {
+                    "bytes_stored": bytes
+                }
+            },
+            None,
+        )
+        .await?;
+
+    Ok(())
+}
+
+/// Add given number of bytes to the total size of files stored by a user, unless that would take it over `limit_bytes`.
+/// The check and the update are a single operation, so concurrent uploads cannot exceed the limit together.
+/// Returns whether the bytes were added.
+pub async fn try_add_user_bytes_stored(user_id: &str, bytes: i64, limit_bytes: u64) -> Result<bool> {
+    let collection = DB.get().await.collection::<User>(COLLECTION_NAME_USERS);
+    let result = collection
+        .update_one(
+            doc! {
+                "id": user_id,
+                "bytes_stored": { "$lte": limit_bytes as i64 - bytes },
+            },
+            doc! {
+                "$inc": {
+                    "bytes_stored": bytes
+                }
+            },
+            None,
+        )
+        .await?;
+
+    Ok(result.modified_count == 1)
+}
+
+/// Get the IDs of users for whom the total size of stored files has never been recorded,
+/// because they were created before it was.
+pub async fn get_user_ids_without_bytes_stored() -> Result<Vec<String>> {
+    let collection = DB.get().await.collection::<User>(COLLECTION_NAME_USERS);
+    let users: Vec<User> = collection
+        .find(doc! { "bytes_stored": { "$exists": false } }, None)
+        .await?
+        .try_collect()
+        .await?;
+
+    Ok(users.into_iter().map(|user| user.id).collect())
+}
+
+/// Set the total size of files stored by a user.
+pub async fn set_user_bytes_stored(user_id: &str, bytes: i64) -> Result<()> {
+    set_user_fields(user_id, doc! { "bytes_stored": bytes }).await
+}
+
+/// Set given fields of a user, leaving other fields as they are.
+async fn set_user_fields(user_id: &str, fields: Document) -> Result<()> {
+    let collection = DB.get().await.collection::<User>(COLLECTION_NAME_USERS);
+    collection
+        .update_one(
+            doc! {
+                "id": user_id,
+            },
+            doc! {
+                "$set": fields
+            },
+            None,
+        )
+        .await?;
+
+    Ok(())
+}
+
 pub async fn get_session(id: &str) -> Result<Option<Session>> {
     get(COLLECTION_NAME_SESSIONS, "id", id).await
 }
@@ -66,6 +280,165 @@ pub async fn upsert_session(session: &Session) -> Result<()> {
     Ok(())
 }
 
+/// Count sessions a user is logged in to.
+pub async fn count_authenticated_sessions() -> Result<u64> {
+    let collection = DB.get().await.collection::<Session>(COLLECTION_NAME_SESSIONS);
+    let count = collection
+        .count_documents(doc! {"user_id": {"$ne": null}}, None)
+        .await?;
+
+    Ok(count)
+}
+
+/// Log given user out of all sessions.
+pub async fn remove_user_from_sessions(user_id: &str) -> Result<()> {
+    DB.get()
+        .await
+        .collection::<Session>(COLLECTION_NAME_SESSIONS)
+        .update_many(
+            doc! {"user_id": user_id},
+            doc! {
+                "$set": {
+                    "user_id": null
+                }
+            },
+            None,
+        )
+        .await?;
+
+    Ok(())
+}
+
+pub async fn insert_api_token(api_token: &ApiToken) -> Result<()> {
+    insert(COLLECTION_NAME_API_TOKENS, api_token).await
+}
+
+pub async fn get_api_token_by_hash(token_hash: &str) -> Result<Option<ApiToken>> {
+    get(COLLECTION_NAME_API_TOKENS, "token_hash", token_hash).await
+}
+
+/// Get all API tokens of a user, oldest first.
+pub async fn get_api_tokens(user_id: &str) -> Result<Vec<ApiToken>> {
+    let collection = DB.get().await.collection::<ApiToken>(COLLECTION_NAME_API_TOKENS);
+    let options = FindOptions::builder().sort(doc! { "created_on": 1 }).build();
+    let cursor = collection.find(doc! { "user_id": user_id }, options).await?;
+    let api_tokens = cursor.try_collect().await?;
+
+    Ok(api_tokens)
+}
+
+pub async fn set_api_token_last_used_on(id: &str, last_used_on: i64) -> Result<()> {
+    let collection = DB.get().await.collection::<ApiToken>(COLLECTION_NAME_API_TOKENS);
+    collection
+        .update_one(
+            doc! { "id": id },
+            doc! { "$set": { "last_used_on": last_used_on } },
+            None,
+        )
+        .await?;
+
+    Ok(())
+}
+
+/// Delete an API token of given user. Returns whether it existed.
+pub async fn delete_api_token(user_id: &str, id: &str) -> Result<bool> {
+    let collection = DB.get().await.collection::<ApiToken>(COLLECTION_NAME_API_TOKENS);
+    let result = collection
+        .delete_one(doc! { "id": id, "user_id": user_id }, None)
+        .await?;
+
+    Ok(result.deleted_count > 0)
+}
+
+/// Revoke all API tokens of given user.
+pub async fn delete_api_tokens_for_user(user_id: &str) -> Result<()> {
+    let collection = DB.get().await.collection::<ApiToken>(COLLECTION_NAME_API_TOKENS);
+    collection.delete_many(doc! { "user_id": user_id }, None).await?;
+
+    Ok(())
+}
+
+/// Add entries to the trash, replacing entries of the user with the same ID.
+pub async fn upsert_trash_entries(entries: &[TrashEntry]) -> Result<()> {
+    let collection = DB.get().await.collection::<TrashEntry>(COLLECTION_NAME_TRASH);
+    for entry in entries {
+        collection
+            .replace_one(
+                doc! { "id": &entry.id, "user_id": &entry.user_id },
+                entry,
+                ReplaceOptions::builder().upsert(true).build(),
+            )
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Get the trash entries of a user, oldest first.
+pub async fn get_trash_entries(user_id: &str) -> Result<Vec<TrashEntry>> {
+    let collection = DB.get().await.collection::<TrashEntry>(COLLECTION_NAME_TRASH);
+    let options = FindOptions::builder().sort(doc! { "trashed_on": 1 }).build();
+    let cursor = collection.find(doc! { "user_id": user_id }, options).await?;
+    let entries = cursor.try_collect().await?;
+
+    Ok(entries)
+}
+
+/// Get the trash entries of a user with given IDs.
+pub async fn get_trash_entries_by_id(user_id: &str, ids: &[String]) -> Result<Vec<TrashEntry>> {
+    let collection = DB.get().await.collection::<TrashEntry>(COLLECTION_NAME_TRASH);
+    let cursor = collection
+        .find(doc! { "id": { "$in": ids }, "user_id": user_id }, None)
+        .await?;
+    let entries = cursor.try_collect().await?;
+
+    Ok(entries)
+}
+
+/// Get the trash entries of all users that were moved to the trash before given Unix timestamp.
+pub async fn get_trash_entries_before(trashed_before: i64) -> Result<Vec<TrashEntry>> {
+    let collection = DB.get().await.collection::<TrashEntry>(COLLECTION_NAME_TRASH);
+    let cursor = collection
+        .find(doc! { "trashed_on": { "$lt": trashed_before } }, None)
+        .await?;
+    let entries = cursor.try_collect().await?;
+
+    Ok(entries)
+}
+
+pub async fn delete_trash_entries(user_id: &str, ids: &[String]) -> Result<()> {
+    let collection = DB.get().await.collection::<TrashEntry>(COLLECTION_NAME_TRASH);
+    collection
+        .delete_many(doc! { "id": { "$in": ids }, "user_id": user_id }, None)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn insert_invite(invite: &Invite) -> Result<()> {
+    insert(COLLECTION_NAME_INVITES, invite).await
+}
+
+pub async fn get_invites() -> Result<Vec<Invite>> {
+    let collection = DB.get().await.collection::<Invite>(COLLECTION_NAME_INVITES);
+    let cursor = collection.find(None, None).await?;
+    let invites = cursor.try_collect().await?;
+
+    Ok(invites)
+}
+
+pub async fn get_invite(code: &str) -> Result<Option<Invite>> {
+    get(COLLECTION_NAME_INVITES, "code", code).await
+}
+
+/// Delete an invite. Returns false if no invite with given code exists.
+pub async fn delete_invite(code: &str) -> Result<bool> {
+    let collection = DB.get().await.collection::<Invite>(COLLECTION_NAME_INVITES);
+    let result = collection.delete_one(doc! { "code": code }, None).await?;
+
+    Ok(result.deleted_count > 0)
+}
+
 pub async fn get_share(id: &str) -> Result<Option<Share>> {
     get(COLLECTION_NAME_SHARES, "id", id).await
 }
@@ -76,6 +449,7 @@ pub async fn upsert_share(share: &Share) -> Result<()> {
         .replace_one(
             doc! {
                 "id": &share.id,
+                "user_id": &share.user_id,
             },
             share,
             ReplaceOptions::builder().upsert(true).build(),
@@ -100,7 +474,8 @@ pub async fn delete_share(user_id: &str, id: &str) -> Result<()> {
     Ok(())
 }
 
-pub async fn set_items_for_share(share_id: &str, item_ids: &[String]) -> Result<()> {
+/// Associate items and files of given user to a share, replacing any items associated to it before.
+pub async fn set_items_for_share(share_id: &str, item_ids: &[String], user_id: &str) -> Result<()> {
     remove_items_from_share(share_id).await?;
 
     let collection_names = vec![EncryptedData::collection_name(), File::collection_name()];
@@ -113,6 +488,7 @@ pub async fn set_items_for_share(share_id: &str, item_ids: &[String]) -> Result<
                     "id": {
                         "$in": item_ids
                     },
+                    "user_id": user_id,
                 },
                 doc! {
                     "$addToSet": {
@@ -266,6 +642,64 @@ pub async fn delete_items<T: DbItem>(ids: &[String], user_id: &str) -> Result<()
     Ok(())
 }
 
+/// Check whether a document with given ID exists in a collection, belonging to another user than given one.
+pub async fn is_id_used_by_other_user(collection_name: &str, id: &str, user_id: &str) -> Result<bool> {
+    let collection = DB.get().await.collection::<Document>(collection_name);
+    let document = collection
+        .find_one(
+            doc! {
+                "id": id,
+                "user_id": { "$ne": user_id },
+            },
+            None,
+        )
+        .await?;
+
+    Ok(document.is_some())
+}
+
+/// Get all documents in a collection, as they are stored.
+pub async fn get_all_documents(collection_name: &str) -> Result<Vec<Document>> {
+    let collection = DB.get().await.collection::<Document>(collection_name);
+    let cursor = collection.find(None, None).await?;
+    let documents = cursor.try_collect().await?;
+
+    Ok(documents)
+}
+
+/// Insert documents into a collection, or replace them if a document with the same `_id` exists.
+pub async fn upsert_documents(collection_name: &str, documents: &[Document]) -> Result<()> {
+    let collection = DB.get().await.collection::<Document>(collection_name);
+    for document in documents {
+        let id = document.get("_id").ok_or_else(|| anyhow!("Document without _id"))?;
+        collection
+            .replace_one(
+                doc! { "_id": id },
+                document,
+                ReplaceOptions::builder().upsert(true).build(),
+            )
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Get the `_id` of every document in a collection.
+pub async fn get_document_ids(collection_name: &str) -> Result<Vec<bson::Bson>> {
+    let collection = DB.get().await.collection::<Document>(collection_name);
+    let ids = collection.distinct("_id", None, None).await?;
+
+    Ok(ids)
+}
+
+/// Count the number of documents in a collection.
+pub async fn count_documents(collection_name: &str) -> Result<u64> {
+    let collection = DB.get().await.collection::<Document>(collection_name);
+    let count = collection.count_documents(None, None).await?;
+
+    Ok(count)
+}
+
 async fn get<T: DeserializeOwned + Unpin + Send + Sync>(
     collection_name: &str,
     id_name: &str,
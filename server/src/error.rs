@@ -0,0 +1,121 @@
+use crate::storage::QuotaExceededError;
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::fmt;
+use upholi_lib::http::response::{ErrorCode, ErrorResponse};
+
+/// Error returned by API handlers and extractors.
+/// Responds with a matching status code and an `ErrorResponse` body.
+#[derive(Debug)]
+pub enum ApiError {
+    /// The request is invalid, for the given reason.
+    BadRequest(String),
+    /// An ID chosen by the client is not valid according to `upholi_lib::ids`.
+    InvalidId(String),
+    /// Not logged in, or an incorrect password was provided.
+    Unauthorized,
+    /// Logged in, but not allowed to do this.
+    Forbidden,
+    AccountDisabled,
+    /// Open registration is disabled, and no valid invite code was provided.
+    InviteRequired,
+    NotFound,
+    /// The request conflicts with the current state, for the given reason.
+    Conflict(String),
+    UsernameTaken,
+    /// An ID chosen by the client is already used by another user.
+    IdInUse(String),
+    QuotaExceeded {
+        quota_bytes: u64,
+    },
+    /// A request that changes state did not include the session's CSRF token.
+    InvalidCsrfToken,
+    /// Anything the client cannot do anything about. Details are logged, but not returned.
+    Internal(anyhow::Error),
+}
+
+impl ApiError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ApiError::BadRequest(_) | ApiError::InvalidId(_) => StatusCode::BAD_REQUEST,
+            ApiError::Unauthorized => StatusCode::UNAUTHORIZED,
+            ApiError::Forbidden | ApiError::AccountDisabled | ApiError::InviteRequired | ApiError::InvalidCsrfToken => {
+                StatusCode::FORBIDDEN
+            }
+            ApiError::NotFound => StatusCode::NOT_FOUND,
+            ApiError::Conflict(_) | ApiError::UsernameTaken | ApiError::IdInUse(_) => StatusCode::CONFLICT,
+            ApiError::QuotaExceeded { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+            ApiError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn code(&self) -> ErrorCode {
+        match self {
+            ApiError::BadRequest(_) => ErrorCode::BadRequest,
+            ApiError::InvalidId(_) => ErrorCode::InvalidId,
+            ApiError::Unauthorized => ErrorCode::Unauthorized,
+            ApiError::Forbidden => ErrorCode::Forbidden,
+            ApiError::AccountDisabled => ErrorCode::AccountDisabled,
+            ApiError::InviteRequired => ErrorCode::InviteRequired,
+            ApiError::NotFound => ErrorCode::NotFound,
+            ApiError::Conflict(_) => ErrorCode::Conflict,
+            ApiError::UsernameTaken => ErrorCode::UsernameTaken,
+            ApiError::IdInUse(_) => ErrorCode::IdInUse,
+            ApiError::QuotaExceeded { .. } => ErrorCode::QuotaExceeded,
+            ApiError::InvalidCsrfToken => ErrorCode::InvalidCsrfToken,
+            ApiError::Internal(_) => ErrorCode::Internal,
+        }
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ApiError::BadRequest(reason) | ApiError::Conflict(reason) => write!(f, "{reason}"),
+            ApiError::InvalidId(id) => write!(f, "Invalid ID '{id}'"),
+            ApiError::Unauthorized => write!(f, "Not authorized"),
+            ApiError::Forbidden => write!(f, "Not allowed"),
+            ApiError::AccountDisabled => write!(f, "This account is disabled"),
+            ApiError::InviteRequired => write!(f, "A valid invite code is required to register"),
+            ApiError::NotFound => write!(f, "Not found"),
+            ApiError::UsernameTaken => write!(f, "A user with this username already exists"),
+            ApiError::IdInUse(id) => write!(f, "ID '{id}' is already in use"),
+            ApiError::QuotaExceeded { quota_bytes } => write!(f, "Storage quota of {quota_bytes} bytes exceeded"),
+            ApiError::InvalidCsrfToken => write!(f, "Missing or invalid CSRF token, reload the page and try again"),
+            ApiError::Internal(_) => write!(f, "Internal server error"),
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        if let ApiError::Internal(error) = &self {
+            tracing::error!("Internal server error: {error:?}");
+        }
+
+        let body = ErrorResponse {
+            code: self.code(),
+            message: self.to_string(),
+        };
+        (self.status_code(), Json(body)).into_response()
+    }
+}
+
+/// Any other error is an internal error, except for a few that can be told apart.
+impl<E> From<E> for ApiError
+where
+    E: Into<anyhow::Error>,
+{
+    fn from(error: E) -> Self {
+        let error: anyhow::Error = error.into();
+        match error.downcast_ref::<QuotaExceededError>() {
+            Some(quota_exceeded) => ApiError::QuotaExceeded {
+                quota_bytes: quota_exceeded.quota_bytes,
+            },
+            None => ApiError::Internal(error),
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use crate::error::ApiError;
+use axum::{
+    async_trait,
+    extract::{
+        multipart::MultipartRejection,
+        rejection::{JsonRejection, PathRejection},
+        FromRequest, FromRequestParts,
+    },
+    http::{request::Parts, Request},
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+
+/// Like `axum::Json`, but an invalid request body is rejected with an `ApiError`,
+/// so the client gets an `ErrorResponse` like for any other error.
+pub struct Json<T>(pub T);
+
+#[async_trait]
+impl<T, S, B> FromRequest<S, B> for Json<T>
+where
+    axum::Json<T>: FromRequest<S, B, Rejection = JsonRejection>,
+    S: Send + Sync,
+    B: Send + 'static,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(req: Request<B>, state: &S) -> Result<Self, Self::Rejection> {
+        match axum::Json::<T>::from_request(req, state).await {
+            Ok(axum::Json(value)) => Ok(Json(value)),
+            Err(rejection) => Err(ApiError::BadRequest(rejection.body_text())),
+        }
+    }
+}
+
+impl<T: Serialize> IntoResponse for Json<T> {
+    fn into_response(self) -> Response {
+        axum::Json(self.0).into_response()
+    }
+}
+
+/// Like `axum::extract::Path`, but an invalid path parameter is rejected with an `ApiError`.
+pub struct Path<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for Path<T>
+where
+    axum::extract::Path<T>: FromRequestParts<S, Rejection = PathRejection>,
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        match axum::extract::Path::<T>::from_request_parts(parts, state).await {
+            Ok(axum::extract::Path(value)) => Ok(Path(value)),
+            Err(rejection) => Err(ApiError::BadRequest(rejection.body_text())),
+        }
+    }
+}
+
+/// Like `axum::extract::Multipart`, but a request that is not multipart is rejected with an `ApiError`.
+pub struct Multipart(pub axum::extract::Multipart);
+
+#[async_trait]
+impl<S, B> FromRequest<S, B> for Multipart
+where
+    axum::extract::Multipart: FromRequest<S, B, Rejection = MultipartRejection>,
+    S: Send + Sync,
+    B: Send + 'static,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(req: Request<B>, state: &S) -> Result<Self, Self::Rejection> {
+        match axum::extract::Multipart::from_request(req, state).await {
+            Ok(multipart) => Ok(Multipart(multipart)),
+            Err(rejection) => Err(ApiError::BadRequest(rejection.body_text())),
+        }
+    }
+}
@@ -0,0 +1,146 @@
+use super::user::{usage_result, user_deletion_result};
+use crate::database::*;
+use crate::error::ApiError;
+use crate::extract::{Json, Path};
+use crate::model::Invite;
+use crate::purge::start_user_purge;
+use crate::AdminUserId;
+use anyhow::Result;
+use axum::{http::StatusCode, response::IntoResponse};
+use cookie::time::OffsetDateTime;
+use upholi_lib::http::{request::*, response::*};
+use upholi_lib::ids::id;
+use upholi_lib::passwords::hash_password;
+
+/// Get all users, including their storage usage.
+pub async fn get_users(AdminUserId(_): AdminUserId) -> Result<Json<Vec<AdminUserResult>>, ApiError> {
+    let users = crate::database::get_users().await?;
+
+    let users = users
+        .iter()
+        .map(|user| AdminUserResult {
+            id: user.id.clone(),
+            username: user.username.clone(),
+            is_admin: user.is_admin,
+            disabled: user.disabled,
+            password_reset: user.password_reset,
+            usage: usage_result(user),
+        })
+        .collect();
+
+    Ok(Json(users))
+}
+
+/// Disable or enable a user, grant or revoke admin rights, or set their storage quota.
+/// Admins cannot change these for themselves, so that an instance is never left without an admin by accident.
+/// Users that are being deleted cannot be changed.
+pub async fn update_user(
+    AdminUserId(admin_user_id): AdminUserId,
+    Path(user_id): Path<String>,
+    Json(request): Json<UpdateUserRequest>,
+) -> Result<StatusCode, ApiError> {
+    if user_id == admin_user_id {
+        return Err(ApiError::BadRequest("Admins cannot change their own account".into()));
+    }
+    get_user_by_id(&user_id).await?.ok_or(ApiError::NotFound)?;
+    if get_purge(&user_id).await?.is_some() {
+        return Err(ApiError::Conflict("This user is being deleted".into()));
+    }
+
+    if let Some(disabled) = request.disabled {
+        set_user_disabled(&user_id, disabled).await?;
+        if disabled {
+            remove_user_from_sessions(&user_id).await?;
+        }
+    }
+    if let Some(is_admin) = request.is_admin {
+        set_user_is_admin(&user_id, is_admin).await?;
+    }
+    if let Some(quota_bytes) = request.quota_bytes {
+        set_user_quota_bytes(&user_id, Some(quota_bytes).filter(|quota_bytes| *quota_bytes > 0)).await?;
+    }
+
+    Ok(StatusCode::OK)
+}
+
+/// Delete a user, and all of their data and stored files.
+/// Deleting happens in the background, like when users delete themselves.
+pub async fn delete_user(
+    AdminUserId(admin_user_id): AdminUserId,
+    Path(user_id): Path<String>,
+) -> Result<impl IntoResponse, ApiError> {
+    if user_id == admin_user_id {
+        return Err(ApiError::BadRequest("Admins cannot delete their own account".into()));
+    }
+    get_user_by_id(&user_id).await?.ok_or(ApiError::NotFound)?;
+
+    let purge = start_user_purge(&user_id).await?;
+
+    Ok((StatusCode::ACCEPTED, Json(user_deletion_result(&purge))))
+}
+
+/// Set a new password for a user, log them out everywhere and revoke their API tokens.
+///
+/// All of the user's data is encrypted with keys derived from their password, so the data becomes unreadable.
+/// The request must confirm this explicitly. The user is flagged, so new keys are created on their next login.
+pub async fn reset_password(
+    AdminUserId(_): AdminUserId,
+    Path(user_id): Path<String>,
+    Json(request): Json<ResetPasswordRequest>,
+) -> Result<StatusCode, ApiError> {
+    if !request.confirm_data_loss {
+        return Err(ApiError::BadRequest(
+            "Resetting a password makes all of the user's data unreadable; set confirm_data_loss to proceed".into(),
+        ));
+    }
+    if request.password.is_empty() {
+        return Err(ApiError::BadRequest("Password is empty".into()));
+    }
+    get_user_by_id(&user_id).await?.ok_or(ApiError::NotFound)?;
+
+    let password_phc = hash_password(&request.password)?;
+    set_user_password(&user_id, &password_phc, true).await?;
+    remove_user_from_sessions(&user_id).await?;
+    delete_api_tokens_for_user(&user_id).await?;
+
+    Ok(StatusCode::OK)
+}
+
+pub async fn get_invites(AdminUserId(_): AdminUserId) -> Result<Json<Vec<InviteResult>>, ApiError> {
+    let invites = crate::database::get_invites().await?;
+
+    let invites = invites
+        .into_iter()
+        .map(|invite| InviteResult {
+            code: invite.code,
+            created_on: invite.created_on,
+        })
+        .collect();
+
+    Ok(Json(invites))
+}
+
+/// Create a single-use invite code, which allows registering a user when open registration is disabled.
+pub async fn create_invite(AdminUserId(_): AdminUserId) -> Result<(StatusCode, Json<InviteResult>), ApiError> {
+    let invite = Invite {
+        code: id(),
+        created_on: OffsetDateTime::now_utc().unix_timestamp(),
+    };
+    insert_invite(&invite).await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(InviteResult {
+            code: invite.code,
+            created_on: invite.created_on,
+        }),
+    ))
+}
+
+pub async fn delete_invite(AdminUserId(_): AdminUserId, Path(code): Path<String>) -> Result<StatusCode, ApiError> {
+    if crate::database::delete_invite(&code).await? {
+        Ok(StatusCode::OK)
+    } else {
+        Err(ApiError::NotFound)
+    }
+}
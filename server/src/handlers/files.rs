@@ -1,91 +1,85 @@
-use crate::model::{File, Session};
+use super::check_new_id;
+use crate::error::ApiError;
+use crate::extract::{Json, Multipart, Path};
+use crate::model::{DbItem, File, Session};
 use crate::storage::store_file;
 use crate::UserId;
 use crate::{database::*, storage};
 use anyhow::Result;
-use axum::extract::Multipart;
-use axum::{extract::Path, http::StatusCode, Json};
+use axum::http::StatusCode;
 use upholi_lib::http::request::DeleteManyRequest;
-use upholi_lib::ids::id;
 
 struct MultipartEntry {
     pub name: String,
     pub bytes: Vec<u8>,
 }
 
-pub async fn get_file_ids(UserId(user_id): UserId) -> Result<Json<Vec<String>>, StatusCode> {
-    match get_item_ids::<File>(&user_id).await {
-        Ok(ids) => Ok(Json(ids)),
-        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
-    }
+pub async fn get_file_ids(UserId(user_id): UserId) -> Result<Json<Vec<String>>, ApiError> {
+    let ids = get_item_ids::<File>(&user_id).await?;
+    Ok(Json(ids))
 }
 
-pub async fn get_file(session: Session, Path(id): Path<String>) -> Result<Vec<u8>, StatusCode> {
-    match get_item::<File>(&id, &session).await {
-        Ok(option) => match option {
-            Some(file) => {
-                let file = crate::storage::get_file(&id, &file.container)
-                    .await
-                    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-                    .ok_or(StatusCode::NOT_FOUND)?;
-                Ok(file)
-            }
-            None => Err(StatusCode::NOT_FOUND),
-        },
-        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
-    }
+pub async fn get_file(session: Session, Path(id): Path<String>) -> Result<Vec<u8>, ApiError> {
+    let file = get_item::<File>(&id, &session).await?.ok_or(ApiError::NotFound)?;
+    let bytes = storage::get_file(&id, &file.container)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    Ok(bytes)
 }
 
-pub async fn set_files(UserId(user_id): UserId, multipart: Multipart) -> Result<StatusCode, StatusCode> {
-    let multipart_entries = get_multipart_entries(multipart)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+/// Store files, using the name of each multipart field as file ID. Returns the IDs of the stored files.
+/// No file is stored if any of the IDs is invalid or used by another user.
+pub async fn set_files(
+    UserId(user_id): UserId,
+    Multipart(multipart): Multipart,
+) -> Result<Json<Vec<String>>, ApiError> {
+    let multipart_entries = get_multipart_entries(multipart).await?;
 
+    for entry in &multipart_entries {
+        check_new_id(File::collection_name(), &entry.name, &user_id).await?;
+    }
+
+    let mut file_ids = vec![];
     for entry in multipart_entries {
-        let file_id = id();
         let file = File {
-            file_id: file_id.clone(),
             container: user_id.clone(),
         };
 
-        store_file(&entry.name, &user_id, &entry.bytes)
-            .await
-            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-        upsert_item(&entry.name, file, &user_id)
-            .await
-            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        store_file(&entry.name, &user_id, &entry.bytes).await?;
+        upsert_item(&entry.name, file, &user_id).await?;
+        file_ids.push(entry.name);
     }
 
-    Ok(StatusCode::OK)
+    Ok(Json(file_ids))
 }
 
-pub async fn delete_file(UserId(user_id): UserId, Path(id): Path<String>) -> Result<StatusCode, StatusCode> {
-    match delete_item::<File>(&id, &user_id).await {
-        Ok(_) => match storage::delete_file(&id, &user_id).await {
-            Ok(()) => Ok(StatusCode::OK),
-            Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
-        },
-        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
-    }
+pub async fn delete_file(UserId(user_id): UserId, Path(id): Path<String>) -> Result<StatusCode, ApiError> {
+    delete_item::<File>(&id, &user_id).await?;
+    storage::delete_file(&id, &user_id).await?;
+
+    Ok(StatusCode::OK)
 }
 
 pub async fn delete_files(
     UserId(user_id): UserId,
     Json(request): Json<DeleteManyRequest>,
-) -> Result<StatusCode, StatusCode> {
+) -> Result<StatusCode, ApiError> {
     for id in request.ids {
         delete_file(UserId(user_id.clone()), Path(id)).await?;
     }
     Ok(StatusCode::OK)
 }
 
-async fn get_multipart_entries(mut multipart: Multipart) -> Result<Vec<MultipartEntry>> {
+async fn get_multipart_entries(mut multipart: axum::extract::Multipart) -> Result<Vec<MultipartEntry>, ApiError> {
     let mut entries: Vec<MultipartEntry> = vec![];
 
-    while let Some(field) = multipart.next_field().await? {
-        let name = field.name().unwrap().to_string();
-        let bytes = field.bytes().await.unwrap();
+    while let Some(field) = multipart.next_field().await.map_err(bad_multipart)? {
+        let name = field
+            .name()
+            .ok_or_else(|| ApiError::BadRequest("Multipart field without a name".to_string()))?
+            .to_string();
+        let bytes = field.bytes().await.map_err(bad_multipart)?;
 
         entries.push(MultipartEntry {
             name,
@@ -95,3 +89,7 @@ async fn get_multipart_entries(mut multipart: Multipart) -> Result<Vec<Multipart
 
     Ok(entries)
 }
+
+fn bad_multipart(error: axum::extract::multipart::MultipartError) -> ApiError {
+    ApiError::BadRequest(error.body_text())
+}
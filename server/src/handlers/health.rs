@@ -0,0 +1,44 @@
+use crate::{database, storage};
+use axum::http::StatusCode;
+use std::{future::Future, time::Duration};
+
+/// Maximum time each dependency check of the readiness probe may take.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Liveness probe. Succeeds as long as the server handles requests.
+pub async fn healthz() -> StatusCode {
+    StatusCode::OK
+}
+
+/// Readiness probe. Succeeds if the database can be reached and storage is writable.
+/// The response body lists the result of each check.
+pub async fn readyz() -> (StatusCode, String) {
+    let database = check("database", database::ping()).await;
+    let storage = check("storage", storage::check_writable()).await;
+
+    let status_code = match database.is_ok() && storage.is_ok() {
+        true => StatusCode::OK,
+        false => StatusCode::SERVICE_UNAVAILABLE,
+    };
+    let body = [database, storage]
+        .into_iter()
+        .map(|result| result.unwrap_or_else(|error| error))
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    (status_code, body)
+}
+
+async fn check(name: &str, future: impl Future<Output = anyhow::Result<()>>) -> Result<String, String> {
+    match tokio::time::timeout(CHECK_TIMEOUT, future).await {
+        Ok(Ok(_)) => Ok(format!("{name}: ok")),
+        Ok(Err(error)) => {
+            tracing::warn!("Readiness check '{name}' failed: {error:?}");
+            Err(format!("{name}: failed"))
+        }
+        Err(_) => {
+            tracing::warn!("Readiness check '{name}' timed out");
+            Err(format!("{name}: timed out"))
+        }
+    }
+}
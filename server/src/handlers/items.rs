@@ -1,48 +1,57 @@
+use super::check_new_id;
 use crate::database;
-use crate::model::{EncryptedData, Session};
+use crate::error::ApiError;
+use crate::extract::{Json, Path};
+use crate::model::{DbItem, EncryptedData, Session};
 use crate::UserId;
 use anyhow::Result;
-use axum::{extract::Path, http::StatusCode, Json};
+use axum::http::StatusCode;
 use upholi_lib::http::request::DeleteManyRequest;
+use upholi_lib::http::response::CreatedResult;
+use upholi_lib::ids::id;
 
-pub async fn get_item_ids(UserId(user_id): UserId) -> Result<Json<Vec<String>>, StatusCode> {
-    match database::get_item_ids::<EncryptedData>(&user_id).await {
-        Ok(ids) => Ok(Json(ids)),
-        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
-    }
+pub async fn get_item_ids(UserId(user_id): UserId) -> Result<Json<Vec<String>>, ApiError> {
+    let ids = database::get_item_ids::<EncryptedData>(&user_id).await?;
+    Ok(Json(ids))
 }
 
-pub async fn get_item(session: Session, Path(id): Path<String>) -> Result<Json<EncryptedData>, StatusCode> {
-    match database::get_item(&id, &session).await {
-        Ok(option) => match option {
-            Some(value) => Ok(Json(value)),
-            None => Err(StatusCode::NOT_FOUND),
-        },
-        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
-    }
+pub async fn get_item(session: Session, Path(id): Path<String>) -> Result<Json<EncryptedData>, ApiError> {
+    let item = database::get_item(&id, &session).await?.ok_or(ApiError::NotFound)?;
+    Ok(Json(item))
 }
 
+/// Create an item with an ID chosen by the server, which is returned.
+pub async fn create_item(
+    UserId(user_id): UserId,
+    Json(item): Json<EncryptedData>,
+) -> Result<(StatusCode, Json<CreatedResult>), ApiError> {
+    let id = id();
+    database::upsert_item(&id, item, &user_id).await?;
+
+    Ok((StatusCode::CREATED, Json(CreatedResult { id })))
+}
+
+/// Create or update an item with an ID chosen by the client.
 pub async fn set_item(
     UserId(user_id): UserId,
     Path(id): Path<String>,
     Json(item): Json<EncryptedData>,
-) -> Result<StatusCode, StatusCode> {
-    match database::upsert_item(&id, item, &user_id).await {
-        Ok(_) => Ok(StatusCode::OK),
-        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
-    }
+) -> Result<StatusCode, ApiError> {
+    check_new_id(EncryptedData::collection_name(), &id, &user_id).await?;
+    database::upsert_item(&id, item, &user_id).await?;
+
+    Ok(StatusCode::OK)
 }
 
-pub async fn delete_item(UserId(user_id): UserId, Path(id): Path<String>) -> Result<StatusCode, StatusCode> {
+pub async fn delete_item(UserId(user_id): UserId, Path(id): Path<String>) -> Result<StatusCode, ApiError> {
     delete_items(UserId(user_id), Json(DeleteManyRequest { ids: vec![id] })).await
 }
 
 pub async fn delete_items(
     UserId(user_id): UserId,
     Json(request): Json<DeleteManyRequest>,
-) -> Result<StatusCode, StatusCode> {
-    match database::delete_items::<EncryptedData>(&request.ids, &user_id).await {
-        Ok(_) => Ok(StatusCode::OK),
-        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
-    }
+) -> Result<StatusCode, ApiError> {
+    database::delete_items::<EncryptedData>(&request.ids, &user_id).await?;
+
+    Ok(StatusCode::OK)
 }
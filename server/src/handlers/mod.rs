@@ -1,10 +1,16 @@
 use crate::database::*;
-use crate::model::Session;
+use crate::error::ApiError;
+use crate::model::{DbItem, EncryptedData, Session};
 use anyhow::Result;
+use upholi_lib::ids::{is_valid_id, PER_USER_IDS};
 
+pub mod admin;
 pub mod files;
+pub mod health;
 pub mod items;
 pub mod shares;
+pub mod tokens;
+pub mod trash;
 pub mod user;
 
 /// Grant the current session access to given share ID. If no session exists, one is created.
@@ -27,3 +33,18 @@ async fn auth_user_for_session(mut session: Session, user_id: &str) -> Result<()
 
     Ok(())
 }
+
+/// Check that an ID chosen by a client is valid, and that no other user uses it in given collection.
+/// Only items can use the well-known IDs that every user has one of, such as the library.
+async fn check_new_id(collection_name: &str, id: &str, user_id: &str) -> Result<(), ApiError> {
+    if !is_valid_id(id) {
+        return Err(ApiError::InvalidId(id.to_string()));
+    }
+
+    let per_user_id = collection_name == EncryptedData::collection_name() && PER_USER_IDS.contains(&id);
+    if !per_user_id && is_id_used_by_other_user(collection_name, id, user_id).await? {
+        return Err(ApiError::IdInUse(id.to_string()));
+    }
+
+    Ok(())
+}
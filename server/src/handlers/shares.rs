@@ -1,10 +1,13 @@
-use super::auth_share_for_session;
+use super::{auth_share_for_session, check_new_id};
 use crate::database::{self, *};
+use crate::error::ApiError;
+use crate::extract::{Json, Path};
 use crate::model::{Session, Share};
 use crate::UserId;
 use anyhow::Result;
-use axum::{extract::Path, http::StatusCode, Json};
+use axum::http::StatusCode;
 use upholi_lib::http::request::*;
+use upholi_lib::ids::is_valid_id;
 use upholi_lib::passwords::{hash_password, verify_password_hash};
 
 pub async fn is_authorized_for_share(Path(id): Path<String>, session: Session) -> StatusCode {
@@ -19,26 +22,21 @@ pub async fn authorize_share(
     session: Session,
     Path(id): Path<String>,
     Json(credentials): Json<AuthorizeShareRequest>,
-) -> Result<StatusCode, StatusCode> {
+) -> Result<StatusCode, ApiError> {
     let already_authorized = session.shares.contains(&id);
 
     if already_authorized {
         // This session is already authorized to this share; we won't verify the provided password.
         Ok(StatusCode::OK)
     } else {
-        let share = database::get_share(&id)
-            .await
-            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-            .ok_or(StatusCode::NOT_FOUND)?;
+        let share = database::get_share(&id).await?.ok_or(ApiError::NotFound)?;
 
         let password_correct = verify_password_hash(&credentials.password, &share.password_phc);
         if password_correct {
-            auth_share_for_session(session, &share.id)
-                .await
-                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+            auth_share_for_session(session, &share.id).await?;
             Ok(StatusCode::OK)
         } else {
-            Err(StatusCode::UNAUTHORIZED)
+            Err(ApiError::Unauthorized)
         }
     }
 }
@@ -47,8 +45,13 @@ pub async fn authorize_share(
 pub async fn create_share(
     UserId(user_id): UserId,
     Json(share): Json<UpsertShareRequest>,
-) -> Result<StatusCode, StatusCode> {
-    let password_phc = hash_password(&share.password).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+) -> Result<StatusCode, ApiError> {
+    check_new_id(COLLECTION_NAME_SHARES, &share.id, &user_id).await?;
+    if let Some(invalid_id) = share.items.iter().find(|id| !is_valid_id(id)) {
+        return Err(ApiError::InvalidId(invalid_id.clone()));
+    }
+
+    let password_phc = hash_password(&share.password)?;
     let item_ids_for_share = [vec![share.id.clone()], share.items].concat();
 
     let share = Share {
@@ -57,28 +60,21 @@ pub async fn create_share(
         password_phc,
     };
 
-    upsert_share(&share)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-    set_items_for_share(&share.id, &item_ids_for_share)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    remove_authorizations_for_share(&share.id)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    upsert_share(&share).await?;
+    set_items_for_share(&share.id, &item_ids_for_share, &user_id).await?;
+    remove_authorizations_for_share(&share.id).await?;
     Ok(StatusCode::OK)
 }
 
 /// Delete a share
-pub async fn delete_share(UserId(user_id): UserId, Path(id): Path<String>) -> Result<StatusCode, StatusCode> {
-    remove_items_from_share(&id)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+pub async fn delete_share(UserId(user_id): UserId, Path(id): Path<String>) -> Result<StatusCode, ApiError> {
+    let share = database::get_share(&id).await?.ok_or(ApiError::NotFound)?;
+    if share.user_id != user_id {
+        return Err(ApiError::NotFound);
+    }
 
-    database::delete_share(&user_id, &id)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    remove_items_from_share(&id).await?;
+    database::delete_share(&user_id, &id).await?;
 
     Ok(StatusCode::OK)
 }
@@ -0,0 +1,77 @@
+use crate::api_tokens::generate_token;
+use crate::database::{self, *};
+use crate::error::ApiError;
+use crate::extract::{Json, Path};
+use crate::model::ApiToken;
+use crate::UserId;
+use anyhow::Result;
+use axum::http::StatusCode;
+use cookie::time::OffsetDateTime;
+use upholi_lib::http::{request::CreateApiTokenRequest, response::*, TokenScope};
+use upholi_lib::ids::id;
+
+/// Get the current user's API tokens. The tokens themselves cannot be retrieved.
+pub async fn get_api_tokens(UserId(user_id): UserId) -> Result<Json<Vec<ApiTokenResult>>, ApiError> {
+    let api_tokens = database::get_api_tokens(&user_id).await?;
+
+    let api_tokens = api_tokens
+        .into_iter()
+        .map(|api_token| ApiTokenResult {
+            id: api_token.id,
+            name: api_token.name,
+            scopes: api_token.scopes,
+            created_on: api_token.created_on,
+            last_used_on: api_token.last_used_on,
+        })
+        .collect();
+
+    Ok(Json(api_tokens))
+}
+
+/// Create an API token for the current user. The response contains the token, which is not shown again.
+pub async fn create_api_token(
+    UserId(user_id): UserId,
+    Json(request): Json<CreateApiTokenRequest>,
+) -> Result<(StatusCode, Json<CreatedApiTokenResult>), ApiError> {
+    if request.name.is_empty() {
+        return Err(ApiError::BadRequest("Name is empty".into()));
+    }
+    if request.scopes.is_empty() {
+        return Err(ApiError::BadRequest("At least one scope is required".into()));
+    }
+    if request.scopes.contains(&TokenScope::Admin) {
+        let user = get_user_by_id(&user_id).await?.ok_or(ApiError::NotFound)?;
+        if !user.is_admin {
+            return Err(ApiError::Forbidden);
+        }
+    }
+
+    let (token, token_hash) = generate_token();
+    let api_token = ApiToken {
+        id: id(),
+        user_id,
+        name: request.name,
+        token_hash,
+        scopes: request.scopes,
+        created_on: OffsetDateTime::now_utc().unix_timestamp(),
+        last_used_on: None,
+    };
+    insert_api_token(&api_token).await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(CreatedApiTokenResult {
+            id: api_token.id,
+            token,
+        }),
+    ))
+}
+
+/// Revoke an API token of the current user.
+pub async fn delete_api_token(UserId(user_id): UserId, Path(id): Path<String>) -> Result<StatusCode, ApiError> {
+    if database::delete_api_token(&user_id, &id).await? {
+        Ok(StatusCode::OK)
+    } else {
+        Err(ApiError::NotFound)
+    }
+}
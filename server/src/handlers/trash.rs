@@ -0,0 +1,127 @@
+use crate::database;
+use crate::error::ApiError;
+use crate::extract::Json;
+use crate::model::TrashEntry;
+use crate::{trash, UserId};
+use anyhow::Result;
+use axum::http::StatusCode;
+use cookie::time::OffsetDateTime;
+use upholi_lib::http::request::{DeleteManyRequest, TrashRequest};
+use upholi_lib::http::response::TrashEntryResult;
+use upholi_lib::ids::is_valid_id;
+
+/// Get the current user's trash entries, oldest first.
+pub async fn get_trash(UserId(user_id): UserId) -> Result<Json<Vec<TrashEntryResult>>, ApiError> {
+    let entries = database::get_trash_entries(&user_id).await?;
+
+    let entries = entries
+        .iter()
+        .map(|entry| TrashEntryResult {
+            id: entry.id.clone(),
+            trashed_on: entry.trashed_on,
+            expires_on: trash::expires_on(entry),
+        })
+        .collect();
+
+    Ok(Json(entries))
+}
+
+/// Move items and files to the trash. They are deleted once the retention period has passed, unless restored.
+pub async fn add_to_trash(UserId(user_id): UserId, Json(request): Json<TrashRequest>) -> Result<StatusCode, ApiError> {
+    if let Some(id) = find_invalid_id(&request) {
+        return Err(ApiError::InvalidId(id.clone()));
+    }
+
+    let trashed_on = OffsetDateTime::now_utc().unix_timestamp();
+    let entries: Vec<TrashEntry> = request
+        .entries
+        .into_iter()
+        .map(|entry| TrashEntry {
+            id: entry.id,
+            user_id: user_id.clone(),
+            item_ids: entry.item_ids,
+            file_ids: entry.file_ids,
+            trashed_on,
+        })
+        .collect();
+    database::upsert_trash_entries(&entries).await?;
+
+    Ok(StatusCode::OK)
+}
+
+/// Find an entry, item or file ID in a trash request that is not valid.
+/// These IDs are later used to delete items and files, which fails for invalid IDs.
+fn find_invalid_id(request: &TrashRequest) -> Option<&String> {
+    request
+        .entries
+        .iter()
+        .flat_map(|entry| std::iter::once(&entry.id).chain(&entry.item_ids).chain(&entry.file_ids))
+        .find(|id| !is_valid_id(id))
+}
+
+/// Take entries out of the trash, keeping their items and files.
+pub async fn restore_from_trash(
+    UserId(user_id): UserId,
+    Json(request): Json<DeleteManyRequest>,
+) -> Result<StatusCode, ApiError> {
+    database::delete_trash_entries(&user_id, &request.ids).await?;
+
+    Ok(StatusCode::OK)
+}
+
+/// Delete trash entries with their items and files right away.
+pub async fn delete_from_trash(
+    UserId(user_id): UserId,
+    Json(request): Json<DeleteManyRequest>,
+) -> Result<StatusCode, ApiError> {
+    let entries = database::get_trash_entries_by_id(&user_id, &request.ids).await?;
+    trash::delete_entries(&entries).await?;
+
+    Ok(StatusCode::OK)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use upholi_lib::http::request::TrashEntryRequest;
+
+    fn trash_request(id: &str, item_ids: &[&str], file_ids: &[&str]) -> TrashRequest {
+        TrashRequest {
+            entries: vec![TrashEntryRequest {
+                id: id.to_string(),
+                item_ids: item_ids.iter().map(|id| id.to_string()).collect(),
+                file_ids: file_ids.iter().map(|id| id.to_string()).collect(),
+            }],
+        }
+    }
+
+    #[test]
+    fn valid_request_has_no_invalid_id() {
+        let request = trash_request("photo-1", &["photo-1"], &["photo-1-original", "photo-1-thumbnail"]);
+
+        assert_eq!(find_invalid_id(&request), None);
+    }
+
+    #[tokio::test]
+    async fn invalid_entry_id_is_rejected() {
+        let result = add_to_trash(UserId("user".to_string()), Json(trash_request("../photo", &[], &[]))).await;
+
+        assert!(matches!(result, Err(ApiError::InvalidId(id)) if id == "../photo"));
+    }
+
+    #[tokio::test]
+    async fn invalid_item_id_is_rejected() {
+        let request = trash_request("photo", &["photo", "bad id"], &[]);
+        let result = add_to_trash(UserId("user".to_string()), Json(request)).await;
+
+        assert!(matches!(result, Err(ApiError::InvalidId(id)) if id == "bad id"));
+    }
+
+    #[tokio::test]
+    async fn invalid_file_id_is_rejected() {
+        let request = trash_request("photo", &["photo"], &["photo-original", "../../other-user/file"]);
+        let result = add_to_trash(UserId("user".to_string()), Json(request)).await;
+
+        assert!(matches!(result, Err(ApiError::InvalidId(id)) if id == "../../other-user/file"));
+    }
+}
@@ -1,66 +1,145 @@
 use super::auth_user_for_session;
 use crate::database::*;
-use crate::model::{Session, User};
-use crate::storage::init_storage_for_user;
-use crate::UserId;
-use anyhow::{anyhow, Result};
-use axum::{http::StatusCode, response::IntoResponse, Json};
+use crate::error::ApiError;
+use crate::extract::Json;
+use crate::model::{Purge, Session, User};
+use crate::purge::start_user_purge;
+use crate::storage::{delete_storage_for_user, get_quota, init_storage_for_user};
+use crate::{UserId, SETTINGS};
+use anyhow::Result;
+use axum::{http::StatusCode, response::IntoResponse};
 use upholi_lib::http::{request::*, response::*};
 use upholi_lib::ids::id;
 use upholi_lib::passwords::{hash_password, verify_password_hash};
 
-pub async fn get_user(UserId(_): UserId) -> StatusCode {
-    StatusCode::OK
+pub async fn get_user(UserId(user_id): UserId) -> Json<UserResult> {
+    Json(UserResult { id: user_id })
+}
+
+/// Get the current user's storage usage and quota.
+pub async fn get_user_usage(UserId(user_id): UserId) -> Result<Json<UsageResult>, ApiError> {
+    let user = get_user_by_id(&user_id).await?.ok_or(ApiError::NotFound)?;
+
+    Ok(Json(usage_result(&user)))
+}
+
+pub fn usage_result(user: &User) -> UsageResult {
+    UsageResult {
+        bytes_used: user.bytes_stored.max(0) as u64,
+        bytes_quota: get_quota(user),
+    }
+}
+
+/// Mark the current user's password reset as handled, after new keys have been created.
+pub async fn clear_password_reset(UserId(user_id): UserId) -> Result<StatusCode, ApiError> {
+    clear_user_password_reset(&user_id).await?;
+
+    Ok(StatusCode::OK)
 }
 
 pub async fn create_user(
     session: Session,
     Json(user_info): Json<CreateUserRequest>,
-) -> Result<impl IntoResponse, StatusCode> {
-    let result = handler_create_user(&user_info)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+) -> Result<impl IntoResponse, ApiError> {
+    if get_user_by_username(&user_info.username).await?.is_some() {
+        return Err(ApiError::UsernameTaken);
+    }
+
+    // The invite is only consumed once the user has been created, so a failed registration can be retried with it
+    let invite_code = if SETTINGS.server.allow_registration {
+        None
+    } else {
+        let invite_code = user_info.invite_code.as_deref().ok_or(ApiError::InviteRequired)?;
+        if get_invite(invite_code).await?.is_none() {
+            return Err(ApiError::InviteRequired);
+        }
+        Some(invite_code)
+    };
+
+    let result = handler_create_user(&user_info).await?;
+
+    if let Some(invite_code) = invite_code {
+        // Another registration may have used the same invite in the meantime
+        if !delete_invite(invite_code).await? {
+            delete_storage_for_user(&result.id).await?;
+            crate::database::delete_user(&result.id).await?;
+            return Err(ApiError::InviteRequired);
+        }
+    }
 
-    auth_user_for_session(session, &result.id)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    auth_user_for_session(session, &result.id).await?;
 
     Ok((StatusCode::CREATED, Json(result)))
 }
 
-async fn handler_create_user(user_info: &CreateUserRequest) -> Result<CreatedResult> {
-    if get_user_by_username(&user_info.username).await?.is_some() {
-        Err(anyhow!("A user with this username already exists."))
-    } else {
-        let password_phc = hash_password(&user_info.password)?;
-        let user_id = id();
-        let user = User {
-            id: user_id.clone(),
-            username: user_info.username.clone(),
-            password_phc,
-        };
-        insert_user(&user).await?;
-        init_storage_for_user(&user).await?;
-        Ok(CreatedResult { id: user_id })
+/// Delete the current user and all of their data, after confirming their password.
+/// Deleting happens in the background; its progress can be followed using `get_user_deletion`.
+pub async fn delete_user(
+    UserId(user_id): UserId,
+    Json(request): Json<DeleteUserRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let user = get_user_by_id(&user_id).await?.ok_or(ApiError::NotFound)?;
+
+    if !verify_password_hash(&request.password, &user.password_phc) {
+        return Err(ApiError::Unauthorized);
     }
+
+    let purge = start_user_purge(&user_id).await?;
+
+    Ok((StatusCode::ACCEPTED, Json(user_deletion_result(&purge))))
+}
+
+/// Get the progress of deleting the current user. The user is disabled while being deleted, so this
+/// takes the session instead of a `UserId`. Once deleting is done, the session is no longer authorized.
+pub async fn get_user_deletion(session: Session) -> Result<Json<UserDeletionResult>, ApiError> {
+    let user_id = session.user_id.ok_or(ApiError::Unauthorized)?;
+    let purge = get_purge(&user_id).await?.ok_or(ApiError::NotFound)?;
+
+    Ok(Json(user_deletion_result(&purge)))
+}
+
+pub fn user_deletion_result(purge: &Purge) -> UserDeletionResult {
+    UserDeletionResult {
+        files_total: purge.files_total,
+        files_deleted: purge.files_deleted,
+    }
+}
+
+async fn handler_create_user(user_info: &CreateUserRequest) -> Result<CreatedResult> {
+    let password_phc = hash_password(&user_info.password)?;
+    let user_id = id();
+    let user = User {
+        id: user_id.clone(),
+        username: user_info.username.clone(),
+        password_phc,
+        bytes_stored: 0,
+        quota_bytes: None,
+        is_admin: false,
+        disabled: false,
+        password_reset: false,
+    };
+    insert_user(&user).await?;
+    init_storage_for_user(&user).await?;
+    Ok(CreatedResult { id: user_id })
 }
 
 pub async fn authenticate_user(
     session: Session,
     Json(credentials): Json<AuthenticateUserRequest>,
-) -> impl IntoResponse {
+) -> Result<Json<AuthenticateUserResult>, ApiError> {
     let user = get_user_by_username(&credentials.username)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .ok_or(StatusCode::NOT_FOUND)?;
+        .await?
+        .ok_or(ApiError::NotFound)?;
 
     let password_correct = verify_password_hash(&credentials.password, &user.password_phc);
-    if password_correct {
-        auth_user_for_session(session, &user.id)
-            .await
-            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-        Ok(StatusCode::OK)
+    if !password_correct {
+        Err(ApiError::Unauthorized)
+    } else if user.disabled {
+        Err(ApiError::AccountDisabled)
     } else {
-        Err(StatusCode::UNAUTHORIZED)
+        auth_user_for_session(session, &user.id).await?;
+        Ok(Json(AuthenticateUserResult {
+            password_reset: user.password_reset,
+        }))
     }
 }
@@ -0,0 +1,47 @@
+use crate::settings::LogFormat;
+use crate::SETTINGS;
+use anyhow::{anyhow, Result};
+use axum::{extract::MatchedPath, http::Request};
+use tower_http::request_id::RequestId;
+use tracing::{field::Empty, Span};
+use tracing_subscriber::EnvFilter;
+
+/// Install the global subscriber for log events, using the level and format from settings.
+pub fn init() -> Result<()> {
+    let filter = EnvFilter::try_new(&SETTINGS.logging.level)?;
+    let builder = tracing_subscriber::fmt().with_env_filter(filter);
+
+    match SETTINGS.logging.format {
+        LogFormat::Pretty => builder.pretty().try_init(),
+        LogFormat::Json => builder.json().try_init(),
+    }
+    .map_err(|error| anyhow!(error))
+}
+
+/// Create the span that all log events of an HTTP request belong to.
+/// The user ID is recorded once the session of the request is known. Request and response bodies are never logged.
+pub fn request_span<B>(request: &Request<B>) -> Span {
+    let request_id = request
+        .extensions()
+        .get::<RequestId>()
+        .and_then(|request_id| request_id.header_value().to_str().ok())
+        .unwrap_or_default();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(MatchedPath::as_str)
+        .unwrap_or_default();
+
+    tracing::info_span!(
+        "request",
+        request_id,
+        method = %request.method(),
+        route,
+        user_id = Empty,
+    )
+}
+
+/// Add the ID of the current user to the span of the current request.
+pub fn record_user_id(user_id: &str) {
+    Span::current().record("user_id", user_id);
+}
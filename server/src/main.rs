@@ -2,30 +2,51 @@ use crate::settings::Settings;
 use anyhow::Result;
 use axum::{
     async_trait,
-    extract::{DefaultBodyLimit, FromRequestParts},
-    http::{request::Parts, HeaderMap, HeaderValue, StatusCode},
+    extract::{DefaultBodyLimit, FromRequestParts, OriginalUri},
+    http::{request::Parts, HeaderMap, HeaderValue, Method},
     middleware::Next,
     response::Response,
     routing::{delete, get, get_service, post},
     Router,
 };
+use clap::{Parser, Subcommand};
 use cookie::{
     time::{Duration, OffsetDateTime},
     SameSite,
 };
 use database::upsert_session;
-use handlers::{files::*, items::*, shares::*, user::*};
+use error::ApiError;
+use handlers::{admin, files::*, health, items::*, shares::*, tokens, trash::*, user::*};
 use lazy_static::lazy_static;
 use model::Session;
+use std::{net::SocketAddr, path::PathBuf};
 use tower_cookies::{Cookie, CookieManagerLayer};
-use tower_http::services::ServeDir;
+use tower_http::{
+    request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer},
+    services::ServeDir,
+    trace::{DefaultOnResponse, TraceLayer},
+    LatencyUnit,
+};
+use tracing::Level;
+use upholi_lib::http::{CSRF_COOKIE_NAME, CSRF_HEADER_NAME};
 use upholi_lib::ids::id;
 
+mod api_tokens;
+mod commands;
 mod database;
+mod error;
+mod extract;
 mod handlers;
+mod logging;
+mod maintenance;
+mod metrics;
 mod model;
+mod purge;
+mod security_headers;
 mod settings;
 mod storage;
+mod tls;
+mod trash;
 
 lazy_static! {
     /// Global application settings
@@ -38,9 +59,78 @@ const SESSION_COOKIE_EXPIRATION_TIME_DAYS: i64 = 60;
 
 pub struct UserId(String);
 
+/// ID of the current user, who must be an admin.
+pub struct AdminUserId(String);
+
+#[derive(Parser)]
+#[command(version, about = "upholi server")]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Run the web server. This is the default when no command is given.
+    Serve,
+    /// Write a snapshot of all data and stored files to an archive.
+    Backup {
+        /// Path of the archive to create
+        path: PathBuf,
+    },
+    /// Restore an archive created by 'backup' into an empty instance.
+    Restore {
+        /// Path of the archive to restore
+        path: PathBuf,
+    },
+    /// Cross-check file records against storage, and report orphaned files and dangling records.
+    Fsck {
+        /// Delete orphaned files and dangling records
+        #[arg(long)]
+        repair: bool,
+    },
+    /// Move files stored by the disk storage provider of earlier versions into per-user directories.
+    MigrateStorage,
+    /// Grant a user admin rights, allowing them to manage other users.
+    SetAdmin {
+        /// Username of the user
+        username: String,
+        /// Revoke admin rights instead
+        #[arg(long)]
+        revoke: bool,
+    },
+}
+
 #[tokio::main]
-async fn main() {
-    let virtual_page_paths: [&str; 6] = ["/404", "/login", "/register", "/albums", "/album/", "/s/"];
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+    Settings::load()?;
+    logging::init()?;
+    database::init().await?;
+
+    match cli.command.unwrap_or(Command::Serve) {
+        Command::Serve => serve().await,
+        Command::Backup { path } => commands::backup::run(&path).await,
+        Command::Restore { path } => commands::restore::run(&path).await,
+        Command::Fsck { repair } => commands::fsck::run(repair).await,
+        Command::MigrateStorage => commands::migrate_storage::run().await,
+        Command::SetAdmin { username, revoke } => commands::set_admin::run(&username, !revoke).await,
+    }
+}
+
+async fn serve() -> Result<()> {
+    let virtual_page_paths: [&str; 10] = [
+        "/404",
+        "/login",
+        "/register",
+        "/albums",
+        "/album/",
+        "/s/",
+        "/duplicates",
+        "/favourites",
+        "/trash",
+        "/search",
+    ];
     let mut index_file_router = Router::new();
 
     for path in virtual_page_paths {
@@ -51,12 +141,27 @@ async fn main() {
     }
 
     let api_routes = Router::new()
-        .route("/user", get(get_user).post(create_user))
+        .route("/user", get(get_user).post(create_user).delete(delete_user))
+        .route("/user/deletion", get(get_user_deletion))
         .route("/user/auth", post(authenticate_user))
+        .route("/user/usage", get(get_user_usage))
+        .route("/user/password-reset", delete(clear_password_reset))
+        .route(
+            "/user/token",
+            get(tokens::get_api_tokens).post(tokens::create_api_token),
+        )
+        .route("/user/token/:id", delete(tokens::delete_api_token))
+        .route("/admin/user", get(admin::get_users))
+        .route("/admin/user/:id", post(admin::update_user).delete(admin::delete_user))
+        .route("/admin/user/:id/password", post(admin::reset_password))
+        .route("/admin/invite", get(admin::get_invites).post(admin::create_invite))
+        .route("/admin/invite/:code", delete(admin::delete_invite))
         .route("/share", post(create_share))
         .route("/share/:id", delete(delete_share))
         .route("/share/:id/auth", get(is_authorized_for_share).post(authorize_share))
-        .route("/item", get(get_item_ids).delete(delete_items))
+        .route("/trash", get(get_trash).post(add_to_trash).delete(delete_from_trash))
+        .route("/trash/restore", post(restore_from_trash))
+        .route("/item", get(get_item_ids).post(create_item).delete(delete_items))
         .route("/item/:id", get(get_item).post(set_item).delete(delete_item))
         .route(
             "/file",
@@ -65,23 +170,80 @@ async fn main() {
                 .layer(DefaultBodyLimit::max(52_428_800))
                 .delete(delete_files),
         )
-        .route("/file/:id", get(get_file).delete(delete_file));
+        .route("/file/:id", get(get_file).delete(delete_file))
+        .layer(axum::middleware::from_fn(metrics::track_requests))
+        .layer(
+            TraceLayer::new_for_http()
+                .make_span_with(logging::request_span)
+                .on_response(
+                    DefaultOnResponse::new()
+                        .level(Level::INFO)
+                        .latency_unit(LatencyUnit::Millis),
+                )
+                // Internal errors are logged by ApiError, including their cause
+                .on_failure(()),
+        );
 
     let app = Router::new()
         .nest("/api", api_routes)
         .merge(index_file_router)
         .fallback(get_service(ServeDir::new(&SETTINGS.server.wwwroot_path)))
         .layer(CookieManagerLayer::new())
-        .layer(axum::middleware::from_fn(session_cookie_layer));
+        .layer(axum::middleware::from_fn(session_cookie_layer))
+        // Probes do not need a session
+        .route("/healthz", get(health::healthz))
+        .route("/readyz", get(health::readyz))
+        .layer(axum::middleware::from_fn(security_headers::security_headers_layer))
+        .layer(PropagateRequestIdLayer::x_request_id())
+        .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid));
+
+    if let Err(error) = maintenance::migrate_legacy_files().await {
+        tracing::error!("Migrating legacy files failed: {error:?}");
+    }
+    maintenance::backfill_bytes_stored().await?;
+    maintenance::spawn_scheduled_fsck();
+    trash::spawn_scheduled_purge();
+    purge::spawn_scheduled_user_purges();
 
     // run it
-    let addr = SETTINGS
-        .server
-        .address
-        .parse()
-        .unwrap_or_else(|_| panic!("Invalid server address: {}", SETTINGS.server.address));
-    println!("listening on {addr}");
-    axum::Server::bind(&addr).serve(app.into_make_service()).await.unwrap();
+    let addr: SocketAddr = SETTINGS.server.address.parse()?;
+    spawn_metrics_server()?;
+
+    if tls::is_enabled() {
+        let config = tls::load_config().await?;
+        tls::spawn_redirect_server(addr.port())?;
+
+        tracing::info!("listening on {addr} (HTTPS)");
+        axum_server::bind_rustls(addr, config)
+            .serve(app.into_make_service())
+            .await?;
+    } else {
+        tracing::info!("listening on {addr}");
+        axum::Server::bind(&addr).serve(app.into_make_service()).await?;
+    }
+
+    Ok(())
+}
+
+/// Serve metrics on their own address, if configured, so they can be kept private.
+fn spawn_metrics_server() -> Result<()> {
+    if SETTINGS.server.metrics_address.is_empty() {
+        return Ok(());
+    }
+
+    let addr = SETTINGS.server.metrics_address.parse()?;
+    let app = Router::new().route("/metrics", get(metrics::get_metrics));
+
+    let server = axum::Server::try_bind(&addr)?.serve(app.into_make_service());
+
+    tracing::info!("serving metrics on {addr}");
+    tokio::spawn(async move {
+        if let Err(error) = server.await {
+            tracing::error!("Metrics server failed: {error:?}");
+        }
+    });
+
+    Ok(())
 }
 
 #[async_trait]
@@ -89,16 +251,20 @@ impl<S> FromRequestParts<S> for Session
 where
     S: Send + Sync,
 {
-    type Rejection = StatusCode;
+    type Rejection = ApiError;
     async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
-        let session_id = get_session_id_from_headers(&parts.headers)
-            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-            .ok_or(StatusCode::UNAUTHORIZED)?;
-
-        database::get_session(&session_id)
-            .await
-            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-            .ok_or(StatusCode::UNAUTHORIZED)
+        if let Some(token) = api_tokens::get_bearer_token(&parts.headers) {
+            // Routes are nested, so the URI of the request parts lacks the '/api' prefix
+            let path = match parts.extensions.get::<OriginalUri>() {
+                Some(OriginalUri(uri)) => uri.path(),
+                None => parts.uri.path(),
+            };
+            return api_tokens::authenticate(token, &parts.method, path).await;
+        }
+
+        let session_id = get_session_id_from_headers(&parts.headers).ok_or(ApiError::Unauthorized)?;
+
+        database::get_session(&session_id).await?.ok_or(ApiError::Unauthorized)
     }
 }
 
@@ -107,60 +273,141 @@ impl<S> FromRequestParts<S> for UserId
 where
     S: Send + Sync,
 {
-    type Rejection = StatusCode;
+    type Rejection = ApiError;
     async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
         let session = Session::from_request_parts(parts, state).await?;
-        let user_id = session.user_id.ok_or(StatusCode::UNAUTHORIZED)?;
+        let user_id = session.user_id.ok_or(ApiError::Unauthorized)?;
+        logging::record_user_id(&user_id);
+
+        // Sessions of disabled users, including users that are being deleted, are no longer authorized
+        let user = database::get_user_by_id(&user_id)
+            .await?
+            .ok_or(ApiError::Unauthorized)?;
+        if user.disabled {
+            return Err(ApiError::AccountDisabled);
+        }
+
         Ok(UserId(user_id))
     }
 }
 
+#[async_trait]
+impl<S> FromRequestParts<S> for AdminUserId
+where
+    S: Send + Sync,
+{
+    type Rejection = ApiError;
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let UserId(user_id) = UserId::from_request_parts(parts, state).await?;
+        let user = database::get_user_by_id(&user_id)
+            .await?
+            .ok_or(ApiError::Unauthorized)?;
+
+        if user.is_admin && !user.disabled {
+            Ok(AdminUserId(user_id))
+        } else {
+            Err(ApiError::Forbidden)
+        }
+    }
+}
+
 /// Middleware that ensures a session exists, and extends its duration if a session was already present in the request.
-async fn session_cookie_layer<B>(mut req: axum::http::Request<B>, next: Next<B>) -> Result<Response, StatusCode> {
-    let session_id = get_session_id_from_headers(req.headers()).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+///
+/// Also issues a CSRF token for each session, in a cookie readable by the app. API requests that can change state
+/// must send the token back in a header, which other sites cannot do.
+async fn session_cookie_layer<B>(mut req: axum::http::Request<B>, next: Next<B>) -> Result<Response, ApiError> {
+    // Requests authenticated with an API token do not use sessions. Browsers never add such tokens by themselves,
+    // so these requests cannot be forged by other sites either.
+    if api_tokens::get_bearer_token(req.headers()).is_some() {
+        return Ok(next.run(req).await);
+    }
+
+    let csrf_token = get_cookie_from_headers(req.headers(), CSRF_COOKIE_NAME);
+    if requires_csrf_token(&req) && !is_csrf_token_valid(req.headers(), csrf_token.as_deref()) {
+        return Err(ApiError::InvalidCsrfToken);
+    }
+
+    let session_id = get_session_id_from_headers(req.headers());
     let request_contains_session = session_id.is_some();
 
+    // A new session gets a new CSRF token
+    let csrf_token = match csrf_token {
+        Some(csrf_token) if request_contains_session => csrf_token,
+        _ => id(),
+    };
+
     // Create a new session if request did not contain one
     let session_id = match session_id {
         Some(session_id) => session_id,
-        None => create_new_session()
-            .await
-            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?,
+        None => create_new_session().await?,
     };
 
     let session_cookie = create_sesson_cookie(session_id);
+    let csrf_cookie = create_csrf_cookie(csrf_token);
 
     // Add the newly created session to the request
     if !request_contains_session {
-        let header_value =
-            HeaderValue::from_str(&session_cookie.to_string()).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        let header_value = HeaderValue::from_str(&session_cookie.to_string())?;
         req.headers_mut().append(axum::http::header::COOKIE, header_value);
     }
 
     // Handle request
     let mut response = next.run(req).await;
 
-    // Write the cookie to the response
+    // Write the cookies to the response
     response.headers_mut().insert(
         axum::http::header::SET_COOKIE,
-        HeaderValue::from_str(&session_cookie.to_string()).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?,
+        HeaderValue::from_str(&session_cookie.to_string())?,
+    );
+    response.headers_mut().append(
+        axum::http::header::SET_COOKIE,
+        HeaderValue::from_str(&csrf_cookie.to_string())?,
     );
 
     Ok(response)
 }
 
-fn get_session_id_from_headers(headers: &HeaderMap) -> Result<Option<String>> {
-    if let Some(cookie_header) = headers.get(axum::http::header::COOKIE).cloned() {
-        let cookie_header = cookie_header.to_str()?;
-        let session_cookie = Cookie::parse(cookie_header)?;
-        let session_id = session_cookie.value();
+/// API requests other than GET, HEAD and OPTIONS require a CSRF token.
+fn requires_csrf_token<B>(req: &axum::http::Request<B>) -> bool {
+    let is_safe_method = [Method::GET, Method::HEAD, Method::OPTIONS].contains(req.method());
+    req.uri().path().starts_with("/api/") && !is_safe_method
+}
 
-        Ok(Some(session_id.into()))
-    } else {
-        Ok(None)
+/// Check that the CSRF token header matches the token cookie.
+fn is_csrf_token_valid(headers: &HeaderMap, csrf_token: Option<&str>) -> bool {
+    let header_token = headers.get(CSRF_HEADER_NAME).and_then(|value| value.to_str().ok());
+
+    match (csrf_token, header_token) {
+        (Some(csrf_token), Some(header_token)) => {
+            // Compare in constant time, so the token cannot be guessed byte by byte
+            csrf_token.len() == header_token.len()
+                && csrf_token
+                    .bytes()
+                    .zip(header_token.bytes())
+                    .fold(0, |difference, (a, b)| difference | (a ^ b))
+                    == 0
+        }
+        _ => false,
     }
 }
 
+fn get_session_id_from_headers(headers: &HeaderMap) -> Option<String> {
+    get_cookie_from_headers(headers, SESSION_COOKIE_NAME)
+}
+
+/// Get the value of the cookie with given name from the Cookie headers of a request.
+/// Malformed headers and cookies are skipped; they may have been set by other applications on the same domain.
+fn get_cookie_from_headers(headers: &HeaderMap, name: &str) -> Option<String> {
+    headers
+        .get_all(axum::http::header::COOKIE)
+        .iter()
+        .filter_map(|cookie_header| cookie_header.to_str().ok())
+        .flat_map(Cookie::split_parse)
+        .filter_map(|cookie| cookie.ok())
+        .find(|cookie| cookie.name() == name)
+        .map(|cookie| cookie.value().to_string())
+}
+
 async fn create_new_session() -> Result<String> {
     let session = Session {
         id: id(),
@@ -171,6 +418,18 @@ async fn create_new_session() -> Result<String> {
     Ok(session.id)
 }
 
+/// Create the cookie holding the CSRF token. It is not HTTP-only, because the app must read it.
+fn create_csrf_cookie<'a>(csrf_token: String) -> Cookie<'a> {
+    let mut expires_on = OffsetDateTime::now_utc();
+    expires_on += Duration::days(SESSION_COOKIE_EXPIRATION_TIME_DAYS);
+    Cookie::build(CSRF_COOKIE_NAME, csrf_token)
+        .path("/")
+        .secure(true)
+        .expires(expires_on)
+        .same_site(SameSite::Strict)
+        .finish()
+}
+
 fn create_sesson_cookie<'a>(session_id: String) -> Cookie<'a> {
     let mut expires_on = OffsetDateTime::now_utc();
     expires_on += Duration::days(SESSION_COOKIE_EXPIRATION_TIME_DAYS);
@@ -182,3 +441,21 @@ fn create_sesson_cookie<'a>(session_id: String) -> Cookie<'a> {
         .same_site(SameSite::Strict)
         .finish()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    #[test]
+    fn malformed_cookies_are_skipped() {
+        let mut headers = HeaderMap::new();
+        headers.append(
+            axum::http::header::COOKIE,
+            HeaderValue::from_static("broken; other=1; session=abc"),
+        );
+
+        assert_eq!(get_cookie_from_headers(&headers, "session").as_deref(), Some("abc"));
+        assert_eq!(get_cookie_from_headers(&headers, "missing"), None);
+    }
+}
@@ -0,0 +1,276 @@
+use crate::database::{self, get_all_documents, COLLECTION_NAME_USERS};
+use crate::model::{DbItem, File, User};
+use crate::storage::{self, StoredFile};
+use anyhow::Result;
+use cookie::time::OffsetDateTime;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::time::Duration;
+
+/// Stored files modified more recently than this are never considered orphaned,
+/// because they may belong to an upload whose file record has not been written yet.
+/// For the same reason, the storage usage of their owners is not checked.
+const ORPHAN_GRACE_PERIOD_SECONDS: i64 = 60 * 60;
+
+/// A record in the 'files' collection.
+pub struct FileRecord {
+    pub user_id: String,
+    pub file_id: String,
+}
+
+/// A user whose recorded storage usage differs from the size of the files actually stored.
+/// Users with uploads in progress are never reported, as their usage already includes files without a record.
+pub struct UsageMismatch {
+    pub user_id: String,
+    pub recorded_bytes: i64,
+    pub actual_bytes: i64,
+}
+
+/// Result of cross-checking file records against storage contents.
+pub struct FsckReport {
+    /// Stored files that no file record refers to.
+    pub orphaned_files: Vec<StoredFile>,
+    /// File records for which no stored file exists.
+    pub dangling_records: Vec<FileRecord>,
+    /// Users whose recorded storage usage is incorrect.
+    pub usage_mismatches: Vec<UsageMismatch>,
+    /// Whether the orphaned files and dangling records have been deleted, and usage corrected.
+    pub repaired: bool,
+}
+
+impl fmt::Display for FsckReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let action = if self.repaired { "fixed" } else { "found" };
+        write!(
+            f,
+            "{} orphaned files, {} dangling file records and {} incorrect usage totals {action}",
+            self.orphaned_files.len(),
+            self.dangling_records.len(),
+            self.usage_mismatches.len()
+        )
+    }
+}
+
+/// Cross-check file records against the contents of storage.
+///
+/// * `repair` - Delete orphaned stored files and dangling file records, and correct users' storage usage.
+pub async fn fsck(repair: bool) -> Result<FsckReport> {
+    // Read records before listing storage; files are stored before their record is written,
+    // so every record read here should have its file present in the listing.
+    let records: Vec<FileRecord> = get_all_documents(File::collection_name())
+        .await?
+        .iter()
+        .map(|document| {
+            Ok(FileRecord {
+                user_id: document.get_str("user_id")?.to_string(),
+                file_id: document.get_str("id")?.to_string(),
+            })
+        })
+        .collect::<Result<_>>()?;
+    let stored_files = storage::list_files().await?;
+
+    let record_keys: HashSet<(&str, &str)> = records
+        .iter()
+        .map(|record| (record.user_id.as_str(), record.file_id.as_str()))
+        .collect();
+    let record_file_ids: HashSet<&str> = records.iter().map(|record| record.file_id.as_str()).collect();
+    let stored_keys: HashSet<(&str, &str)> = stored_files
+        .iter()
+        .filter_map(|file| Some((file.owner_user_id.as_deref()?, file.file_id.as_str())))
+        .collect();
+    let stored_file_ids_without_owner: HashSet<&str> = stored_files
+        .iter()
+        .filter(|file| file.owner_user_id.is_none())
+        .map(|file| file.file_id.as_str())
+        .collect();
+
+    let orphaned_before = OffsetDateTime::now_utc().unix_timestamp() - ORPHAN_GRACE_PERIOD_SECONDS;
+    let orphaned_files: Vec<StoredFile> = stored_files
+        .iter()
+        .filter(|file| file.modified_on < orphaned_before)
+        .filter(|file| match &file.owner_user_id {
+            Some(owner_user_id) => !record_keys.contains(&(owner_user_id.as_str(), file.file_id.as_str())),
+            None => !record_file_ids.contains(file.file_id.as_str()),
+        })
+        .cloned()
+        .collect();
+    let dangling_records: Vec<FileRecord> = records
+        .iter()
+        .filter(|record| {
+            !stored_keys.contains(&(record.user_id.as_str(), record.file_id.as_str()))
+                && !stored_file_ids_without_owner.contains(record.file_id.as_str())
+        })
+        .map(|record| FileRecord {
+            user_id: record.user_id.clone(),
+            file_id: record.file_id.clone(),
+        })
+        .collect();
+
+    // Storage usage of each user, counting only files that are referenced by a record.
+    let user_id_by_file_id: HashMap<&str, &str> = records
+        .iter()
+        .map(|record| (record.file_id.as_str(), record.user_id.as_str()))
+        .collect();
+    let mut actual_bytes_by_user_id: HashMap<&str, i64> = HashMap::new();
+    for file in &stored_files {
+        let user_id = match &file.owner_user_id {
+            Some(owner_user_id) => record_keys
+                .contains(&(owner_user_id.as_str(), file.file_id.as_str()))
+                .then_some(owner_user_id.as_str()),
+            None => user_id_by_file_id.get(file.file_id.as_str()).copied(),
+        };
+        if let Some(user_id) = user_id {
+            *actual_bytes_by_user_id.entry(user_id).or_default() += file.size as i64;
+        }
+    }
+
+    let users: Vec<User> = get_all_documents(COLLECTION_NAME_USERS)
+        .await?
+        .into_iter()
+        .map(|document| Ok(bson::from_document(document)?))
+        .collect::<Result<_>>()?;
+    let user_ids_uploading: HashSet<&str> = stored_files
+        .iter()
+        .filter(|file| file.modified_on >= orphaned_before)
+        .filter_map(|file| file.owner_user_id.as_deref())
+        .collect();
+    let usage_mismatches: Vec<UsageMismatch> = users
+        .iter()
+        .filter(|user| !user_ids_uploading.contains(user.id.as_str()))
+        .map(|user| UsageMismatch {
+            user_id: user.id.clone(),
+            recorded_bytes: user.bytes_stored,
+            actual_bytes: actual_bytes_by_user_id.get(user.id.as_str()).copied().unwrap_or(0),
+        })
+        .filter(|usage| usage.recorded_bytes != usage.actual_bytes)
+        .collect();
+
+    if repair {
+        let mut deleted_bytes_by_user_id: HashMap<&str, i64> = HashMap::new();
+        for file in &orphaned_files {
+            match &file.owner_user_id {
+                Some(owner_user_id) => {
+                    storage::delete_file(&file.file_id, owner_user_id).await?;
+                    *deleted_bytes_by_user_id.entry(owner_user_id).or_default() += file.size as i64;
+                }
+                None => storage::delete_legacy_file(&file.file_id)?,
+            }
+        }
+        for record in &dangling_records {
+            database::delete_item::<File>(&record.file_id, &record.user_id).await?;
+        }
+        // Correct usage by adding the difference rather than setting the total, so that changes made by uploads
+        // and deletions in the meantime are kept. Deleting an orphaned file already subtracted its size.
+        for usage in &usage_mismatches {
+            let deleted_bytes = deleted_bytes_by_user_id
+                .get(usage.user_id.as_str())
+                .copied()
+                .unwrap_or(0);
+            let correction = usage.actual_bytes - (usage.recorded_bytes - deleted_bytes);
+            if correction != 0 {
+                database::add_user_bytes_stored(&usage.user_id, correction).await?;
+            }
+        }
+    }
+
+    Ok(FsckReport {
+        orphaned_files,
+        dangling_records,
+        usage_mismatches,
+        repaired: repair,
+    })
+}
+
+/// Record the total size of stored files of users created before it was recorded, by adding up the sizes
+/// of their stored files. Storage quotas cannot be checked for such users until this is done.
+pub async fn backfill_bytes_stored() -> Result<()> {
+    for user_id in database::get_user_ids_without_bytes_stored().await? {
+        let mut bytes_stored = 0;
+        for file_id in database::get_item_ids::<File>(&user_id).await? {
+            bytes_stored += storage::get_file_size(&file_id, &user_id).await?.unwrap_or(0) as i64;
+        }
+
+        database::set_user_bytes_stored(&user_id, bytes_stored).await?;
+        tracing::info!("Recorded storage usage of user '{user_id}': {bytes_stored} bytes");
+    }
+
+    Ok(())
+}
+
+/// Move legacy files of the disk storage provider, which are stored in a single directory for all users,
+/// into the directory of the users owning them. The owners are looked up from the file records;
+/// a file with records of several users is copied to each of them.
+///
+/// Files without a record are left in place, and can be deleted using 'fsck --repair'. Files that cannot be
+/// migrated, e.g. because an owner already has a file with the same name, are reported and left in place too.
+pub async fn migrate_legacy_files() -> Result<()> {
+    let legacy_files = storage::list_legacy_files()?;
+    if legacy_files.is_empty() {
+        return Ok(());
+    }
+
+    let mut user_ids_by_file_id: HashMap<String, Vec<String>> = HashMap::new();
+    for document in get_all_documents(File::collection_name()).await? {
+        user_ids_by_file_id
+            .entry(document.get_str("id")?.to_string())
+            .or_default()
+            .push(document.get_str("user_id")?.to_string());
+    }
+
+    let mut migrated = 0;
+    let mut skipped = 0;
+    for file in &legacy_files {
+        let Some(user_ids) = user_ids_by_file_id.get(&file.file_id) else {
+            tracing::warn!("File '{}' has no record", file.file_id);
+            skipped += 1;
+            continue;
+        };
+
+        match migrate_legacy_file(&file.file_id, user_ids).await {
+            Ok(_) => migrated += 1,
+            Err(error) => {
+                tracing::warn!("File '{}' could not be migrated: {error:?}", file.file_id);
+                skipped += 1;
+            }
+        }
+    }
+
+    if migrated > 0 || skipped > 0 {
+        tracing::info!("{migrated} legacy files migrated, {skipped} left in place");
+    }
+    Ok(())
+}
+
+/// Copy a legacy file to each of its owners, then delete it. Owners that already have the file,
+/// e.g. from an earlier attempt that failed halfway, are skipped.
+async fn migrate_legacy_file(file_id: &str, owner_user_ids: &[String]) -> Result<()> {
+    for user_id in owner_user_ids {
+        if storage::get_file_size(file_id, user_id).await?.is_none() {
+            storage::copy_legacy_file(file_id, user_id)?;
+        }
+    }
+
+    storage::delete_legacy_file(file_id)
+}
+
+/// Run fsck periodically in the background, if enabled in settings.
+pub fn spawn_scheduled_fsck() {
+    let interval_hours = crate::SETTINGS.maintenance.fsck_interval_hours;
+    let repair = crate::SETTINGS.maintenance.fsck_repair;
+
+    if interval_hours > 0 {
+        tokio::spawn(async move {
+            let period = Duration::from_secs(interval_hours * 60 * 60);
+            let mut interval = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+            loop {
+                interval.tick().await;
+                match fsck(repair).await {
+                    Ok(report) => tracing::info!("Scheduled fsck: {report}"),
+                    Err(error) => tracing::error!("Scheduled fsck failed: {error:?}"),
+                }
+            }
+        });
+    }
+}
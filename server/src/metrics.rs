@@ -0,0 +1,136 @@
+use crate::database;
+use axum::{
+    extract::MatchedPath,
+    http::{header, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use lazy_static::lazy_static;
+use mongodb::event::command::{CommandEventHandler, CommandFailedEvent, CommandSucceededEvent};
+use prometheus::{
+    register_histogram_vec, register_int_counter_vec, register_int_gauge_vec, Encoder, HistogramVec, IntCounterVec,
+    IntGaugeVec, TextEncoder,
+};
+use std::time::Instant;
+
+lazy_static! {
+    static ref HTTP_REQUESTS: IntCounterVec = register_int_counter_vec!(
+        "upholi_http_requests_total",
+        "Number of handled API requests",
+        &["method", "route", "status"]
+    )
+    .unwrap();
+    static ref HTTP_REQUEST_DURATION: HistogramVec = register_histogram_vec!(
+        "upholi_http_request_duration_seconds",
+        "Time taken to handle API requests",
+        &["method", "route"]
+    )
+    .unwrap();
+    static ref STORAGE_BYTES: IntCounterVec = register_int_counter_vec!(
+        "upholi_storage_bytes_total",
+        "Number of bytes written to (in) and read from (out) storage",
+        &["provider", "direction"]
+    )
+    .unwrap();
+    static ref STORAGE_OPERATION_DURATION: HistogramVec = register_histogram_vec!(
+        "upholi_storage_operation_duration_seconds",
+        "Time taken by storage operations",
+        &["provider", "operation"]
+    )
+    .unwrap();
+    static ref DATABASE_OPERATION_DURATION: HistogramVec = register_histogram_vec!(
+        "upholi_database_operation_duration_seconds",
+        "Time taken by database commands",
+        &["operation", "outcome"]
+    )
+    .unwrap();
+    static ref SESSIONS: IntGaugeVec = register_int_gauge_vec!(
+        "upholi_sessions",
+        "Number of sessions, by whether a user is logged in to them",
+        &["authenticated"]
+    )
+    .unwrap();
+}
+
+/// Handler exposing all metrics in the Prometheus text format.
+pub async fn get_metrics() -> Response {
+    if let Err(error) = update_session_counts().await {
+        tracing::error!("Failed to count sessions: {error:?}");
+    }
+
+    let mut buffer = vec![];
+    match TextEncoder::new().encode(&prometheus::gather(), &mut buffer) {
+        Ok(_) => ([(header::CONTENT_TYPE, prometheus::TEXT_FORMAT)], buffer).into_response(),
+        Err(error) => {
+            tracing::error!("Failed to encode metrics: {error:?}");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+async fn update_session_counts() -> anyhow::Result<()> {
+    let total = database::count_documents(database::COLLECTION_NAME_SESSIONS).await?;
+    let authenticated = database::count_authenticated_sessions().await?;
+
+    SESSIONS.with_label_values(&["true"]).set(authenticated as i64);
+    SESSIONS
+        .with_label_values(&["false"])
+        .set(total.saturating_sub(authenticated) as i64);
+    Ok(())
+}
+
+/// Middleware counting requests and measuring their duration, per route.
+pub async fn track_requests<B>(request: Request<B>, next: Next<B>) -> Response {
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_default();
+    let started = Instant::now();
+
+    let response = next.run(request).await;
+
+    HTTP_REQUEST_DURATION
+        .with_label_values(&[&method, &route])
+        .observe(started.elapsed().as_secs_f64());
+    HTTP_REQUESTS
+        .with_label_values(&[&method, &route, response.status().as_str()])
+        .inc();
+
+    response
+}
+
+/// Count bytes written to storage.
+pub fn add_storage_bytes_in(provider: &str, bytes: usize) {
+    STORAGE_BYTES.with_label_values(&[provider, "in"]).inc_by(bytes as u64);
+}
+
+/// Count bytes read from storage.
+pub fn add_storage_bytes_out(provider: &str, bytes: usize) {
+    STORAGE_BYTES.with_label_values(&[provider, "out"]).inc_by(bytes as u64);
+}
+
+/// Start measuring a storage operation. The duration is recorded when the returned timer is dropped.
+pub fn time_storage_operation(provider: &str, operation: &str) -> prometheus::HistogramTimer {
+    STORAGE_OPERATION_DURATION
+        .with_label_values(&[provider, operation])
+        .start_timer()
+}
+
+/// Records the duration of each command sent to the database.
+pub struct DatabaseMetrics;
+
+impl CommandEventHandler for DatabaseMetrics {
+    fn handle_command_succeeded_event(&self, event: CommandSucceededEvent) {
+        DATABASE_OPERATION_DURATION
+            .with_label_values(&[&event.command_name, "success"])
+            .observe(event.duration.as_secs_f64());
+    }
+
+    fn handle_command_failed_event(&self, event: CommandFailedEvent) {
+        DATABASE_OPERATION_DURATION
+            .with_label_values(&[&event.command_name, "failure"])
+            .observe(event.duration.as_secs_f64());
+    }
+}
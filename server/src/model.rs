@@ -1,4 +1,5 @@
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use upholi_lib::http::TokenScope;
 
 pub trait DbItem: Serialize + DeserializeOwned + Sync + Send + Unpin {
     /// Get the name of the collection this item will be stored in in the database.
@@ -10,6 +11,67 @@ pub struct User {
     pub id: String,
     pub username: String,
     pub password_phc: String,
+    /// Total size in bytes of all files stored by this user.
+    #[serde(default)]
+    pub bytes_stored: i64,
+    /// Storage quota in bytes for this user. Overrides the default quota from settings.
+    #[serde(default)]
+    pub quota_bytes: Option<u64>,
+    /// Whether this user can manage other users.
+    #[serde(default)]
+    pub is_admin: bool,
+    /// Disabled users cannot log in.
+    #[serde(default)]
+    pub disabled: bool,
+    /// Set when an administrator reset this user's password. Data encrypted with keys derived
+    /// from the previous password can no longer be read, so new keys must be created on next login.
+    #[serde(default)]
+    pub password_reset: bool,
+}
+
+/// Progress of deleting a user and all of their data, which continues after a restart until it is done.
+#[derive(Serialize, Deserialize)]
+pub struct Purge {
+    pub user_id: String,
+    /// Unix timestamp of when the deletion was requested.
+    pub started_on: i64,
+    /// Number of files the user had when the deletion was requested.
+    pub files_total: u64,
+    pub files_deleted: u64,
+}
+
+/// Items and files of a user that were moved to the trash. They are deleted once the retention period has passed.
+#[derive(Serialize, Deserialize)]
+pub struct TrashEntry {
+    pub id: String,
+    pub user_id: String,
+    pub item_ids: Vec<String>,
+    pub file_ids: Vec<String>,
+    /// Unix timestamp of when the entry was moved to the trash.
+    pub trashed_on: i64,
+}
+
+/// A single-use code that allows registering a user when open registration is disabled.
+#[derive(Serialize, Deserialize)]
+pub struct Invite {
+    pub code: String,
+    /// Unix timestamp of when the invite was created.
+    pub created_on: i64,
+}
+
+/// A personal access token, for clients other than the web app.
+#[derive(Serialize, Deserialize)]
+pub struct ApiToken {
+    pub id: String,
+    pub user_id: String,
+    pub name: String,
+    /// SHA-256 hash of the token, in hex. The token itself is not stored.
+    pub token_hash: String,
+    pub scopes: Vec<TokenScope>,
+    /// Unix timestamp of when the token was created.
+    pub created_on: i64,
+    /// Unix timestamp of when the token was last used, if ever.
+    pub last_used_on: Option<i64>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -40,7 +102,6 @@ impl DbItem for EncryptedData {
 
 #[derive(Serialize, Deserialize)]
 pub struct File {
-    pub file_id: String,
     /// Path, container ID/name, something that indicates where this file is stores.
     pub container: String,
 }
@@ -0,0 +1,100 @@
+use crate::database::{self, get_item_ids};
+use crate::model::{File, Purge};
+use crate::storage;
+use anyhow::Result;
+use cookie::time::OffsetDateTime;
+use lazy_static::lazy_static;
+use std::collections::HashSet;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Number of files deleted between progress updates.
+const PURGE_BATCH_SIZE: usize = 100;
+/// Interval at which unfinished purges, e.g. ones that failed, are started again.
+const RETRY_INTERVAL_SECONDS: u64 = 10 * 60;
+
+lazy_static! {
+    /// IDs of users whose purge is currently running, so the same purge is never run twice at once.
+    static ref RUNNING_PURGES: Mutex<HashSet<String>> = Mutex::new(HashSet::new());
+}
+
+/// Start deleting a user and all of their data in the background.
+/// The user is disabled right away, which rejects their sessions for everything except following
+/// the progress of the deletion. Returns the existing purge if one was already started.
+pub async fn start_user_purge(user_id: &str) -> Result<Purge> {
+    if let Some(purge) = database::get_purge(user_id).await? {
+        return Ok(purge);
+    }
+
+    database::set_user_disabled(user_id, true).await?;
+    let purge = Purge {
+        user_id: user_id.to_string(),
+        started_on: OffsetDateTime::now_utc().unix_timestamp(),
+        files_total: get_item_ids::<File>(user_id).await?.len() as u64,
+        files_deleted: 0,
+    };
+    database::insert_purge(&purge).await?;
+
+    spawn_user_purge(user_id.to_string());
+    Ok(purge)
+}
+
+/// Periodically continue deleting users whose deletion was interrupted by a restart or failed,
+/// starting right away.
+pub fn spawn_scheduled_user_purges() {
+    tokio::spawn(async {
+        let mut interval = tokio::time::interval(Duration::from_secs(RETRY_INTERVAL_SECONDS));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+        loop {
+            interval.tick().await;
+            match database::get_purges().await {
+                Ok(purges) => {
+                    for purge in purges {
+                        spawn_user_purge(purge.user_id);
+                    }
+                }
+                Err(error) => tracing::error!("Getting unfinished user deletions failed: {error:?}"),
+            }
+        }
+    });
+}
+
+/// Run the purge of a user in the background, unless it is already running.
+fn spawn_user_purge(user_id: String) {
+    if !RUNNING_PURGES.lock().unwrap().insert(user_id.clone()) {
+        return;
+    }
+
+    tokio::spawn(async move {
+        tracing::info!("Deleting user '{user_id}'");
+        match purge_user(&user_id).await {
+            Ok(_) => tracing::info!("User '{user_id}' deleted"),
+            Err(error) => tracing::error!("Deleting user '{user_id}' failed, retrying later: {error:?}"),
+        }
+        RUNNING_PURGES.lock().unwrap().remove(&user_id);
+    });
+}
+
+/// Delete all stored files and file records of a user in batches, then the rest of their data.
+/// Every step can be repeated, so an interrupted purge can simply be run again.
+async fn purge_user(user_id: &str) -> Result<()> {
+    let Some(purge) = database::get_purge(user_id).await? else {
+        return Ok(());
+    };
+    database::set_user_disabled(user_id, true).await?;
+
+    // Files deleted by an earlier run no longer exist, so progress is derived from the files that remain
+    let file_ids = get_item_ids::<File>(user_id).await?;
+    let mut files_remaining = file_ids.len() as u64;
+    for file_ids in file_ids.chunks(PURGE_BATCH_SIZE) {
+        storage::purge_files(file_ids, user_id).await?;
+        database::delete_items::<File>(file_ids, user_id).await?;
+        files_remaining -= file_ids.len() as u64;
+        database::set_purge_files_deleted(user_id, purge.files_total.saturating_sub(files_remaining)).await?;
+    }
+
+    storage::delete_storage_for_user(user_id).await?;
+    database::delete_user(user_id).await?;
+    database::delete_purge(user_id).await
+}
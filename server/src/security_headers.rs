@@ -0,0 +1,138 @@
+use crate::SETTINGS;
+use axum::{
+    http::{header, HeaderValue, Request},
+    middleware::Next,
+    response::Response,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use lazy_static::lazy_static;
+use sha2::{Digest, Sha256};
+
+lazy_static! {
+    /// Content Security Policy of all responses.
+    ///
+    /// Scripts may only be loaded from this origin, except for the inline scripts of index.html, which are
+    /// allowed by their hash. Instantiating the app's WASM module requires 'wasm-unsafe-eval'.
+    /// Inline styles are allowed, because components set style attributes; they cannot run code.
+    static ref CONTENT_SECURITY_POLICY: HeaderValue = {
+        let script_hashes: String = get_index_inline_script_hashes()
+            .iter()
+            .map(|hash| format!(" 'sha256-{hash}'"))
+            .collect();
+
+        let policy = [
+            "default-src 'self'".to_string(),
+            format!("script-src 'self' 'wasm-unsafe-eval'{script_hashes}"),
+            "style-src 'self' 'unsafe-inline'".to_string(),
+            "img-src 'self' data: blob:".to_string(),
+            "connect-src 'self'".to_string(),
+            "object-src 'none'".to_string(),
+            "base-uri 'none'".to_string(),
+            "form-action 'self'".to_string(),
+            "frame-ancestors 'none'".to_string(),
+        ]
+        .join("; ");
+
+        HeaderValue::from_str(&policy).expect("Invalid Content Security Policy")
+    };
+}
+
+const PERMISSIONS_POLICY: &str = "camera=(), microphone=(), geolocation=(), payment=(), usb=(), interest-cohort=()";
+const STRICT_TRANSPORT_SECURITY: &str = "max-age=31536000";
+
+/// Middleware that adds security headers to all responses.
+pub async fn security_headers_layer<B>(req: Request<B>, next: Next<B>) -> Response {
+    let mut response = next.run(req).await;
+    let headers = response.headers_mut();
+
+    headers.insert(header::CONTENT_SECURITY_POLICY, CONTENT_SECURITY_POLICY.clone());
+    headers.insert(header::X_CONTENT_TYPE_OPTIONS, HeaderValue::from_static("nosniff"));
+    headers.insert(header::X_FRAME_OPTIONS, HeaderValue::from_static("DENY"));
+    headers.insert(header::REFERRER_POLICY, HeaderValue::from_static("no-referrer"));
+    headers.insert("permissions-policy", HeaderValue::from_static(PERMISSIONS_POLICY));
+    headers.insert("cross-origin-opener-policy", HeaderValue::from_static("same-origin"));
+    if crate::tls::is_enabled() {
+        headers.insert(
+            header::STRICT_TRANSPORT_SECURITY,
+            HeaderValue::from_static(STRICT_TRANSPORT_SECURITY),
+        );
+    }
+
+    response
+}
+
+/// Get the base64 encoded SHA-256 hashes of the inline scripts in index.html, such as the loader that Trunk adds.
+fn get_index_inline_script_hashes() -> Vec<String> {
+    let path = format!("{}/index.html", SETTINGS.server.wwwroot_path);
+    match std::fs::read_to_string(&path) {
+        Ok(html) => get_inline_scripts(&html)
+            .iter()
+            .map(|script| STANDARD.encode(Sha256::digest(script.as_bytes())))
+            .collect(),
+        Err(error) => {
+            tracing::warn!("Failed to read '{path}', inline scripts will be blocked: {error}");
+            vec![]
+        }
+    }
+}
+
+/// Get the contents of all script elements without a src attribute.
+fn get_inline_scripts(html: &str) -> Vec<&str> {
+    let mut scripts = vec![];
+    let mut remaining = html;
+
+    while let Some(start) = remaining.find("<script") {
+        remaining = &remaining[start..];
+        let Some(tag_end) = remaining.find('>') else {
+            break;
+        };
+        let Some(end) = remaining.find("</script>") else {
+            break;
+        };
+
+        let tag = &remaining[..tag_end];
+        if !tag.contains("src=") && tag_end < end {
+            scripts.push(&remaining[tag_end + 1..end]);
+        }
+        remaining = &remaining[end + "</script>".len()..];
+    }
+
+    scripts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_inline_scripts() {
+        assert!(get_inline_scripts("<html><head><title>Upholi</title></head><body></body></html>").is_empty());
+    }
+
+    #[test]
+    fn single_inline_script() {
+        let html = "<head><script>init();</script></head>";
+
+        assert_eq!(get_inline_scripts(html), vec!["init();"]);
+    }
+
+    #[test]
+    fn multiple_inline_scripts() {
+        let html = r#"<head><script>first();</script><link rel="stylesheet" href="main.css"></head>
+            <body><script type="module">second();</script></body>"#;
+
+        assert_eq!(get_inline_scripts(html), vec!["first();", "second();"]);
+    }
+
+    #[test]
+    fn scripts_with_src_are_skipped() {
+        let html = r#"<script src="/app.js"></script><script>init();</script><script defer src="/other.js"></script>"#;
+
+        assert_eq!(get_inline_scripts(html), vec!["init();"]);
+    }
+
+    #[test]
+    fn unterminated_script_is_ignored() {
+        assert!(get_inline_scripts("<script>init();").is_empty());
+    }
+}
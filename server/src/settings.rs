@@ -1,27 +1,52 @@
 use crate::Result;
+use anyhow::{anyhow, Context};
 use config::{Config, File};
 use serde::Deserialize;
 use std::env::var;
+use std::net::SocketAddr;
+use std::path::Path;
 
 const ENV_VAR_SERVER_ADDRESS: &str = "UPHOLI_SERVER_ADDRESS";
 const ENV_VAR_SERVER_WWWROOT_PATH: &str = "UPHOLI_SERVER_WWWROOT_PATH";
+const ENV_VAR_SERVER_ALLOWREGISTRATION: &str = "UPHOLI_SERVER_ALLOWREGISTRATION";
+const ENV_VAR_SERVER_METRICSADDRESS: &str = "UPHOLI_SERVER_METRICSADDRESS";
+const ENV_VAR_SERVER_TLSCERTIFICATEPATH: &str = "UPHOLI_SERVER_TLSCERTIFICATEPATH";
+const ENV_VAR_SERVER_TLSKEYPATH: &str = "UPHOLI_SERVER_TLSKEYPATH";
+const ENV_VAR_SERVER_HTTPREDIRECTADDRESS: &str = "UPHOLI_SERVER_HTTPREDIRECTADDRESS";
 const ENV_VAR_DATABASE_CONNECTIONSTRING: &str = "UPHOLI_DATABASE_CONNECTIONSTRING";
 const ENV_VAR_STORAGE_PROVIDER: &str = "UPHOLI_STORAGE_PROVIDER";
 const ENV_VAR_STORAGE_DIRECTORYPHOTOS: &str = "UPHOLI_STORAGE_DIRECTORYPHOTOS";
 const ENV_VAR_STORAGE_AZURESTORAGEACCOUNTNAME: &str = "UPHOLI_STORAGE_AZURESTORAGEACCOUNTNAME";
 const ENV_VAR_STORAGE_AZURESTORAGEACCOUNTKEY: &str = "UPHOLI_STORAGE_AZURESTORAGEACCOUNTKEY";
+const ENV_VAR_STORAGE_DEFAULTQUOTABYTES: &str = "UPHOLI_STORAGE_DEFAULTQUOTABYTES";
+const ENV_VAR_STORAGE_TRASHRETENTIONDAYS: &str = "UPHOLI_STORAGE_TRASHRETENTIONDAYS";
+const ENV_VAR_MAINTENANCE_FSCKINTERVALHOURS: &str = "UPHOLI_MAINTENANCE_FSCKINTERVALHOURS";
+const ENV_VAR_MAINTENANCE_FSCKREPAIR: &str = "UPHOLI_MAINTENANCE_FSCKREPAIR";
+const ENV_VAR_LOGGING_LEVEL: &str = "UPHOLI_LOGGING_LEVEL";
+const ENV_VAR_LOGGING_FORMAT: &str = "UPHOLI_LOGGING_FORMAT";
 
 #[derive(Debug, Deserialize)]
 pub enum StorageProvider {
     Disk,
     Azure,
 }
+
+#[derive(Debug, Deserialize)]
+pub enum LogFormat {
+    /// Human-readable, multi-line output
+    Pretty,
+    /// One JSON object per line
+    Json,
+}
+
 /// Application settings
 #[derive(Debug, Deserialize)]
 pub struct Settings {
     pub server: Server,
     pub database: Database,
     pub storage: Storage,
+    pub maintenance: Maintenance,
+    pub logging: Logging,
 }
 
 /// Web server settings
@@ -29,6 +54,16 @@ pub struct Settings {
 pub struct Server {
     pub address: String,
     pub wwwroot_path: String,
+    /// Whether anyone can register a user. When disabled, registering requires an invite code.
+    pub allow_registration: bool,
+    /// Address to serve metrics on, separately from the API. Empty disables metrics.
+    pub metrics_address: String,
+    /// Path of a PEM file with the certificate chain to serve HTTPS with. Empty serves plain HTTP.
+    pub tls_certificate_path: String,
+    /// Path of a PEM file with the private key of the certificate.
+    pub tls_key_path: String,
+    /// Address to listen on for plain HTTP, redirecting all requests to HTTPS. Empty disables it.
+    pub http_redirect_address: String,
 }
 
 /// Database settings
@@ -44,6 +79,27 @@ pub struct Storage {
     pub directory_photos: String,
     pub azure_storage_account_name: String,
     pub azure_storage_account_key: String,
+    /// Storage quota in bytes that applies to users without a quota of their own. 0 means unlimited.
+    pub default_quota_bytes: u64,
+    /// Number of days after which photos in the trash are deleted. 0 keeps them until the trash is emptied.
+    pub trash_retention_days: u64,
+}
+
+/// Background maintenance settings
+#[derive(Debug, Deserialize)]
+pub struct Maintenance {
+    /// Interval in hours at which file records are cross-checked against storage. 0 disables it.
+    pub fsck_interval_hours: u64,
+    /// Whether the scheduled check deletes the inconsistencies it finds, or only reports them.
+    pub fsck_repair: bool,
+}
+
+/// Logging settings
+#[derive(Debug, Deserialize)]
+pub struct Logging {
+    /// Minimum level of log events, or a filter directive such as "info,upholi=debug".
+    pub level: String,
+    pub format: LogFormat,
 }
 
 impl Default for Settings {
@@ -57,9 +113,73 @@ impl Settings {
     ///
     /// # Panics
     ///
-    /// Panics if anything went wrong
+    /// Panics if anything went wrong. Call `load` at startup to get an error instead.
     pub fn new() -> Self {
-        Self::try_new().unwrap()
+        Self::load().unwrap()
+    }
+
+    /// Get and validate all application settings
+    pub fn load() -> Result<Self> {
+        let settings = Self::try_new()?;
+        settings.validate()?;
+        Ok(settings)
+    }
+
+    fn validate(&self) -> Result<()> {
+        self.server
+            .address
+            .parse::<SocketAddr>()
+            .with_context(|| format!("Invalid server address '{}'", self.server.address))?;
+        if !self.server.metrics_address.is_empty() {
+            self.server
+                .metrics_address
+                .parse::<SocketAddr>()
+                .with_context(|| format!("Invalid metrics address '{}'", self.server.metrics_address))?;
+        }
+
+        if self.server.tls_certificate_path.is_empty() != self.server.tls_key_path.is_empty() {
+            return Err(anyhow!(
+                "Both a TLS certificate path and key path are required for HTTPS"
+            ));
+        }
+        for path in [&self.server.tls_certificate_path, &self.server.tls_key_path] {
+            if !path.is_empty() && !Path::new(path).is_file() {
+                return Err(anyhow!("TLS file '{path}' does not exist"));
+            }
+        }
+        if !self.server.http_redirect_address.is_empty() {
+            if self.server.tls_certificate_path.is_empty() {
+                return Err(anyhow!("Redirecting HTTP to HTTPS requires HTTPS to be configured"));
+            }
+            self.server
+                .http_redirect_address
+                .parse::<SocketAddr>()
+                .with_context(|| format!("Invalid HTTP redirect address '{}'", self.server.http_redirect_address))?;
+        }
+
+        if self.database.connection_string.is_empty() {
+            return Err(anyhow!("No database connection string configured"));
+        }
+
+        match self.storage.provider {
+            StorageProvider::Disk => {
+                if !Path::new(&self.storage.directory_photos).is_dir() {
+                    return Err(anyhow!(
+                        "Photos directory '{}' does not exist",
+                        self.storage.directory_photos
+                    ));
+                }
+            }
+            StorageProvider::Azure => {
+                if self.storage.azure_storage_account_name.is_empty()
+                    || self.storage.azure_storage_account_key.is_empty()
+                {
+                    return Err(anyhow!("Azure storage account name and key are required"));
+                }
+            }
+        }
+
+        Ok(())
     }
 
     fn try_new() -> Result<Self> {
@@ -67,6 +187,17 @@ impl Settings {
             .add_source(File::with_name("config/default"))
             .set_override_option("server.address", var(ENV_VAR_SERVER_ADDRESS).ok())?
             .set_override_option("server.wwwroot_path", var(ENV_VAR_SERVER_WWWROOT_PATH).ok())?
+            .set_override_option("server.allow_registration", var(ENV_VAR_SERVER_ALLOWREGISTRATION).ok())?
+            .set_override_option("server.metrics_address", var(ENV_VAR_SERVER_METRICSADDRESS).ok())?
+            .set_override_option(
+                "server.tls_certificate_path",
+                var(ENV_VAR_SERVER_TLSCERTIFICATEPATH).ok(),
+            )?
+            .set_override_option("server.tls_key_path", var(ENV_VAR_SERVER_TLSKEYPATH).ok())?
+            .set_override_option(
+                "server.http_redirect_address",
+                var(ENV_VAR_SERVER_HTTPREDIRECTADDRESS).ok(),
+            )?
             .set_override_option(
                 "database.connection_string",
                 var(ENV_VAR_DATABASE_CONNECTIONSTRING).ok(),
@@ -80,7 +211,22 @@ impl Settings {
             .set_override_option(
                 "storage.azure_storage_account_key",
                 var(ENV_VAR_STORAGE_AZURESTORAGEACCOUNTKEY).ok(),
-            )?;
+            )?
+            .set_override_option(
+                "storage.default_quota_bytes",
+                var(ENV_VAR_STORAGE_DEFAULTQUOTABYTES).ok(),
+            )?
+            .set_override_option(
+                "storage.trash_retention_days",
+                var(ENV_VAR_STORAGE_TRASHRETENTIONDAYS).ok(),
+            )?
+            .set_override_option(
+                "maintenance.fsck_interval_hours",
+                var(ENV_VAR_MAINTENANCE_FSCKINTERVALHOURS).ok(),
+            )?
+            .set_override_option("maintenance.fsck_repair", var(ENV_VAR_MAINTENANCE_FSCKREPAIR).ok())?
+            .set_override_option("logging.level", var(ENV_VAR_LOGGING_LEVEL).ok())?
+            .set_override_option("logging.format", var(ENV_VAR_LOGGING_FORMAT).ok())?;
 
         Ok(builder.build()?.try_deserialize::<Self>()?)
     }
@@ -1,12 +1,21 @@
 use anyhow::{anyhow, Result};
+use azure_core::StatusCode;
 use azure_storage::StorageCredentials;
 use azure_storage_blobs::prelude::{BlobClient, BlobServiceClient, ContainerClient};
 use futures::StreamExt;
+use std::collections::HashSet;
+
+use super::StoredFile;
 
 pub struct AzureStorageProvider {
     blob_client: BlobServiceClient,
 }
 
+/// Container used to check that the account is writable. User IDs never contain a hyphen, so it cannot clash
+/// with a user's container. Never listed.
+const PROBE_CONTAINER_NAME: &str = "upholi-probe";
+const PROBE_BLOB_NAME: &str = "probe";
+
 impl AzureStorageProvider {
     pub fn new() -> AzureStorageProvider {
         let account_name = &crate::SETTINGS.storage.azure_storage_account_name;
@@ -34,12 +43,58 @@ impl AzureStorageProvider {
         Ok(Some(bytes))
     }
 
+    pub async fn get_file_size(&self, container: &str, name: &str) -> Result<Option<u64>> {
+        let blob = self.get_blob_client(container, name);
+        match blob.get_properties().await {
+            Ok(response) => Ok(Some(response.blob.properties.content_length)),
+            Err(error) => match error.as_http_error() {
+                Some(http_error) if http_error.status() == StatusCode::NotFound => Ok(None),
+                _ => Err(anyhow!("{error:?}")),
+            },
+        }
+    }
+
     pub async fn delete_file(&self, container: &str, name: &str) -> Result<()> {
         let blob = self.get_blob_client(container, name);
         blob.delete().into_future().await?;
         Ok(())
     }
 
+    /// Check that blobs can be written to the account, by writing and deleting a probe blob.
+    pub async fn check_writable(&self) -> Result<()> {
+        self.create_container_if_not_exists(PROBE_CONTAINER_NAME).await?;
+        self.store_file(PROBE_CONTAINER_NAME, PROBE_BLOB_NAME, &[]).await?;
+        self.delete_file(PROBE_CONTAINER_NAME, PROBE_BLOB_NAME).await
+    }
+
+    /// List all blobs in the containers of given users.
+    /// Other containers in the storage account may not belong to upholi, so they are never listed.
+    pub async fn list_files(&self, user_ids: &HashSet<String>) -> Result<Vec<StoredFile>> {
+        let mut files = vec![];
+
+        let mut containers = self.blob_client.list_containers().into_stream();
+        while let Some(page) = containers.next().await {
+            for container in page?.containers {
+                if !user_ids.contains(&container.name) {
+                    continue;
+                }
+                let mut blobs = self.get_container_client(&container.name).list_blobs().into_stream();
+                while let Some(page) = blobs.next().await {
+                    for blob in page?.blobs.blobs() {
+                        files.push(StoredFile {
+                            owner_user_id: Some(container.name.clone()),
+                            file_id: blob.name.clone(),
+                            size: blob.properties.content_length,
+                            modified_on: blob.properties.last_modified.unix_timestamp(),
+                        });
+                    }
+                }
+            }
+        }
+
+        Ok(files)
+    }
+
     fn get_blob_client(&self, container_name: &str, blob_name: &str) -> BlobClient {
         self.get_container_client(container_name).blob_client(blob_name)
     }
@@ -59,6 +114,17 @@ impl AzureStorageProvider {
         }
     }
 
+    /// Delete container with given name and all blobs in it, if it exists.
+    pub async fn delete_container_if_exists(&self, container_name: &str) -> Result<()> {
+        let container_exists = self.container_exists(container_name).await?;
+        if container_exists {
+            let container_client = self.get_container_client(container_name);
+            container_client.delete().await.map_err(|error| anyhow!("{error:?}"))
+        } else {
+            Ok(())
+        }
+    }
+
     /// Create container with given name, if it doesn't already exist.
     pub async fn container_exists(&self, container_name: &str) -> Result<bool> {
         let mut stream = self
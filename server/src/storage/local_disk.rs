@@ -1,51 +1,192 @@
+use super::StoredFile;
 use anyhow::{anyhow, Result};
-use std::path::Path;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
 use std::{fs::File, io::prelude::*};
 
+/// Stores files as '{directory_photos}/{container}/{shard}/{name}', where the container is the owner's user ID
+/// and the shard is the first byte of the SHA-256 hash of the name, in hex.
+/// This keeps users' files apart, and the number of files per directory manageable.
+///
+/// Earlier versions stored all files directly in '{directory_photos}'. These legacy files are still listed,
+/// without an owner, until they are copied using `copy_legacy_file`.
 pub struct LocalDiskStorageProvider {}
 
+/// Name of the file written to check that the base directory is writable. Never listed.
+const PROBE_FILE_NAME: &str = ".upholi-probe";
+
 impl LocalDiskStorageProvider {
     pub fn new() -> LocalDiskStorageProvider {
         LocalDiskStorageProvider {}
     }
 
-    pub fn store_file(&self, file_name: &str, file_bytes: &[u8]) -> Result<()> {
-        let photo_absolute_path = Self::get_absolute_photo_path(file_name)?;
+    pub fn store_file(&self, container: &str, name: &str, file_bytes: &[u8]) -> Result<()> {
+        let absolute_path = Self::get_absolute_file_path(container, name)?;
+        if let Some(directory) = absolute_path.parent() {
+            std::fs::create_dir_all(directory)?;
+        }
 
-        let mut file = File::create(photo_absolute_path)?;
+        let mut file = File::create(absolute_path)?;
 
         file.write_all(file_bytes)?;
 
         Ok(())
     }
 
-    pub fn get_file(&self, file_id: &str) -> Result<Option<Vec<u8>>> {
-        let photo_relative_path = file_id;
-        let photo_absolute_path = Self::get_absolute_photo_path(photo_relative_path)?;
-        let mut file = File::open(photo_absolute_path)?;
+    pub fn get_file(&self, container: &str, name: &str) -> Result<Option<Vec<u8>>> {
+        let absolute_path = Self::get_absolute_file_path(container, name)?;
+        let mut file = match File::open(absolute_path) {
+            Ok(file) => file,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(error.into()),
+        };
 
         let mut file_bytes: Vec<u8> = Vec::new();
         file.read_to_end(&mut file_bytes)?;
         Ok(Some(file_bytes))
     }
 
-    pub fn delete_file(&self, file_id: &str) -> Result<()> {
-        let photo_relative_path = file_id;
-        let absolute_path = Self::get_absolute_photo_path(photo_relative_path)?;
+    pub fn get_file_size(&self, container: &str, name: &str) -> Result<Option<u64>> {
+        let absolute_path = Self::get_absolute_file_path(container, name)?;
+        match std::fs::metadata(absolute_path) {
+            Ok(metadata) => Ok(Some(metadata.len())),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    pub fn delete_file(&self, container: &str, name: &str) -> Result<()> {
+        let absolute_path = Self::get_absolute_file_path(container, name)?;
         std::fs::remove_file(absolute_path)?;
         Ok(())
     }
 
-    /// Returns the absolute path for given relative photo path
-    fn get_absolute_photo_path(photo_relative_path: &str) -> Result<String> {
+    /// Delete the directory of a container, including all files in it, if it exists.
+    pub fn delete_container_if_exists(&self, container: &str) -> Result<()> {
+        Self::validate_name(container)?;
+        let container_path = Path::new(Self::get_photos_base_path()?).join(container);
+        match std::fs::remove_dir_all(container_path) {
+            Ok(()) => Ok(()),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    /// Check that files can be written to the base directory, by writing and deleting a probe file.
+    pub fn check_writable(&self) -> Result<()> {
+        let probe_path = Path::new(Self::get_photos_base_path()?).join(PROBE_FILE_NAME);
+        std::fs::write(&probe_path, [])?;
+        std::fs::remove_file(&probe_path)?;
+        Ok(())
+    }
+
+    /// List all files of all containers, and any legacy files that have not been migrated yet.
+    pub fn list_files(&self) -> Result<Vec<StoredFile>> {
+        let base_path = Self::get_photos_base_path()?;
+        let mut files = vec![];
+
+        for entry in std::fs::read_dir(base_path)? {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            if entry.file_name() == PROBE_FILE_NAME {
+                continue;
+            } else if metadata.is_file() {
+                files.push(Self::stored_file(None, &entry, &metadata)?);
+            } else if metadata.is_dir() {
+                let container = entry.file_name().to_string_lossy().to_string();
+                for shard in std::fs::read_dir(entry.path())? {
+                    let shard = shard?;
+                    if !shard.metadata()?.is_dir() {
+                        continue;
+                    }
+                    for file in std::fs::read_dir(shard.path())? {
+                        let file = file?;
+                        let metadata = file.metadata()?;
+                        if metadata.is_file() {
+                            files.push(Self::stored_file(Some(container.clone()), &file, &metadata)?);
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(files)
+    }
+
+    /// List only the legacy files in the base directory.
+    pub fn list_legacy_files(&self) -> Result<Vec<StoredFile>> {
+        let base_path = Self::get_photos_base_path()?;
+        let mut files = vec![];
+
+        for entry in std::fs::read_dir(base_path)? {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            if metadata.is_file() && entry.file_name() != PROBE_FILE_NAME {
+                files.push(Self::stored_file(None, &entry, &metadata)?);
+            }
+        }
+
+        Ok(files)
+    }
+
+    /// Copy a legacy file, stored directly in the base directory, into given container.
+    /// Fails if the container already contains a file with the same name.
+    pub fn copy_legacy_file(&self, container: &str, name: &str) -> Result<()> {
+        Self::validate_name(name)?;
+        let legacy_path = Path::new(Self::get_photos_base_path()?).join(name);
+        let absolute_path = Self::get_absolute_file_path(container, name)?;
+        if absolute_path.exists() {
+            return Err(anyhow!("File {} already exists", absolute_path.display()));
+        }
+        if let Some(directory) = absolute_path.parent() {
+            std::fs::create_dir_all(directory)?;
+        }
+
+        std::fs::copy(legacy_path, absolute_path)?;
+        Ok(())
+    }
+
+    pub fn delete_legacy_file(&self, name: &str) -> Result<()> {
+        Self::validate_name(name)?;
+        let legacy_path = Path::new(Self::get_photos_base_path()?).join(name);
+        std::fs::remove_file(legacy_path)?;
+        Ok(())
+    }
+
+    fn stored_file(
+        owner_user_id: Option<String>,
+        entry: &std::fs::DirEntry,
+        metadata: &std::fs::Metadata,
+    ) -> Result<StoredFile> {
+        Ok(StoredFile {
+            owner_user_id,
+            file_id: entry.file_name().to_string_lossy().to_string(),
+            size: metadata.len(),
+            modified_on: metadata.modified()?.duration_since(UNIX_EPOCH)?.as_secs() as i64,
+        })
+    }
+
+    /// Returns the absolute path of a file in a container
+    fn get_absolute_file_path(container: &str, name: &str) -> Result<PathBuf> {
+        Self::validate_name(container)?;
+        Self::validate_name(name)?;
+
+        let mut hasher = Sha256::new();
+        hasher.update(name.as_bytes());
+        let shard = format!("{:02x}", hasher.finalize()[0]);
+
         let base_path = Self::get_photos_base_path()?;
-        let path = Path::new(base_path);
-        let path = path.join(photo_relative_path);
+        Ok(Path::new(base_path).join(container).join(shard).join(name))
+    }
 
-        Ok(path
-            .to_str()
-            .ok_or_else(|| anyhow!("Empty directory path"))?
-            .to_string())
+    /// Container and file names are used as path segments, so they must not be able to point elsewhere.
+    fn validate_name(name: &str) -> Result<()> {
+        if name.is_empty() || name.contains("..") || name.contains(['/', '\\', '\0']) {
+            Err(anyhow!("Invalid name '{name}'"))
+        } else {
+            Ok(())
+        }
     }
 
     /// Returns the absolute path to photo storage base directory
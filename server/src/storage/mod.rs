@@ -1,6 +1,9 @@
+use crate::database;
+use crate::metrics;
 use crate::model::User;
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use lazy_static::lazy_static;
+use std::fmt;
 
 mod azure_storage;
 mod local_disk;
@@ -12,11 +15,48 @@ lazy_static! {
     };
 }
 
+/// A file as found in storage.
+#[derive(Clone)]
+pub struct StoredFile {
+    /// ID of the user owning the file. Unknown for legacy files of the disk storage provider,
+    /// which were not stored per user.
+    pub owner_user_id: Option<String>,
+    pub file_id: String,
+    /// Size in bytes.
+    pub size: u64,
+    /// Unix timestamp of when the file was last modified.
+    pub modified_on: i64,
+}
+
+/// Error returned when storing a file would take its owner over their storage quota.
+#[derive(Debug)]
+pub struct QuotaExceededError {
+    pub quota_bytes: u64,
+}
+
+impl fmt::Display for QuotaExceededError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Storage quota of {} bytes exceeded", self.quota_bytes)
+    }
+}
+
+impl std::error::Error for QuotaExceededError {}
+
 enum StorageProvider {
     Disk(local_disk::LocalDiskStorageProvider),
     Azure(azure_storage::AzureStorageProvider),
 }
 
+impl StorageProvider {
+    /// Name of the provider, as used in metrics.
+    fn name(&self) -> &'static str {
+        match self {
+            StorageProvider::Disk(_) => "disk",
+            StorageProvider::Azure(_) => "azure",
+        }
+    }
+}
+
 /// Get storage provider
 fn get_provider<'a>() -> &'a StorageProvider {
     &STORAGE_PROVIDER
@@ -30,33 +70,170 @@ pub async fn init_storage_for_user(user: &User) -> Result<()> {
     }
 }
 
-/// Store a file
-/// Returns a unique id for the file
-pub async fn store_file(file_id: &str, owner_user_id: &str, file_bytes: &[u8]) -> Result<()> {
-    // Store bytes
+/// Check that files can be written to storage.
+pub async fn check_writable() -> Result<()> {
     match get_provider() {
-        StorageProvider::Disk(disk) => disk.store_file(file_id, file_bytes),
-        StorageProvider::Azure(azure) => azure.store_file(owner_user_id, file_id, file_bytes).await,
+        StorageProvider::Disk(disk) => disk.check_writable(),
+        StorageProvider::Azure(azure) => azure.check_writable().await,
+    }
+}
+
+/// Delete stored files of a user without updating their usage, for when the user is being deleted.
+/// Files that do not exist are skipped.
+pub async fn purge_files(file_ids: &[String], owner_user_id: &str) -> Result<()> {
+    for file_id in file_ids {
+        if get_file_size(file_id, owner_user_id).await?.is_some() {
+            match get_provider() {
+                StorageProvider::Disk(disk) => disk.delete_file(owner_user_id, file_id),
+                StorageProvider::Azure(azure) => azure.delete_file(owner_user_id, file_id).await,
+            }?;
+        }
     }
+
+    Ok(())
+}
+
+/// Remove all storage of a user, including all files still in it.
+pub async fn delete_storage_for_user(user_id: &str) -> Result<()> {
+    match get_provider() {
+        StorageProvider::Disk(disk) => disk.delete_container_if_exists(user_id),
+        StorageProvider::Azure(azure) => azure.delete_container_if_exists(user_id).await,
+    }
+}
+
+/// Store a file.
+/// Fails with a `QuotaExceededError` if the file would take the owner over their storage quota.
+pub async fn store_file(file_id: &str, owner_user_id: &str, file_bytes: &[u8]) -> Result<()> {
+    let existing_size = get_file_size(file_id, owner_user_id).await?.unwrap_or(0);
+    let size_delta = file_bytes.len() as i64 - existing_size as i64;
+
+    if size_delta > 0 {
+        let owner = database::get_user_by_id(owner_user_id)
+            .await?
+            .ok_or_else(|| anyhow!("User '{owner_user_id}' not found"))?;
+        if let Some(quota_bytes) = get_quota(&owner) {
+            // Reserve the space before writing, so concurrent uploads cannot exceed the quota together
+            if !database::try_add_user_bytes_stored(owner_user_id, size_delta, quota_bytes).await? {
+                return Err(QuotaExceededError { quota_bytes }.into());
+            }
+            if let Err(error) = write_file(file_id, owner_user_id, file_bytes).await {
+                database::add_user_bytes_stored(owner_user_id, -size_delta).await?;
+                return Err(error);
+            }
+            return Ok(());
+        }
+    }
+
+    write_file(file_id, owner_user_id, file_bytes).await?;
+    database::add_user_bytes_stored(owner_user_id, size_delta).await
+}
+
+/// Store a file without checking the owner's quota or updating their usage,
+/// for files whose size is already accounted for, such as those restored from a backup.
+pub async fn import_file(file_id: &str, owner_user_id: &str, file_bytes: &[u8]) -> Result<()> {
+    write_file(file_id, owner_user_id, file_bytes).await
+}
+
+async fn write_file(file_id: &str, owner_user_id: &str, file_bytes: &[u8]) -> Result<()> {
+    let provider = get_provider();
+    let _timer = metrics::time_storage_operation(provider.name(), "write");
+
+    match provider {
+        StorageProvider::Disk(disk) => disk.store_file(owner_user_id, file_id, file_bytes),
+        StorageProvider::Azure(azure) => azure.store_file(owner_user_id, file_id, file_bytes).await,
+    }?;
+
+    metrics::add_storage_bytes_in(provider.name(), file_bytes.len());
+    Ok(())
 }
 
 /// Retreive file contents
 pub async fn get_file(file_id: &str, owner_user_id: &str) -> Result<Option<Vec<u8>>> {
-    let bytes = match get_provider() {
-        StorageProvider::Disk(disk) => disk.get_file(file_id),
+    let provider = get_provider();
+    let _timer = metrics::time_storage_operation(provider.name(), "read");
+
+    let bytes = match provider {
+        StorageProvider::Disk(disk) => disk.get_file(owner_user_id, file_id),
         StorageProvider::Azure(azure) => azure.get_file(owner_user_id, file_id).await,
     }?;
 
     match bytes {
-        Some(bytes) => Ok(Some(bytes)),
+        Some(bytes) => {
+            metrics::add_storage_bytes_out(provider.name(), bytes.len());
+            Ok(Some(bytes))
+        }
         None => Ok(None),
     }
 }
 
+/// Get the size in bytes of a file, or None if it does not exist.
+pub async fn get_file_size(file_id: &str, owner_user_id: &str) -> Result<Option<u64>> {
+    let provider = get_provider();
+    let _timer = metrics::time_storage_operation(provider.name(), "stat");
+
+    match provider {
+        StorageProvider::Disk(disk) => disk.get_file_size(owner_user_id, file_id),
+        StorageProvider::Azure(azure) => azure.get_file_size(owner_user_id, file_id).await,
+    }
+}
+
 /// Delete a file
 pub async fn delete_file(file_id: &str, owner_user_id: &str) -> Result<()> {
-    match get_provider() {
-        StorageProvider::Disk(disk) => disk.delete_file(file_id),
+    let size = get_file_size(file_id, owner_user_id).await?.unwrap_or(0);
+
+    let provider = get_provider();
+    let timer = metrics::time_storage_operation(provider.name(), "delete");
+    match provider {
+        StorageProvider::Disk(disk) => disk.delete_file(owner_user_id, file_id),
         StorageProvider::Azure(azure) => azure.delete_file(owner_user_id, file_id).await,
+    }?;
+    drop(timer);
+
+    database::add_user_bytes_stored(owner_user_id, -(size as i64)).await
+}
+
+/// List all files in storage.
+pub async fn list_files() -> Result<Vec<StoredFile>> {
+    match get_provider() {
+        StorageProvider::Disk(disk) => disk.list_files(),
+        StorageProvider::Azure(azure) => {
+            let user_ids = database::get_users().await?.into_iter().map(|user| user.id).collect();
+            azure.list_files(&user_ids).await
+        }
     }
 }
+
+/// Copy a file stored in the flat directory layout used by earlier versions of the disk storage provider
+/// into the directory of an owner. Such files are listed by `list_files` without an owner.
+pub fn copy_legacy_file(file_id: &str, owner_user_id: &str) -> Result<()> {
+    match get_provider() {
+        StorageProvider::Disk(disk) => disk.copy_legacy_file(owner_user_id, file_id),
+        StorageProvider::Azure(_) => Err(anyhow!("Only the disk storage provider has legacy files")),
+    }
+}
+
+/// Delete a legacy file of the disk storage provider.
+pub fn delete_legacy_file(file_id: &str) -> Result<()> {
+    match get_provider() {
+        StorageProvider::Disk(disk) => disk.delete_legacy_file(file_id),
+        StorageProvider::Azure(_) => Err(anyhow!("Only the disk storage provider has legacy files")),
+    }
+}
+
+/// List legacy files of the disk storage provider, stored before files were kept per user.
+pub fn list_legacy_files() -> Result<Vec<StoredFile>> {
+    match get_provider() {
+        StorageProvider::Disk(disk) => disk.list_legacy_files(),
+        StorageProvider::Azure(_) => Ok(vec![]),
+    }
+}
+
+/// Get the storage quota in bytes that applies to given user, if any.
+pub fn get_quota(user: &User) -> Option<u64> {
+    let default_quota_bytes = match crate::SETTINGS.storage.default_quota_bytes {
+        0 => None,
+        quota_bytes => Some(quota_bytes),
+    };
+
+    user.quota_bytes.or(default_quota_bytes)
+}
@@ -0,0 +1,101 @@
+use crate::SETTINGS;
+use anyhow::Result;
+use axum::{
+    extract::Host,
+    http::{uri::Scheme, StatusCode, Uri},
+    response::{IntoResponse, Redirect, Response},
+    Router,
+};
+use axum_server::tls_rustls::RustlsConfig;
+use std::{net::SocketAddr, path::Path, time::Duration, time::SystemTime};
+
+/// Interval at which the certificate and key files are checked for changes.
+const RELOAD_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Whether HTTPS is configured.
+pub fn is_enabled() -> bool {
+    !SETTINGS.server.tls_certificate_path.is_empty()
+}
+
+/// Load the configured certificate and key, and reload them in the background whenever either file changes,
+/// so that renewed certificates are used without a restart.
+pub async fn load_config() -> Result<RustlsConfig> {
+    let certificate_path = &SETTINGS.server.tls_certificate_path;
+    let key_path = &SETTINGS.server.tls_key_path;
+    let config = RustlsConfig::from_pem_file(certificate_path, key_path).await?;
+
+    let reloaded_config = config.clone();
+    tokio::spawn(async move {
+        let mut last_modified = get_last_modified(certificate_path, key_path);
+        let mut interval = tokio::time::interval(RELOAD_CHECK_INTERVAL);
+
+        loop {
+            interval.tick().await;
+            let modified = get_last_modified(certificate_path, key_path);
+            if modified == last_modified {
+                continue;
+            }
+
+            match reloaded_config.reload_from_pem_file(certificate_path, key_path).await {
+                Ok(_) => {
+                    tracing::info!("TLS certificate reloaded");
+                    last_modified = modified;
+                }
+                // The files may be halfway through being replaced; try again at the next check.
+                Err(error) => tracing::warn!("Failed to reload TLS certificate: {error:?}"),
+            }
+        }
+    });
+
+    Ok(config)
+}
+
+fn get_last_modified(certificate_path: &str, key_path: &str) -> [Option<SystemTime>; 2] {
+    [certificate_path, key_path].map(|path| Path::new(path).metadata().and_then(|metadata| metadata.modified()).ok())
+}
+
+/// Listen for plain HTTP on the configured redirect address, if any, and redirect all requests to HTTPS.
+pub fn spawn_redirect_server(https_port: u16) -> Result<()> {
+    if SETTINGS.server.http_redirect_address.is_empty() {
+        return Ok(());
+    }
+
+    let addr: SocketAddr = SETTINGS.server.http_redirect_address.parse()?;
+    let app = Router::new().fallback(move |host: Host, uri: Uri| redirect_to_https(host, uri, https_port));
+    let server = axum::Server::try_bind(&addr)?.serve(app.into_make_service());
+
+    tracing::info!("redirecting HTTP on {addr} to HTTPS");
+    tokio::spawn(async move {
+        if let Err(error) = server.await {
+            tracing::error!("HTTP redirect server failed: {error:?}");
+        }
+    });
+
+    Ok(())
+}
+
+async fn redirect_to_https(Host(host): Host, uri: Uri, https_port: u16) -> Response {
+    let host = match host.rsplit_once(':') {
+        Some((hostname, port)) if port.chars().all(|c| c.is_ascii_digit()) => hostname.to_string(),
+        _ => host,
+    };
+    let authority = match https_port {
+        443 => host,
+        port => format!("{host}:{port}"),
+    };
+
+    let mut parts = uri.into_parts();
+    parts.scheme = Some(Scheme::HTTPS);
+    parts.authority = match authority.parse() {
+        Ok(authority) => Some(authority),
+        Err(_) => return StatusCode::BAD_REQUEST.into_response(),
+    };
+    if parts.path_and_query.is_none() {
+        parts.path_and_query = Some("/".parse().unwrap());
+    }
+
+    match Uri::from_parts(parts) {
+        Ok(uri) => Redirect::permanent(&uri.to_string()).into_response(),
+        Err(_) => StatusCode::BAD_REQUEST.into_response(),
+    }
+}
@@ -0,0 +1,104 @@
+use crate::database;
+use crate::model::{EncryptedData, File, TrashEntry};
+use crate::storage;
+use anyhow::Result;
+use cookie::time::OffsetDateTime;
+use std::time::Duration;
+
+/// Interval at which expired trash entries are deleted.
+const PURGE_INTERVAL_SECONDS: u64 = 60 * 60;
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// Get the Unix timestamp after which a trash entry is deleted, or None if entries are kept until the trash is emptied.
+pub fn expires_on(entry: &TrashEntry) -> Option<i64> {
+    match crate::SETTINGS.storage.trash_retention_days {
+        0 => None,
+        days => Some(entry.trashed_on + days as i64 * SECONDS_PER_DAY),
+    }
+}
+
+/// Get the Unix timestamp before which trash entries have expired.
+fn expired_before(now: i64, retention_days: u64) -> i64 {
+    now - retention_days as i64 * SECONDS_PER_DAY
+}
+
+/// Delete the items and files of trash entries, and the entries themselves.
+pub async fn delete_entries(entries: &[TrashEntry]) -> Result<()> {
+    for entry in entries {
+        delete_entry(entry).await?;
+    }
+
+    Ok(())
+}
+
+/// Delete the items and files of a trash entry, and the entry itself.
+/// Every step can be repeated, so an interrupted deletion can simply be run again.
+async fn delete_entry(entry: &TrashEntry) -> Result<()> {
+    database::delete_items::<EncryptedData>(&entry.item_ids, &entry.user_id).await?;
+    for file_id in &entry.file_ids {
+        if storage::get_file_size(file_id, &entry.user_id).await?.is_some() {
+            storage::delete_file(file_id, &entry.user_id).await?;
+        }
+    }
+    database::delete_items::<File>(&entry.file_ids, &entry.user_id).await?;
+    database::delete_trash_entries(&entry.user_id, std::slice::from_ref(&entry.id)).await?;
+
+    Ok(())
+}
+
+/// Delete expired trash entries of all users periodically in the background, unless entries never expire.
+pub fn spawn_scheduled_purge() {
+    let retention_days = crate::SETTINGS.storage.trash_retention_days;
+
+    if retention_days > 0 {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(PURGE_INTERVAL_SECONDS));
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+            loop {
+                interval.tick().await;
+                match purge_expired_entries(retention_days).await {
+                    Ok(0) => {}
+                    Ok(deleted) => tracing::info!("{deleted} expired trash entries deleted"),
+                    Err(error) => tracing::error!("Deleting expired trash entries failed: {error:?}"),
+                }
+            }
+        });
+    }
+}
+
+/// Delete expired trash entries and return how many were deleted.
+/// An entry that cannot be deleted is logged and skipped, so it does not hold up the entries after it.
+async fn purge_expired_entries(retention_days: u64) -> Result<usize> {
+    let trashed_before = expired_before(OffsetDateTime::now_utc().unix_timestamp(), retention_days);
+    let entries = database::get_trash_entries_before(trashed_before).await?;
+
+    let mut deleted = 0;
+    for entry in &entries {
+        match delete_entry(entry).await {
+            Ok(()) => deleted += 1,
+            Err(error) => tracing::error!(
+                "Deleting expired trash entry '{}' of user '{}' failed: {error:?}",
+                entry.id,
+                entry.user_id
+            ),
+        }
+    }
+
+    Ok(deleted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries_expire_after_retention_period() {
+        let now = 1_700_000_000;
+        let trashed_before = expired_before(now, 30);
+
+        assert_eq!(trashed_before, now - 30 * SECONDS_PER_DAY);
+        assert!(now - 31 * SECONDS_PER_DAY < trashed_before);
+        assert!(now - 29 * SECONDS_PER_DAY >= trashed_before);
+    }
+}